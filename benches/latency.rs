@@ -0,0 +1,51 @@
+// 前のタスクの結果を待って次のタスクが動く、依存関係の連なりにかかる時間を測る
+// Task::pollなどのデバッグ出力が多いので、stderrは捨てて実行する:
+//   cargo bench --bench latency 2>/dev/null
+use std::time::{Duration, Instant};
+
+use async_runtime::Engine;
+use async_runtime::engine::block_on;
+use async_runtime::engine::schedule::fifo::Fifo;
+use async_runtime::utils::mpsc;
+
+const WORKERS: [usize; 3] = [1, 2, 4];
+const ROUNDS: usize = 5;
+const CHAIN: usize = 1_000;
+
+// CHAIN個のタスクをチャネルでつなぎ、先頭に送った値が末尾に届くまでの時間
+fn chain(engine: &Engine) -> Duration {
+    let (first, mut receiver) = mpsc::channel::<usize>();
+    for _ in 0..CHAIN {
+        let (sender, next) = mpsc::channel();
+        let mut prev = receiver;
+        engine.spawn(async move {
+            if let Some(value) = prev.recv().await {
+                let _ = sender.send(value + 1);
+            }
+        });
+        receiver = next;
+    }
+
+    let started = Instant::now();
+    first.send(0).unwrap();
+    let last = block_on(receiver.recv());
+    let elapsed = started.elapsed();
+    assert_eq!(last, Some(CHAIN));
+    elapsed
+}
+
+fn main() {
+    println!("chain ({CHAIN} dependent tasks)");
+    for workers in WORKERS {
+        let engine = Engine::new(workers, Fifo::new());
+        chain(&engine);
+
+        let best = (0..ROUNDS).map(|_| chain(&engine)).min().unwrap();
+        engine.graceful_shutdown();
+
+        println!(
+            "  workers={workers:<2} total={best:>10.3?} per hop={:>8.3?}",
+            best / CHAIN as u32
+        );
+    }
+}
@@ -0,0 +1,69 @@
+// Worker数を1からNまで増やしたときのスループットを測る
+// Task::pollなどのデバッグ出力が多いので、stderrは捨てて実行する:
+//   cargo bench --bench scaling 2>/dev/null
+use std::time::{Duration, Instant};
+
+use async_runtime::Engine;
+use async_runtime::engine::schedule::fifo::Fifo;
+use async_runtime::engine::{block_on, yield_now};
+
+const WORKERS: [usize; 4] = [1, 2, 4, 8];
+const ROUNDS: usize = 5;
+
+// 外から大量のタスクをspawnする（injectorへのpushが中心）
+fn spawn_many(engine: &Engine) {
+    const TASKS: usize = 10_000;
+    let handles: Vec<_> = (0..TASKS).map(|i| engine.spawn(async move { i })).collect();
+    block_on(async {
+        for handle in handles {
+            handle.await.unwrap();
+        }
+    });
+}
+
+// 各タスクが何度もyieldする（wakeからの再スケジュールが中心）
+fn yield_many(engine: &Engine) {
+    const TASKS: usize = 1_000;
+    const YIELDS: usize = 20;
+    let handles: Vec<_> = (0..TASKS)
+        .map(|_| {
+            engine.spawn(async {
+                for _ in 0..YIELDS {
+                    yield_now().await;
+                }
+            })
+        })
+        .collect();
+    block_on(async {
+        for handle in handles {
+            handle.await.unwrap();
+        }
+    });
+}
+
+fn run(name: &str, ops: usize, bench: fn(&Engine)) {
+    println!("{name}");
+    for workers in WORKERS {
+        let engine = Engine::new(workers, Fifo::new());
+        // 1回目はスレッドの起動などを含むので捨てる
+        bench(&engine);
+
+        let mut best = Duration::MAX;
+        for _ in 0..ROUNDS {
+            let started = Instant::now();
+            bench(&engine);
+            best = best.min(started.elapsed());
+        }
+        engine.graceful_shutdown();
+
+        println!(
+            "  workers={workers:<2} best={best:>10.3?} {:>12.0} ops/s",
+            ops as f64 / best.as_secs_f64()
+        );
+    }
+}
+
+fn main() {
+    run("spawn_many (10000 tasks)", 10_000, spawn_many);
+    run("yield_many (1000 tasks x 20 yields)", 20_000, yield_many);
+}
@@ -0,0 +1,21 @@
+use async_runtime::engine::spawn;
+
+#[async_runtime::main(worker_threads = 4)]
+async fn main() {
+    println!("=== Attribute Main Example ===\n");
+
+    let handles: Vec<_> = (1..=5)
+        .map(|i| {
+            spawn(async move {
+                println!("  [Task {i}] Computing {i} * {i}");
+                i * i
+            })
+        })
+        .collect();
+
+    let mut total = 0;
+    for handle in handles {
+        total += handle.await.unwrap();
+    }
+    println!("\nSum of squares: {total}");
+}
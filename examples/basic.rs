@@ -6,7 +6,7 @@ fn main() {
     println!("=== Basic Async Runtime Example ===\n");
 
     // Create engine with FIFO scheduler and 4 workers
-    let mut engine = Engine::new(4, |receiver| Box::new(Fifo::new(receiver)));
+    let mut engine = Engine::new(4, Fifo::new());
 
     println!("Created engine with 4 workers\n");
 
@@ -46,9 +46,9 @@ fn main() {
 
     // Collect results
     println!("\nCollecting results:");
-    println!("  Task 1 result: {}", block_on(r1));
-    println!("  Task 2 result: {}", block_on(r2));
-    println!("  Task 3 result: {}", block_on(r3));
+    println!("  Task 1 result: {}", block_on(r1).unwrap());
+    println!("  Task 2 result: {}", block_on(r2).unwrap());
+    println!("  Task 3 result: {}", block_on(r3).unwrap());
 
     println!("\nShutting down engine...");
     engine.graceful_shutdown();
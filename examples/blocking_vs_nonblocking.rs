@@ -61,7 +61,7 @@ fn main() {
     println!("--- BAD: Blocking sleep (blocks worker thread) ---");
     let start = Instant::now();
     {
-        let mut engine = Engine::new(2, |receiver| Box::new(Fifo::new(receiver)));
+        let mut engine = Engine::new(2, Fifo::new());
 
         println!("Submitting 3 tasks that sleep 100ms each (BLOCKING)");
         let r1 = engine.reserve(
@@ -94,9 +94,9 @@ fn main() {
             None,
         );
 
-        block_on(r1);
-        block_on(r2);
-        block_on(r3);
+        block_on(r1).unwrap();
+        block_on(r2).unwrap();
+        block_on(r3).unwrap();
 
         engine.graceful_shutdown();
     }
@@ -108,7 +108,7 @@ fn main() {
     println!("--- GOOD: Non-blocking sleep (uses Waker) ---");
     let start = Instant::now();
     {
-        let mut engine = Engine::new(2, |receiver| Box::new(Fifo::new(receiver)));
+        let mut engine = Engine::new(2, Fifo::new());
 
         println!("Submitting 3 tasks that sleep 100ms each (NON-BLOCKING)");
         let r1 = engine.reserve(
@@ -141,9 +141,9 @@ fn main() {
             None,
         );
 
-        block_on(r1);
-        block_on(r2);
-        block_on(r3);
+        block_on(r1).unwrap();
+        block_on(r2).unwrap();
+        block_on(r3).unwrap();
 
         engine.graceful_shutdown();
     }
@@ -7,7 +7,7 @@ fn main() {
     println!("=== Complex Task Dependencies Example ===\n");
     println!("Simulating a web service with multiple dependent API calls\n");
 
-    let mut engine = Engine::new(4, |receiver| Box::new(Fifo::new(receiver)));
+    let mut engine = Engine::new(4, Fifo::new());
 
     // Simulate fetching user data
     println!("[1] Fetching user profile...");
@@ -25,7 +25,7 @@ fn main() {
     println!("[2] Fetching friends list...");
     let friends_list = engine.reserve(
         async move {
-            let (username, _user_id) = block_on(user_profile);
+            let (username, _user_id) = block_on(user_profile).unwrap();
             println!("  -> Friends API called for user: {}", username);
             std::thread::sleep(Duration::from_millis(150));
             vec![
@@ -52,8 +52,8 @@ fn main() {
     println!("[4] Processing aggregated data...");
     let aggregated = engine.reserve(
         async move {
-            let friends = block_on(friends_list);
-            let posts = block_on(user_posts);
+            let friends = block_on(friends_list).unwrap();
+            let posts = block_on(user_posts).unwrap();
 
             println!(
                 "  -> Aggregating {} friends and {} posts",
@@ -85,8 +85,8 @@ fn main() {
     println!("[6] Assembling dashboard...");
     let dashboard = engine.reserve(
         async move {
-            let (friend_count, post_count) = block_on(aggregated);
-            let notif_count = block_on(notifications);
+            let (friend_count, post_count) = block_on(aggregated).unwrap();
+            let notif_count = block_on(notifications).unwrap();
 
             println!("\n=== Dashboard Ready ===");
             println!("Friends: {}", friend_count);
@@ -100,7 +100,7 @@ fn main() {
     );
 
     println!("\nWaiting for dashboard to be ready...\n");
-    let result = block_on(dashboard);
+    let result = block_on(dashboard).unwrap();
 
     println!("Dashboard loaded successfully!");
     println!(
@@ -8,7 +8,7 @@ fn main() {
     println!("This example demonstrates the deadline scheduler.");
     println!("Tasks are executed in order of their deadlines (smallest first).\n");
 
-    let mut engine = Engine::new(2, |receiver| Box::new(DeadLineScheduler::new(receiver)));
+    let mut engine = Engine::new(2, DeadLineScheduler::new());
 
     println!("Submitting tasks with different deadlines:");
 
@@ -34,10 +34,10 @@ fn main() {
 
     println!("\nWaiting for results...\n");
 
-    let r1 = block_on(task1);
-    let r2 = block_on(task2);
-    let r3 = block_on(task3);
-    let r4 = block_on(task4);
+    let r1 = block_on(task1).unwrap();
+    let r2 = block_on(task2).unwrap();
+    let r3 = block_on(task3).unwrap();
+    let r4 = block_on(task4).unwrap();
 
     println!("\n=== Results ===");
     println!("Task 1 (deadline=100): {}", r1);
@@ -5,7 +5,7 @@ use async_runtime::engine::schedule::fifo::Fifo;
 fn main() {
     println!("=== Different Return Types Example ===\n");
 
-    let mut engine = Engine::new(4, |receiver| Box::new(Fifo::new(receiver)));
+    let mut engine = Engine::new(4, Fifo::new());
 
     println!("Testing different return types:\n");
 
@@ -23,7 +23,7 @@ fn main() {
 
     // Float
     println!("4. Float task");
-    let r_float = engine.reserve(async { 3.14159 }, None);
+    let r_float = engine.reserve(async { std::f64::consts::PI }, None);
 
     // Tuple
     println!("5. Tuple task");
@@ -34,12 +34,12 @@ fn main() {
     let r_vec = engine.reserve(async { vec![1, 2, 3, 4, 5] }, None);
 
     println!("\nResults:");
-    println!("  Integer: {}", block_on(r_int));
-    println!("  String: {}", block_on(r_string));
-    println!("  Boolean: {}", block_on(r_bool));
-    println!("  Float: {}", block_on(r_float));
-    println!("  Tuple: {:?}", block_on(r_tuple));
-    println!("  Vector: {:?}", block_on(r_vec));
+    println!("  Integer: {}", block_on(r_int).unwrap());
+    println!("  String: {}", block_on(r_string).unwrap());
+    println!("  Boolean: {}", block_on(r_bool).unwrap());
+    println!("  Float: {}", block_on(r_float).unwrap());
+    println!("  Tuple: {:?}", block_on(r_tuple).unwrap());
+    println!("  Vector: {:?}", block_on(r_vec).unwrap());
 
     println!("\nShutting down engine...");
     engine.graceful_shutdown();
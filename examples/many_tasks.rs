@@ -5,7 +5,7 @@ use async_runtime::engine::schedule::fifo::Fifo;
 fn main() {
     println!("=== Many Tasks Example ===\n");
 
-    let mut engine = Engine::new(8, |receiver| Box::new(Fifo::new(receiver)));
+    let mut engine = Engine::new(8, Fifo::new());
 
     println!("Created engine with 8 workers");
     println!("Spawning 100 tasks...\n");
@@ -30,7 +30,7 @@ fn main() {
     println!("\nWaiting for all tasks to complete...\n");
 
     for (i, receiver) in receivers {
-        let result = block_on(receiver);
+        let result = block_on(receiver).unwrap();
         assert_eq!(result, i * i + 85, "Task {} produced wrong result", i);
     }
 
@@ -6,7 +6,7 @@ fn main() {
     println!("=== Nested Awaits Example ===\n");
     println!("Testing deeply nested async/await chains\n");
 
-    let mut engine = Engine::new(4, |receiver| Box::new(Fifo::new(receiver)));
+    let mut engine = Engine::new(4, Fifo::new());
 
     // Level 1: Simple async task
     let level1 = engine.reserve(async {
@@ -17,7 +17,7 @@ fn main() {
     // Level 2: Await level 1
     let level2 = engine.reserve(async move {
         println!("[Level 2] Starting...");
-        let result1 = block_on(level1);
+        let result1 = block_on(level1).unwrap();
         println!("[Level 2] Got from level 1: {}", result1);
         result1 + 10
     }, None);
@@ -25,7 +25,7 @@ fn main() {
     // Level 3: Await level 2
     let level3 = engine.reserve(async move {
         println!("[Level 3] Starting...");
-        let result2 = block_on(level2);
+        let result2 = block_on(level2).unwrap();
         println!("[Level 3] Got from level 2: {}", result2);
         result2 * 2
     }, None);
@@ -33,7 +33,7 @@ fn main() {
     // Level 4: Await level 3
     let level4 = engine.reserve(async move {
         println!("[Level 4] Starting...");
-        let result3 = block_on(level3);
+        let result3 = block_on(level3).unwrap();
         println!("[Level 4] Got from level 3: {}", result3);
         result3 + 100
     }, None);
@@ -55,7 +55,7 @@ fn main() {
         let c = step3.await;
         println!("[Level 5] Step 3: {}", c);
 
-        let result4 = block_on(level4);
+        let result4 = block_on(level4).unwrap();
         println!("[Level 5] Got from level 4: {}", result4);
 
         result4 + a + b + c
@@ -97,13 +97,13 @@ fn main() {
     let final_result = engine.reserve(async move {
         println!("[Final] Starting...");
 
-        let main_result = block_on(level5);
+        let main_result = block_on(level5).unwrap();
         println!("[Final] Main chain result: {}", main_result);
 
-        let b1 = block_on(branch1);
+        let b1 = block_on(branch1).unwrap();
         println!("[Final] Branch 1 result: {}", b1);
 
-        let b2 = block_on(branch2);
+        let b2 = block_on(branch2).unwrap();
         println!("[Final] Branch 2 result: {}", b2);
 
         // One more nested layer
@@ -120,12 +120,11 @@ fn main() {
     }, None);
 
     println!("\nWaiting for final result...\n");
-    let result = block_on(final_result);
+    let result = block_on(final_result).unwrap();
 
     println!("\n=== Results ===");
     println!("Final result: {}", result);
-    println!("Expected: {} (42 + 10) * 2 + 100 + 5 + 10 + 15 + 6 + 60 + 15 = {}",
-             "(42 + 10) * 2 + 100 + 5 + 10 + 15",
+    println!("Expected: (42 + 10) * 2 + 100 + 5 + 10 + 15 + 6 + 60 + 15 = {}",
              (42 + 10) * 2 + 100 + 5 + 10 + 15 + 6 + 60 + 15);
 
     assert_eq!(result, 315, "Result mismatch!");
@@ -6,7 +6,7 @@ fn main() {
     println!("=== Nested Task Waker Example ===\n");
     println!("This demonstrates that a task's Waker is called when it awaits another task\n");
 
-    let mut engine = Engine::new(2, |receiver| Box::new(Fifo::new(receiver)));
+    let mut engine = Engine::new(2, Fifo::new());
 
     println!("Creating inner task...");
     let inner_task = engine.reserve(async {
@@ -22,7 +22,7 @@ fn main() {
         println!("  [Outer task] Starting, will await inner task");
         println!("  [Outer task] Calling inner_task.await (will return Pending first)...");
 
-        let result = inner_task.await;
+        let result = inner_task.await.unwrap();
 
         println!("  [Outer task] Inner task completed with result: {}", result);
         println!("  [Outer task] Waker was called to wake us up!");
@@ -30,7 +30,7 @@ fn main() {
     }, None);
 
     println!("\nMain thread: Waiting for outer task...");
-    let final_result = block_on(outer_task);
+    let final_result = block_on(outer_task).unwrap();
 
     println!("\nFinal result: {}", final_result);
     println!("\nLook for 'called waker' and 'task reshceduled!!' in the output!");
@@ -71,7 +71,7 @@ impl Future for CountingFuture {
 fn main() {
     println!("=== Pending Future Example ===\n");
 
-    let mut engine = Engine::new(2, |receiver| Box::new(Fifo::new(receiver)));
+    let mut engine = Engine::new(2, Fifo::new());
 
     println!("Submitting task 1: CountingFuture(3 polls, value=100)");
     let r1 = engine.reserve(CountingFuture::new(3, 100), None);
@@ -84,13 +84,13 @@ fn main() {
 
     println!("\n--- Waiting for results ---\n");
 
-    let result1 = block_on(r1);
+    let result1 = block_on(r1).unwrap();
     println!("Task 1 result: {}", result1);
 
-    let result2 = block_on(r2);
+    let result2 = block_on(r2).unwrap();
     println!("Task 2 result: {}", result2);
 
-    let result3 = block_on(r3);
+    let result3 = block_on(r3).unwrap();
     println!("Task 3 result: {}", result3);
 
     println!("\n=== All tasks completed successfully! ===");
@@ -0,0 +1,56 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_runtime::{
+    Engine,
+    engine::{
+        block_on,
+        schedule::priority::{Priority, PriorityScheduler},
+    },
+};
+
+fn main() {
+    println!("=== Priority Scheduler Example ===\n");
+    println!("Tasks are executed in priority order (Critical first).");
+    println!("Tasks waiting longer than the aging period are promoted one level.\n");
+
+    let mut engine = Engine::new(
+        1,
+        PriorityScheduler::new().with_aging(Some(Duration::from_millis(50))),
+    );
+
+    let order = Arc::new(Mutex::new(Vec::new()));
+
+    // Workerを塞いでおき、その間に全てのタスクを登録する
+    let blocker = engine.reserve_with_priority(
+        async {
+            std::thread::sleep(Duration::from_millis(20));
+        },
+        Priority::Critical,
+    );
+    std::thread::sleep(Duration::from_millis(5));
+
+    let mut receivers = Vec::new();
+    for priority in [
+        Priority::Background,
+        Priority::Normal,
+        Priority::High,
+        Priority::Critical,
+    ] {
+        let order = order.clone();
+        receivers.push(engine.reserve_with_priority(
+            async move {
+                println!("  Running {:?} task", priority);
+                order.lock().unwrap().push(priority);
+            },
+            priority,
+        ));
+    }
+
+    block_on(blocker).unwrap();
+    receivers.into_iter().for_each(|receiver| block_on(receiver).unwrap());
+
+    println!("\nExecution order: {:?}", order.lock().unwrap());
+
+    engine.graceful_shutdown();
+}
@@ -9,7 +9,7 @@ fn main() {
     println!("=== Receiver Waker Example ===\n");
     println!("This demonstrates that sender.send() calls the receiver's waker\n");
 
-    let mut engine = Engine::new(2, |receiver| Box::new(Fifo::new(receiver)));
+    let mut engine = Engine::new(2, Fifo::new());
 
     println!("Submitting task that sleeps before returning...");
     let receiver = engine.reserve(async {
@@ -23,7 +23,7 @@ fn main() {
     println!("Main thread: calling block_on(receiver)...");
     println!("Main thread: receiver will return Pending first, then sender will wake it up\n");
 
-    let result = block_on(receiver);
+    let result = block_on(receiver).unwrap();
 
     println!("\nMain thread: Got result: {}", result);
     println!("\nLook for '[Sender] Calling waker to wake up receiver!' in the output above!");
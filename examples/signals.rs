@@ -0,0 +1,42 @@
+use std::time::Duration;
+
+use async_runtime::engine::{Engine, block_on, schedule::fifo::Fifo};
+use async_runtime::signal::{self, SignalKind};
+
+fn raise(kind: SignalKind) {
+    unsafe { libc::kill(libc::getpid(), kind.as_raw_value()) };
+}
+
+fn main() {
+    println!("=== Signal Handling Example ===\n");
+
+    let engine = Engine::new(2, Fifo::new());
+    let (ready_tx, ready_rx) = std::sync::mpsc::channel();
+
+    // SIGHUPで設定を読み直し、SIGTERMで止まる
+    let service = engine.spawn(async move {
+        let mut hangup = signal::unix(SignalKind::hangup()).unwrap();
+        let mut terminate = signal::unix(SignalKind::terminate()).unwrap();
+        ready_tx.send(()).unwrap();
+
+        let mut generation = 0;
+        hangup.recv().await;
+        generation += 1;
+        println!("  [service] SIGHUP received, reloaded config (generation {generation})");
+
+        terminate.recv().await;
+        println!("  [service] SIGTERM received, shutting down");
+        generation
+    });
+
+    ready_rx.recv().unwrap();
+    println!("Sending SIGHUP");
+    raise(SignalKind::hangup());
+    std::thread::sleep(Duration::from_millis(50));
+    println!("Sending SIGTERM");
+    raise(SignalKind::terminate());
+
+    let generation = block_on(service).unwrap();
+    println!("\nService stopped after {generation} reload(s)");
+    engine.shutdown(Duration::from_secs(1));
+}
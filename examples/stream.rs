@@ -0,0 +1,52 @@
+use std::time::Duration;
+
+use async_runtime::Engine;
+use async_runtime::engine::block_on;
+use async_runtime::engine::schedule::fifo::Fifo;
+use async_runtime::time::{interval, sleep};
+use async_runtime::utils::stream::{StreamExt, iter};
+
+fn main() {
+    println!("=== Stream Example ===\n");
+
+    let mut engine = Engine::new(2, Fifo::new());
+
+    // intervalのtickを3回分だけ数える
+    let ticks = engine.reserve(
+        async {
+            let mut ticks =
+                interval(Duration::from_millis(20)).take_until(sleep(Duration::from_millis(50)));
+            let mut count = 0;
+            while ticks.next().await.is_some() {
+                count += 1;
+                println!("  [ticks] tick {}", count);
+            }
+            count
+        },
+        None,
+    );
+
+    // 複数のsleepを同時に進め、終わった順に受け取る
+    let completed = engine.reserve(
+        async {
+            let mut stream = iter([30u64, 10, 20])
+                .map(|ms| async move {
+                    sleep(Duration::from_millis(ms)).await;
+                    ms
+                })
+                .buffer_unordered(3);
+            let mut order = Vec::new();
+            while let Some(ms) = stream.next().await {
+                println!("  [buffer_unordered] {}ms finished", ms);
+                order.push(ms);
+            }
+            order
+        },
+        None,
+    );
+
+    println!("Ticks observed: {}", block_on(ticks).unwrap());
+    println!("Completion order: {:?}", block_on(completed).unwrap());
+
+    engine.graceful_shutdown();
+}
@@ -0,0 +1,41 @@
+use std::fs::File;
+
+use async_runtime::engine::schedule::deadline::DeadLineScheduler;
+use async_runtime::engine::{Engine, Recorder, block_on, yield_now};
+
+fn main() -> std::io::Result<()> {
+    println!("=== Trace Export Example ===\n");
+
+    let recorder = Recorder::new();
+    let engine = Engine::builder()
+        .with_worker_threads(2)
+        .with_scheduler(DeadLineScheduler::new)
+        .with_recorder(recorder.clone())
+        .build()?;
+
+    let handles: Vec<_> = [300, 100, 200]
+        .into_iter()
+        .map(|deadline| {
+            engine
+                .task_builder()
+                .name(format!("deadline-{deadline}"))
+                .deadline(deadline)
+                .spawn(async move {
+                    for _ in 0..3 {
+                        yield_now().await;
+                    }
+                    deadline
+                })
+        })
+        .collect();
+    for handle in handles {
+        println!("  finished task with deadline={}", block_on(handle).unwrap());
+    }
+    engine.shutdown_now();
+
+    let path = std::env::temp_dir().join("async_runtime_trace.json");
+    recorder.write_chrome_trace(File::create(&path)?)?;
+    println!("\nRecorded {} events", recorder.events().len());
+    println!("Open {} in chrome://tracing or https://ui.perfetto.dev", path.display());
+    Ok(())
+}
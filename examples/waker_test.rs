@@ -75,7 +75,7 @@ fn main() {
     println!("=== Waker Test Example ===\n");
     println!("This example demonstrates async tasks that are woken by background threads\n");
 
-    let mut engine = Engine::new(2, |receiver| Box::new(Fifo::new(receiver)));
+    let mut engine = Engine::new(2, Fifo::new());
 
     println!("Submitting task 1: AsyncWakerFuture(100ms delay, value=100)");
     let r1 = engine.reserve(AsyncWakerFuture::new(100, 100), None);
@@ -88,13 +88,13 @@ fn main() {
 
     println!("\n--- Waiting for results ---\n");
 
-    let result3 = block_on(r3);
+    let result3 = block_on(r3).unwrap();
     println!("Task 3 result: {}", result3);
 
-    let result1 = block_on(r1);
+    let result1 = block_on(r1).unwrap();
     println!("Task 1 result: {}", result1);
 
-    let result2 = block_on(r2);
+    let result2 = block_on(r2).unwrap();
     println!("Task 2 result: {}", result2);
 
     println!("\n=== All tasks completed! ===");
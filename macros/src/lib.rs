@@ -0,0 +1,302 @@
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::parse::Parser;
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::{Expr, ExprLit, ItemFn, Lit, MetaNameValue, ReturnType, Token};
+
+// async fn mainをEngineの上で実行する
+//
+// #[async_runtime::main]
+// #[async_runtime::main(worker_threads = 4, scheduler = "priority")]
+// #[async_runtime::main(flavor = "simulation", seed = 42)]
+#[proc_macro_attribute]
+pub fn main(args: TokenStream, item: TokenStream) -> TokenStream {
+    expand(args, item, Kind::Main)
+}
+
+// async fnのテストをEngineの上で実行する。引数は#[main]と同じ
+// worker_threadsを省略したときは1つのWorkerで実行する
+#[proc_macro_attribute]
+pub fn test(args: TokenStream, item: TokenStream) -> TokenStream {
+    expand(args, item, Kind::Test)
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Kind {
+    Main,
+    Test,
+}
+
+impl Kind {
+    fn name(self) -> &'static str {
+        match self {
+            Kind::Main => "main",
+            Kind::Test => "test",
+        }
+    }
+}
+
+enum Flavor {
+    MultiThread {
+        worker_threads: Option<Expr>,
+        scheduler: TokenStream2,
+        start_paused: bool,
+    },
+    Simulation {
+        seed: Option<Expr>,
+    },
+}
+
+fn expand(args: TokenStream, item: TokenStream, kind: Kind) -> TokenStream {
+    let input = match syn::parse::<ItemFn>(item.clone()) {
+        Ok(input) => input,
+        Err(e) => return with_error(item, e),
+    };
+    let parsed = Punctuated::<MetaNameValue, Token![,]>::parse_terminated
+        .parse(args)
+        .and_then(|args| parse_flavor(args, kind))
+        .and_then(|flavor| check_signature(&input, kind).map(|()| flavor));
+    match parsed {
+        Ok(flavor) => build(input, flavor, kind).into(),
+        Err(e) => with_error(item, e),
+    }
+}
+
+// エラーと一緒に元の関数も出力し、関数が見つからないという余計なエラーを出さない
+fn with_error(item: TokenStream, e: syn::Error) -> TokenStream {
+    let mut tokens = TokenStream2::from(item);
+    tokens.extend(e.into_compile_error());
+    tokens.into()
+}
+
+fn parse_flavor(args: Punctuated<MetaNameValue, Token![,]>, kind: Kind) -> syn::Result<Flavor> {
+    let mut flavor = None;
+    let mut worker_threads = None;
+    let mut scheduler = None;
+    let mut start_paused = None;
+    let mut seed = None;
+
+    for arg in args {
+        let name = arg
+            .path
+            .get_ident()
+            .map(|ident| ident.to_string())
+            .unwrap_or_default();
+        let slot = match name.as_str() {
+            "flavor" => &mut flavor,
+            "worker_threads" => &mut worker_threads,
+            "scheduler" => &mut scheduler,
+            "start_paused" => &mut start_paused,
+            "seed" => &mut seed,
+            _ => {
+                return Err(syn::Error::new(
+                    arg.path.span(),
+                    "unknown argument; expected one of `flavor`, `worker_threads`, \
+                     `scheduler`, `start_paused`, `seed`",
+                ));
+            }
+        };
+        if slot.is_some() {
+            return Err(syn::Error::new(
+                arg.path.span(),
+                format!("`{name}` is set more than once"),
+            ));
+        }
+        *slot = Some(arg.value);
+    }
+
+    let simulation = match &flavor {
+        None => false,
+        Some(expr) => match str_lit(expr).as_deref() {
+            Some("multi_thread") => false,
+            Some("simulation") => true,
+            _ => {
+                return Err(syn::Error::new(
+                    expr.span(),
+                    "`flavor` must be \"multi_thread\" or \"simulation\"",
+                ));
+            }
+        },
+    };
+
+    if simulation {
+        // シミュレーションは呼び出したスレッドだけで動き、時計は最初から止まっている
+        for (name, value) in [
+            ("worker_threads", &worker_threads),
+            ("scheduler", &scheduler),
+            ("start_paused", &start_paused),
+        ] {
+            if let Some(value) = value {
+                return Err(syn::Error::new(
+                    value.span(),
+                    format!("`{name}` cannot be used with the simulation flavor"),
+                ));
+            }
+        }
+        return Ok(Flavor::Simulation { seed });
+    }
+
+    if let Some(seed) = seed {
+        return Err(syn::Error::new(
+            seed.span(),
+            "`seed` can only be used with the simulation flavor",
+        ));
+    }
+    if let Some(expr) = &worker_threads
+        && int_lit(expr).is_some_and(|n| n == 0)
+    {
+        return Err(syn::Error::new(
+            expr.span(),
+            "`worker_threads` must be greater than 0",
+        ));
+    }
+    let start_paused = match start_paused {
+        None => false,
+        Some(Expr::Lit(ExprLit {
+            lit: Lit::Bool(b), ..
+        })) => b.value,
+        Some(expr) => {
+            return Err(syn::Error::new(
+                expr.span(),
+                "`start_paused` must be `true` or `false`",
+            ));
+        }
+    };
+    let scheduler = match scheduler {
+        Some(expr) => scheduler_expr(&expr)?,
+        None => quote!(::async_runtime::engine::schedule::fifo::Fifo::new()),
+    };
+    let worker_threads = worker_threads.or_else(|| {
+        // テストは並列に走るので、省略時は1つのWorkerで足りる
+        (kind == Kind::Test).then(|| syn::parse_quote!(1))
+    });
+    Ok(Flavor::MultiThread {
+        worker_threads,
+        scheduler,
+        start_paused,
+    })
+}
+
+fn check_signature(input: &ItemFn, kind: Kind) -> syn::Result<()> {
+    let sig = &input.sig;
+    if sig.asyncness.is_none() {
+        return Err(syn::Error::new(
+            sig.fn_token.span(),
+            "the `async` keyword is missing from the function declaration",
+        ));
+    }
+    if !sig.inputs.is_empty() {
+        return Err(syn::Error::new(
+            sig.inputs.span(),
+            format!("#[{}] functions cannot take arguments", kind.name()),
+        ));
+    }
+    if !sig.generics.params.is_empty() {
+        return Err(syn::Error::new(
+            sig.generics.span(),
+            format!("#[{}] functions cannot be generic", kind.name()),
+        ));
+    }
+    if kind == Kind::Test
+        && let Some(attr) = input.attrs.iter().find(|attr| attr.path().is_ident("test"))
+    {
+        return Err(syn::Error::new(
+            attr.span(),
+            "the #[test] attribute is already added by this macro",
+        ));
+    }
+    Ok(())
+}
+
+fn build(mut input: ItemFn, flavor: Flavor, kind: Kind) -> TokenStream2 {
+    input.sig.asyncness = None;
+    let output_ty = match &input.sig.output {
+        ReturnType::Default => quote!(()),
+        ReturnType::Type(_, ty) => quote!(#ty),
+    };
+    let block = &input.block;
+
+    let run = match flavor {
+        Flavor::MultiThread {
+            worker_threads,
+            scheduler,
+            start_paused,
+        } => {
+            let worker_threads = match worker_threads {
+                Some(n) => quote!(#n),
+                None => quote! {
+                    ::std::thread::available_parallelism().map_or(1, ::std::num::NonZero::get)
+                },
+            };
+            let pause = start_paused.then(|| quote!(::async_runtime::time::pause();));
+            quote! {
+                let engine = ::async_runtime::Engine::new(#worker_threads, #scheduler);
+                let output: #output_ty = engine.block_on(async move {
+                    #pause
+                    #block
+                });
+                engine.graceful_shutdown();
+                output
+            }
+        }
+        Flavor::Simulation { seed } => {
+            let simulation = match seed {
+                Some(seed) => quote!(::async_runtime::engine::Simulation::new(#seed)),
+                None => quote!(::async_runtime::engine::Simulation::from_env()),
+            };
+            quote! {
+                let output: #output_ty = #simulation.block_on(async move #block);
+                output
+            }
+        }
+    };
+
+    input.block = syn::parse_quote!({ #run });
+    let test_attr = (kind == Kind::Test).then(|| quote!(#[::core::prelude::v1::test]));
+    quote! {
+        #test_attr
+        #input
+    }
+}
+
+// 組み込みのスケジューラは名前で、それ以外は式で指定する
+fn scheduler_expr(expr: &Expr) -> syn::Result<TokenStream2> {
+    let Some(name) = str_lit(expr) else {
+        return Ok(quote!(#expr));
+    };
+    let path = match name.as_str() {
+        "fifo" => quote!(fifo::Fifo),
+        "priority" => quote!(priority::PriorityScheduler),
+        "deadline" => quote!(deadline::DeadLineScheduler),
+        "mlfq" => quote!(mlfq::Mlfq),
+        "fair_share" => quote!(fair_share::FairShare),
+        _ => {
+            return Err(syn::Error::new(
+                expr.span(),
+                "unknown scheduler; expected \"fifo\", \"priority\", \"deadline\", \
+                 \"mlfq\", \"fair_share\" or an expression",
+            ));
+        }
+    };
+    Ok(quote!(::async_runtime::engine::schedule::#path::new()))
+}
+
+fn str_lit(expr: &Expr) -> Option<String> {
+    match expr {
+        Expr::Lit(ExprLit {
+            lit: Lit::Str(s), ..
+        }) => Some(s.value()),
+        _ => None,
+    }
+}
+
+fn int_lit(expr: &Expr) -> Option<u64> {
+    match expr {
+        Expr::Lit(ExprLit {
+            lit: Lit::Int(n), ..
+        }) => n.base10_parse().ok(),
+        _ => None,
+    }
+}
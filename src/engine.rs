@@ -1,88 +1,379 @@
+pub mod affinity;
+pub mod blocking;
+pub mod builder;
+pub mod coop;
+mod dispatch;
+pub mod handle;
+mod inject;
+pub mod join;
+pub mod join_set;
+pub mod pool;
+mod registry;
 pub mod schedule;
+pub mod shutdown;
+pub mod sim;
 pub mod task;
+pub mod task_local;
+pub mod trace;
 pub mod waker;
 pub mod worker;
 
-use schedule::Scheduler;
+use dispatch::Dispatcher;
+use pool::Pool;
+use schedule::fair_share::GroupId;
+use schedule::priority::Priority;
+use schedule::{Scheduler, SchedulerMetrics};
 use std::{
     future::Future,
     sync::{
-        Arc, Mutex,
-        atomic::{AtomicBool, Ordering},
-        mpsc::channel as mpsc_channel,
+        Arc,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
     },
-    task::Wake,
-    thread::{JoinHandle, spawn},
+    task::{Context, Poll, Wake},
+    time::{Duration, Instant},
 };
-use worker::{Worker, WorkerInfo};
 
-use task::Task;
+use crate::time::clock::Clock;
+use crate::time::driver::Driver;
 
-use crate::utils::channel::{Receiver, channel};
+pub use affinity::{Affinity, CpuSet};
+pub use blocking::spawn_blocking;
+pub use builder::Builder;
+pub use coop::yield_now;
+pub use handle::Handle;
+pub use join::JoinHandle;
+pub use join_set::{JoinSet, Scope, scope, scope_on};
+pub use pool::ScalingPolicy;
+pub use shutdown::ShutdownSignal;
+pub use sim::Simulation;
+pub use task::{TaskBuilder, TaskDump};
+pub use task_local::LocalKey;
+pub use trace::Recorder;
+pub use worker::{WorkerId, WorkerMetrics};
+
+use crate::utils::channel::Receiver;
+
+// shutdownを呼ばずに破棄されたときに、タスクの完了を待つ時間
+const DROP_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(1);
 
 pub struct Engine {
-    scheduler: Arc<Mutex<Box<dyn Scheduler + Send + 'static>>>,
-    shutdown: Arc<AtomicBool>,
-    worker_threads: Vec<JoinHandle<()>>,
-    worker_handles: std::sync::mpsc::Receiver<std::thread::Thread>,
+    // 通常は1つで、thread-per-coreではCPUごとに1つ
+    cores: Vec<Core>,
+    // Engineから直接登録したタスクを順番にコアへ振り分ける
+    next_core: AtomicUsize,
+    thread_per_core: bool,
+    stopped: bool,
+}
+
+// 独立したスケジューラと、それを実行するWorker
+struct Core {
+    handle: Handle,
+    pool: Arc<Pool>,
+}
+
+pub(crate) struct CoreConfig {
+    pub(crate) scheduler: Box<dyn Scheduler + Send>,
+    pub(crate) workers: usize,
+    pub(crate) affinity: Affinity,
+    pub(crate) recorder: Option<Recorder>,
 }
 
 impl Engine {
-    pub fn new(
-        worker_num: usize,
-        scheduler_factory: impl FnOnce(
-            std::sync::mpsc::Receiver<WorkerInfo>,
-        ) -> Box<dyn Scheduler + Send + 'static>,
-    ) -> Self {
-        let (worker_sender, worker_receiver) = mpsc_channel();
-        let (handle_sender, handle_receiver) = mpsc_channel();
-        let scheduler = Arc::new(Mutex::new(scheduler_factory(worker_receiver)));
-        let shutdown = Arc::new(AtomicBool::new(false));
-
-        let mut worker_threads = Vec::new();
-        for _ in 0..worker_num {
-            let cloned_scheduler = scheduler.clone();
-            let cloned_sender = worker_sender.clone();
-            let cloned_shutdown = shutdown.clone();
-            let cloned_handle_sender = handle_sender.clone();
-
-            let tj = spawn(move || {
-                // スレッドハンドルを送信
-                let _ = cloned_handle_sender.send(std::thread::current());
-                Worker::new(cloned_sender, cloned_scheduler, cloned_shutdown).execute();
-            });
-
-            worker_threads.push(tj);
-        }
+    pub fn new(worker_num: usize, scheduler: impl Scheduler + Send + 'static) -> Self {
+        let core = CoreConfig {
+            scheduler: Box::new(scheduler),
+            workers: worker_num,
+            affinity: Affinity::Any,
+            recorder: None,
+        };
+        Self::start(vec![core], false)
+    }
+
+    pub fn builder() -> Builder {
+        Builder::new()
+    }
+
+    // タイマーは全てのコアで共有する
+    pub(crate) fn start(configs: Vec<CoreConfig>, thread_per_core: bool) -> Self {
+        let driver = Driver::spawn(Clock::real());
+        let ids = Arc::new(AtomicUsize::new(0));
+        let cores: Vec<Core> = configs
+            .into_iter()
+            .map(|config| {
+                let dispatcher = Arc::new(Dispatcher::new(config.scheduler, driver.clone()));
+                if let Some(recorder) = config.recorder {
+                    dispatcher.set_recorder(recorder);
+                }
+                let handle = Handle::new(dispatcher);
+                let pool = Arc::new(Pool::new(handle.clone(), ids.clone(), config.affinity));
+                pool.resize(config.workers);
+                Core { handle, pool }
+            })
+            .collect();
+        // time::pause()で止めた時計は、全てのWorkerが眠ったら次の期限まで進める
+        let dispatchers: Vec<_> = cores
+            .iter()
+            .map(|core| Arc::downgrade(core.handle.dispatcher()))
+            .collect();
+        driver.set_idle_check(move || {
+            dispatchers
+                .iter()
+                .all(|weak| weak.upgrade().is_some_and(|d| d.is_idle()))
+        });
         Self {
-            scheduler,
-            shutdown,
-            worker_threads,
-            worker_handles: handle_receiver,
+            cores,
+            next_core: AtomicUsize::new(0),
+            thread_per_core,
+            stopped: false,
         }
     }
 
+    fn next_handle(&self) -> &Handle {
+        let index = self.next_core.fetch_add(1, Ordering::Relaxed) % self.cores.len();
+        &self.cores[index].handle
+    }
+
     pub fn reserve<V, W>(&mut self, task: V, deadline: Option<u64>) -> Receiver<W>
     where
         V: Future<Output = W> + Send + 'static,
         W: Clone + Send + Unpin + 'static,
     {
-        let (sender, receiver) = channel();
-        let task = Task::new(task, sender, deadline);
-        self.scheduler.lock().unwrap().schedule(task);
-        receiver
+        self.next_handle().reserve(task, deadline)
     }
 
-    pub fn graceful_shutdown(self) {
-        self.shutdown.store(true, Ordering::Release);
+    pub fn reserve_with_priority<V, W>(&mut self, task: V, priority: Priority) -> Receiver<W>
+    where
+        V: Future<Output = W> + Send + 'static,
+        W: Clone + Send + Unpin + 'static,
+    {
+        self.next_handle().reserve_with_priority(task, priority)
+    }
 
-        while let Ok(thread) = self.worker_handles.try_recv() {
-            thread.unpark();
+    pub fn reserve_in_group<V, W>(&mut self, task: V, group: GroupId) -> Receiver<W>
+    where
+        V: Future<Output = W> + Send + 'static,
+        W: Clone + Send + Unpin + 'static,
+    {
+        self.next_handle().reserve_in_group(task, group)
+    }
+
+    pub fn spawn<F>(&self, future: F) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        self.next_handle().spawn(future)
+    }
+
+    // 名前や優先度を付けてタスクを登録する
+    pub fn task_builder(&self) -> TaskBuilder {
+        self.next_handle().task_builder()
+    }
+
+    // 呼び出したスレッドでfutureを完了まで実行する。中からspawnしたタスクはこのEngineで動く
+    // futureがpollされているか起こされている間は、Workerと同じく動いているものとして数え、
+    // time::pause()で止めた時計を進めない
+    pub fn block_on<F: Future>(&self, future: F) -> F::Output {
+        let _guard = self.handle().enter();
+        let dispatcher = self.handle().dispatcher().clone();
+        let main = Arc::new(MainWaker {
+            thread: std::thread::current(),
+            notified: AtomicBool::new(true),
+            dispatcher: dispatcher.clone(),
+        });
+        dispatcher.block_on_busy();
+
+        let waker = std::task::Waker::from(main.clone());
+        let mut cx = Context::from_waker(&waker);
+        let mut future = std::pin::pin!(future);
+        loop {
+            main.notified.store(false, Ordering::SeqCst);
+            let poll = future.as_mut().poll(&mut cx);
+            if let Poll::Ready(output) = poll {
+                // 以後のwakeは数えない。poll中に起こされていればその分も戻す
+                if main.notified.swap(true, Ordering::SeqCst) {
+                    dispatcher.block_on_idle();
+                }
+                dispatcher.block_on_idle();
+                return output;
+            }
+            dispatcher.block_on_idle();
+            while !main.notified.load(Ordering::SeqCst) {
+                std::thread::park();
+            }
         }
+    }
+
+    // thread-per-coreではコアごとのキューの長さとタスクの数を足し合わせる
+    pub fn scheduler_metrics(&self) -> SchedulerMetrics {
+        let mut metrics = SchedulerMetrics::default();
+        for core in &self.cores {
+            let core_metrics = core.handle.scheduler_metrics();
+            if metrics.queue_depths.len() < core_metrics.queue_depths.len() {
+                metrics
+                    .queue_depths
+                    .resize(core_metrics.queue_depths.len(), 0);
+            }
+            for (total, depth) in metrics
+                .queue_depths
+                .iter_mut()
+                .zip(core_metrics.queue_depths)
+            {
+                *total += depth;
+            }
+            for (name, count) in core_metrics.named_tasks {
+                *metrics.named_tasks.entry(name).or_default() += count;
+            }
+            metrics.local_tasks += core_metrics.local_tasks;
+        }
+        metrics
+    }
+
+    // 全てのコアに残っている、まだ完了していないタスクの一覧
+    pub fn dump(&self) -> Vec<TaskDump> {
+        let mut tasks: Vec<_> = self
+            .cores
+            .iter()
+            .flat_map(|core| core.handle.dump())
+            .collect();
+        tasks.sort_by_key(|task| task.id);
+        tasks
+    }
+
+    // 最初のコアのHandle
+    pub fn handle(&self) -> &Handle {
+        &self.cores[0].handle
+    }
+
+    pub fn core_count(&self) -> usize {
+        self.cores.len()
+    }
+
+    // index番目のコアにタスクを登録するためのHandle
+    pub fn core_handle(&self, index: usize) -> &Handle {
+        &self.cores[index].handle
+    }
+
+    // 実行中のWorkerの数を変える。減らすときは新しく増やしたものから、実行中のpollを終えてから止める
+    // 0にするとタスクを実行するものがいなくなるので受け付けない
+    pub fn set_worker_count(&self, n: usize) {
+        assert!(n > 0, "worker count must be at least 1");
+        self.single_core("set_worker_count").pool.resize(n);
+    }
+
+    pub fn worker_count(&self) -> usize {
+        self.cores.iter().map(|core| core.pool.len()).sum()
+    }
+
+    // 退役したものを含む、Workerごとの統計
+    pub fn worker_metrics(&self) -> Vec<WorkerMetrics> {
+        let mut metrics: Vec<_> = self
+            .cores
+            .iter()
+            .flat_map(|core| core.pool.metrics())
+            .collect();
+        metrics.sort_by_key(|m| m.id);
+        metrics
+    }
+
+    // 負荷に合わせてWorkerの数を自動で増減させる
+    pub fn set_scaling_policy(&self, policy: ScalingPolicy) {
+        self.single_core("set_scaling_policy")
+            .pool
+            .set_policy(Some(policy));
+    }
+
+    // 自動での増減をやめる。Workerの数はその時点のまま
+    pub fn clear_scaling_policy(&self) {
+        self.single_core("clear_scaling_policy")
+            .pool
+            .set_policy(None);
+    }
+
+    // thread-per-coreではコアごとのWorkerは1つに決まっている
+    fn single_core(&self, method: &str) -> &Core {
+        assert!(
+            !self.thread_per_core,
+            "{method} is not supported by a thread-per-core engine"
+        );
+        &self.cores[0]
+    }
+
+    // 新しいタスクを受け付けるのをやめ、登録済みのタスクが完了するのをtimeoutまで待つ
+    // 残ったタスクは破棄し、JoinHandleにはErr(JoinError::Shutdown)を返す
+    // 全て完了していればtrue
+    pub fn shutdown(mut self, timeout: Duration) -> bool {
+        self.stop(timeout)
+    }
+
+    // 実行中のpollが終わるのだけを待ち、残りのタスクはすぐに破棄する
+    pub fn shutdown_now(self) {
+        self.shutdown(Duration::ZERO);
+    }
+
+    // Dropと同じだけ完了を待ってから止める。待つ時間を決めるならshutdownを使う
+    pub fn graceful_shutdown(self) -> bool {
+        self.shutdown(DROP_SHUTDOWN_TIMEOUT)
+    }
+
+    fn stop(&mut self, timeout: Duration) -> bool {
+        self.stopped = true;
+        let deadline = Instant::now() + timeout;
+        for core in &self.cores {
+            core.handle.dispatcher().close();
+        }
+        let drained = self.cores.iter().all(|core| {
+            let timeout = deadline.saturating_duration_since(Instant::now());
+            core.handle.dispatcher().wait_drained(timeout)
+        });
+        for core in &self.cores {
+            core.pool.stop();
+        }
+        for core in &self.cores {
+            core.handle.dispatcher().cancel_all();
+        }
+        self.handle().driver().shutdown();
+        drained
+    }
+}
+
+impl Drop for Engine {
+    fn drop(&mut self) {
+        if !self.stopped {
+            self.stop(DROP_SHUTDOWN_TIMEOUT);
+        }
+    }
+}
+
+// 現在のEngineにタスクを登録する。Workerスレッド上かHandle::enter()の範囲内で呼ぶ
+pub fn spawn<F>(future: F) -> JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    Handle::current().spawn(future)
+}
+
+// Engine::block_onのFutureを起こす
+struct MainWaker {
+    thread: std::thread::Thread,
+    // 起こされてから次のpollが始まるまでtrue
+    notified: AtomicBool,
+    dispatcher: Arc<Dispatcher>,
+}
+
+impl Wake for MainWaker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
 
-        for tj in self.worker_threads {
-            let _ = tj.join();
+    fn wake_by_ref(self: &Arc<Self>) {
+        // 先に数えてから起こす。止めた時計が、起こされたのにまだ数えられていない隙に進まない
+        self.dispatcher.block_on_busy();
+        if self.notified.swap(true, Ordering::SeqCst) {
+            self.dispatcher.block_on_idle();
         }
+        self.thread.unpark();
     }
 }
 
@@ -118,5 +409,5 @@ pub fn block_on<T, F: IntoFuture<Output = T>>(future: F) -> T {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, not(loom)))]
 mod test;
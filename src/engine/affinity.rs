@@ -0,0 +1,142 @@
+use std::collections::BTreeSet;
+use std::io;
+
+// スレッドを動かしてよいCPUの集合
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct CpuSet(BTreeSet<usize>);
+
+impl CpuSet {
+    pub fn new(cpus: impl IntoIterator<Item = usize>) -> Self {
+        CpuSet(cpus.into_iter().collect())
+    }
+
+    pub fn single(cpu: usize) -> Self {
+        Self::new([cpu])
+    }
+
+    // このプロセスが今動いてよいCPU
+    pub fn available() -> io::Result<Self> {
+        imp::get_current()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn contains(&self, cpu: usize) -> bool {
+        self.0.contains(&cpu)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.0.iter().copied()
+    }
+
+    // index番目のCPU。集合の大きさで折り返す
+    pub(crate) fn nth_wrapping(&self, index: usize) -> Option<usize> {
+        if self.is_empty() {
+            return None;
+        }
+        self.0.iter().nth(index % self.len()).copied()
+    }
+}
+
+// Workerをどのように固定するか
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum Affinity {
+    // OSに任せる
+    #[default]
+    Any,
+    // 全てのWorkerを同じ集合に固定する
+    Shared(CpuSet),
+    // WorkerごとにCPUを1つずつ順番に割り当てる
+    OnePerCpu(CpuSet),
+}
+
+impl Affinity {
+    // index番目のWorkerを固定する先
+    pub(crate) fn for_worker(&self, index: usize) -> Option<CpuSet> {
+        match self {
+            Affinity::Any => None,
+            Affinity::Shared(cpus) => Some(cpus.clone()),
+            Affinity::OnePerCpu(cpus) => cpus.nth_wrapping(index).map(CpuSet::single),
+        }
+    }
+}
+
+// 呼び出したスレッドをcpusの中だけで動かす
+pub fn pin_current(cpus: &CpuSet) -> io::Result<()> {
+    if cpus.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "cpu set must not be empty",
+        ));
+    }
+    imp::set_current(cpus)
+}
+
+#[cfg(target_os = "linux")]
+mod imp {
+    use std::io;
+    use std::mem;
+
+    use super::CpuSet;
+
+    pub(super) fn set_current(cpus: &CpuSet) -> io::Result<()> {
+        let mut set: libc::cpu_set_t = unsafe { mem::zeroed() };
+        for cpu in cpus.iter() {
+            if cpu >= libc::CPU_SETSIZE as usize {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("cpu {cpu} is out of range"),
+                ));
+            }
+            unsafe { libc::CPU_SET(cpu, &mut set) };
+        }
+        // pidに0を渡すと呼び出したスレッドが対象になる
+        let res = unsafe { libc::sched_setaffinity(0, mem::size_of::<libc::cpu_set_t>(), &set) };
+        if res < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    pub(super) fn get_current() -> io::Result<CpuSet> {
+        let mut set: libc::cpu_set_t = unsafe { mem::zeroed() };
+        let res =
+            unsafe { libc::sched_getaffinity(0, mem::size_of::<libc::cpu_set_t>(), &mut set) };
+        if res < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(CpuSet::new(
+            (0..libc::CPU_SETSIZE as usize).filter(|&cpu| unsafe { libc::CPU_ISSET(cpu, &set) }),
+        ))
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod imp {
+    use std::io;
+
+    use super::CpuSet;
+
+    pub(super) fn set_current(_cpus: &CpuSet) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "cpu affinity is only supported on linux",
+        ))
+    }
+
+    pub(super) fn get_current() -> io::Result<CpuSet> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "cpu affinity is only supported on linux",
+        ))
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod test;
@@ -0,0 +1,50 @@
+use std::io;
+use std::thread;
+
+use super::{Affinity, CpuSet, pin_current};
+
+#[test]
+fn cpu_set_orders_and_wraps() {
+    let cpus = CpuSet::new([3, 1, 2]);
+    assert_eq!(cpus.iter().collect::<Vec<_>>(), vec![1, 2, 3]);
+    assert_eq!(cpus.nth_wrapping(0), Some(1));
+    assert_eq!(cpus.nth_wrapping(4), Some(2));
+    assert_eq!(CpuSet::default().nth_wrapping(0), None);
+}
+
+#[test]
+fn affinity_for_worker() {
+    let cpus = CpuSet::new([0, 1]);
+    assert_eq!(Affinity::Any.for_worker(0), None);
+    assert_eq!(
+        Affinity::Shared(cpus.clone()).for_worker(5),
+        Some(cpus.clone())
+    );
+    assert_eq!(
+        Affinity::OnePerCpu(cpus).for_worker(3),
+        Some(CpuSet::single(1))
+    );
+}
+
+#[test]
+fn empty_set_is_rejected() {
+    let err = pin_current(&CpuSet::default()).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn pin_current_restricts_thread() {
+    let available = CpuSet::available().unwrap();
+    assert!(!available.is_empty());
+    let cpu = available.iter().last().unwrap();
+
+    // 固定するのは別スレッドにして、他のテストに影響させない
+    let pinned = thread::spawn(move || {
+        pin_current(&CpuSet::single(cpu)).unwrap();
+        CpuSet::available().unwrap()
+    })
+    .join()
+    .unwrap();
+    assert_eq!(pinned, CpuSet::single(cpu));
+}
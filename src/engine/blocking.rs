@@ -0,0 +1,165 @@
+use std::collections::VecDeque;
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
+use std::sync::{Arc, Condvar, Mutex, OnceLock};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+use std::time::Duration;
+
+use crate::time::driver::Driver;
+
+// ブロッキング処理を実行するスレッドの上限
+const MAX_THREADS: usize = 64;
+// この時間仕事がなければスレッドを終える
+const KEEP_ALIVE: Duration = Duration::from_secs(10);
+
+type Job = Box<dyn FnOnce() + Send>;
+
+// ファイル操作などのブロッキング処理を、Workerとは別のスレッドで実行する
+// スレッドは必要になったら起動し、しばらく使われなければ止まる
+struct Pool {
+    shared: Mutex<Shared>,
+    condvar: Condvar,
+}
+
+struct Shared {
+    queue: VecDeque<Job>,
+    threads: usize,
+    // 仕事を待っているスレッドの数
+    idle: usize,
+    // 起こしたが、まだ目を覚ましていないスレッドの数
+    notified: usize,
+    // スレッド名に付ける番号。止まったスレッドのものは使い回さない
+    spawned: usize,
+}
+
+static POOL: OnceLock<Pool> = OnceLock::new();
+
+impl Pool {
+    fn global() -> &'static Pool {
+        POOL.get_or_init(|| Pool {
+            shared: Mutex::new(Shared {
+                queue: VecDeque::new(),
+                threads: 0,
+                idle: 0,
+                notified: 0,
+                spawned: 0,
+            }),
+            condvar: Condvar::new(),
+        })
+    }
+
+    // 待っているスレッドがいれば起こし、いなければ上限まで増やす
+    // 上限に達していたら、先に動いているスレッドが手を空けるまでキューで待つ
+    fn push(&'static self, job: Job) {
+        let mut shared = self.shared.lock().unwrap();
+        shared.queue.push_back(job);
+        if shared.idle > 0 {
+            shared.idle -= 1;
+            shared.notified += 1;
+            self.condvar.notify_one();
+        } else if shared.threads < MAX_THREADS {
+            shared.threads += 1;
+            shared.spawned += 1;
+            let id = shared.spawned;
+            drop(shared);
+            thread::Builder::new()
+                .name(format!("async-runtime-blocking-{id}"))
+                .spawn(move || self.run())
+                .expect("failed to spawn blocking thread");
+        }
+    }
+
+    // キューが空になるまで続けて実行してから眠る
+    fn run(&self) {
+        let mut shared = self.shared.lock().unwrap();
+        loop {
+            while let Some(job) = shared.queue.pop_front() {
+                drop(shared);
+                job();
+                shared = self.shared.lock().unwrap();
+            }
+            shared.idle += 1;
+            loop {
+                let (next, timeout) = self.condvar.wait_timeout(shared, KEEP_ALIVE).unwrap();
+                shared = next;
+                // idleはpush側で減らしてある
+                if shared.notified > 0 {
+                    shared.notified -= 1;
+                    break;
+                }
+                if timeout.timed_out() {
+                    shared.idle -= 1;
+                    shared.threads -= 1;
+                    return;
+                }
+            }
+        }
+    }
+}
+
+// fを別スレッドで実行し、結果を待つFutureを返す
+// fがパニックした場合は、awaitした側でパニックする
+pub fn spawn_blocking<F, T>(f: F) -> Blocking<T>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    let slot = Arc::new(Mutex::new(Slot {
+        result: None,
+        waker: None,
+    }));
+    let shared = slot.clone();
+    // 止めた時計は、この処理が終わるまで進めない
+    let driver = Driver::current();
+    driver.blocking_started();
+    Pool::global().push(Box::new(move || {
+        let result = panic::catch_unwind(AssertUnwindSafe(f));
+        let waker = {
+            let mut slot = shared.lock().unwrap();
+            slot.result = Some(result);
+            slot.waker.take()
+        };
+        // 待っているタスクを起こしてから数を減らし、その間に全て眠ったと見なされないようにする
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+        driver.blocking_finished();
+    }));
+    Blocking { slot }
+}
+
+struct Slot<T> {
+    result: Option<thread::Result<T>>,
+    waker: Option<Waker>,
+}
+
+pub struct Blocking<T> {
+    slot: Arc<Mutex<Slot<T>>>,
+}
+
+impl<T> Future for Blocking<T> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<T> {
+        let mut slot = self.slot.lock().unwrap();
+        match slot.result.take() {
+            Some(Ok(value)) => Poll::Ready(value),
+            Some(Err(payload)) => {
+                drop(slot);
+                panic::resume_unwind(payload)
+            }
+            None => {
+                match &mut slot.waker {
+                    Some(waker) => waker.clone_from(cx.waker()),
+                    None => slot.waker = Some(cx.waker().clone()),
+                }
+                Poll::Pending
+            }
+        }
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod test;
@@ -0,0 +1,70 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use super::spawn_blocking;
+use crate::engine::schedule::fifo::Fifo;
+use crate::engine::{Engine, block_on};
+use crate::time::{pause, timeout};
+
+#[test]
+fn runs_on_another_thread() {
+    let caller = thread::current().id();
+    let (id, name) = block_on(spawn_blocking(|| {
+        let current = thread::current();
+        (current.id(), current.name().map(str::to_string))
+    }));
+    assert_ne!(id, caller);
+    assert!(name.unwrap().starts_with("async-runtime-blocking-"));
+}
+
+#[test]
+fn does_not_block_workers() {
+    let engine = Engine::new(1, Fifo::new());
+    let (tx, rx) = mpsc::channel::<()>();
+    // 唯一のWorkerが待つのはFutureだけで、ブロックするのは別スレッド
+    let blocked = engine.spawn(async move { spawn_blocking(move || rx.recv().unwrap()).await });
+    assert_eq!(block_on(engine.spawn(async { 1 })), Ok(1));
+    tx.send(()).unwrap();
+    assert_eq!(block_on(blocked), Ok(()));
+    engine.shutdown_now();
+}
+
+#[test]
+fn many_jobs_complete() {
+    let count = Arc::new(AtomicUsize::new(0));
+    let handles: Vec<_> = (0..200)
+        .map(|_| {
+            let count = count.clone();
+            spawn_blocking(move || {
+                thread::sleep(Duration::from_millis(1));
+                count.fetch_add(1, Ordering::SeqCst)
+            })
+        })
+        .collect();
+    for handle in handles {
+        block_on(handle);
+    }
+    assert_eq!(count.load(Ordering::SeqCst), 200);
+}
+
+#[test]
+#[should_panic(expected = "blocking boom")]
+fn panic_is_resumed() {
+    block_on(spawn_blocking(|| panic!("blocking boom")));
+}
+
+#[test]
+fn paused_clock_waits_for_blocking_work() {
+    let engine = Engine::new(1, Fifo::new());
+    let _guard = engine.handle().enter();
+    pause();
+    // Workerは眠っているが、ブロッキング処理が終わる前に時計を進めてはいけない
+    let res = block_on(engine.spawn(async {
+        let work = spawn_blocking(|| thread::sleep(Duration::from_millis(200)));
+        timeout(Duration::from_secs(5), work).await
+    }));
+    assert_eq!(res, Ok(Ok(())));
+}
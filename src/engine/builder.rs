@@ -0,0 +1,125 @@
+use std::io;
+
+use crate::engine::affinity::{Affinity, CpuSet};
+use crate::engine::schedule::Scheduler;
+use crate::engine::schedule::fifo::Fifo;
+use crate::engine::trace::Recorder;
+use crate::engine::{CoreConfig, Engine};
+
+type SchedulerFactory = Box<dyn Fn() -> Box<dyn Scheduler + Send>>;
+
+// Engineの設定を組み立てる
+// thread-per-coreではコアごとにスケジューラを作るので、スケジューラは作り方で受け取る
+pub struct Builder {
+    worker_threads: Option<usize>,
+    scheduler: SchedulerFactory,
+    affinity: Affinity,
+    thread_per_core: Option<CpuSet>,
+    recorder: Option<Recorder>,
+}
+
+impl Builder {
+    pub fn new() -> Self {
+        Self {
+            worker_threads: None,
+            scheduler: Box::new(|| Box::new(Fifo::new())),
+            affinity: Affinity::Any,
+            thread_per_core: None,
+            recorder: None,
+        }
+    }
+
+    // 省略したときはCPUの数だけWorkerを起動する
+    pub fn with_worker_threads(mut self, worker_threads: usize) -> Self {
+        self.worker_threads = Some(worker_threads);
+        self
+    }
+
+    pub fn with_scheduler<S>(mut self, factory: impl Fn() -> S + 'static) -> Self
+    where
+        S: Scheduler + Send + 'static,
+    {
+        self.scheduler = Box::new(move || Box::new(factory()));
+        self
+    }
+
+    // Workerを動かすCPUを固定する
+    pub fn with_affinity(mut self, affinity: Affinity) -> Self {
+        self.affinity = affinity;
+        self
+    }
+
+    // cpusのCPUごとに、1つのWorkerと専用のスケジューラを持つコアを作る
+    // タスクは登録されたコアから移らず、タスクの中からspawnしたものも同じコアで動く
+    pub fn with_thread_per_core(mut self, cpus: CpuSet) -> Self {
+        self.thread_per_core = Some(cpus);
+        self
+    }
+
+    // タスクの登録・poll・完了などをrecorderに記録する
+    pub fn with_recorder(mut self, recorder: Recorder) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+
+    pub fn build(self) -> io::Result<Engine> {
+        if let Some(cpus) = self.thread_per_core {
+            if self.worker_threads.is_some() || self.affinity != Affinity::Any {
+                return Err(invalid(
+                    "thread-per-core cannot be combined with worker_threads or affinity",
+                ));
+            }
+            check_available(&cpus)?;
+            let cores = cpus
+                .iter()
+                .map(|cpu| CoreConfig {
+                    scheduler: (self.scheduler)(),
+                    workers: 1,
+                    affinity: Affinity::Shared(CpuSet::single(cpu)),
+                    recorder: self.recorder.clone(),
+                })
+                .collect();
+            return Ok(Engine::start(cores, true));
+        }
+
+        match &self.affinity {
+            Affinity::Any => {}
+            Affinity::Shared(cpus) | Affinity::OnePerCpu(cpus) => check_available(cpus)?,
+        }
+        let workers = self.worker_threads.unwrap_or_else(|| {
+            std::thread::available_parallelism().map_or(1, std::num::NonZero::get)
+        });
+        let core = CoreConfig {
+            scheduler: (self.scheduler)(),
+            workers,
+            affinity: self.affinity,
+            recorder: self.recorder,
+        };
+        Ok(Engine::start(vec![core], false))
+    }
+}
+
+impl Default for Builder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// 固定する先のCPUが、このプロセスで使えるものか確かめる
+fn check_available(cpus: &CpuSet) -> io::Result<()> {
+    if cpus.is_empty() {
+        return Err(invalid("cpu set must not be empty"));
+    }
+    let available = CpuSet::available()?;
+    match cpus.iter().find(|&cpu| !available.contains(cpu)) {
+        Some(cpu) => Err(invalid(&format!("cpu {cpu} is not available"))),
+        None => Ok(()),
+    }
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message.to_string())
+}
+
+#[cfg(all(test, not(loom)))]
+mod test;
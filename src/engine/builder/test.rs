@@ -0,0 +1,101 @@
+use std::io;
+use std::thread;
+
+use crate::engine::affinity::{Affinity, CpuSet};
+use crate::engine::schedule::mlfq::Mlfq;
+use crate::engine::{Engine, block_on, spawn};
+
+fn available() -> CpuSet {
+    CpuSet::available().unwrap()
+}
+
+fn thread_name() -> String {
+    thread::current().name().unwrap_or_default().to_string()
+}
+
+#[test]
+fn builder_runs_tasks() {
+    let engine = Engine::builder()
+        .with_worker_threads(2)
+        .with_scheduler(Mlfq::new)
+        .build()
+        .unwrap();
+    assert_eq!(engine.worker_count(), 2);
+    assert_eq!(block_on(engine.spawn(async { 7 })), Ok(7));
+    assert_eq!(engine.scheduler_metrics().queue_depths.len(), 3);
+    engine.shutdown_now();
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn workers_are_pinned() {
+    let cpu = available().iter().last().unwrap();
+    let engine = Engine::builder()
+        .with_worker_threads(2)
+        .with_affinity(Affinity::Shared(CpuSet::single(cpu)))
+        .build()
+        .unwrap();
+
+    let pinned = block_on(engine.spawn(async { CpuSet::available().unwrap() })).unwrap();
+    assert_eq!(pinned, CpuSet::single(cpu));
+    engine.shutdown_now();
+}
+
+#[test]
+fn unavailable_cpu_is_rejected() {
+    let err = Engine::builder()
+        .with_affinity(Affinity::Shared(CpuSet::single(usize::MAX)))
+        .build()
+        .err()
+        .unwrap();
+    assert!(matches!(
+        err.kind(),
+        io::ErrorKind::InvalidInput | io::ErrorKind::Unsupported
+    ));
+}
+
+#[test]
+fn thread_per_core_rejects_worker_threads() {
+    let err = Engine::builder()
+        .with_worker_threads(2)
+        .with_thread_per_core(CpuSet::single(0))
+        .build()
+        .err()
+        .unwrap();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn thread_per_core_keeps_tasks_on_their_core() {
+    let cpus = available();
+    let engine = Engine::builder()
+        .with_thread_per_core(cpus.clone())
+        .build()
+        .unwrap();
+    assert_eq!(engine.core_count(), cpus.len());
+    assert_eq!(engine.worker_count(), cpus.len());
+
+    for (index, cpu) in cpus.iter().enumerate() {
+        let (outer, inner, pinned) = block_on(engine.core_handle(index).spawn(async {
+            // 中からspawnしたタスクも同じコアのWorkerで動く
+            let inner = spawn(async { thread_name() }).await.unwrap();
+            (thread_name(), inner, CpuSet::available().unwrap())
+        }))
+        .unwrap();
+        assert_eq!(outer, inner);
+        assert_eq!(pinned, CpuSet::single(cpu));
+    }
+    engine.shutdown_now();
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+#[should_panic(expected = "not supported by a thread-per-core engine")]
+fn thread_per_core_cannot_resize() {
+    let engine = Engine::builder()
+        .with_thread_per_core(available())
+        .build()
+        .unwrap();
+    engine.set_worker_count(1);
+}
@@ -0,0 +1,71 @@
+use std::cell::Cell;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+// 1回のpollでランタイム提供のFutureが進められる回数
+const INITIAL_BUDGET: u8 = 128;
+
+thread_local! {
+    // Noneの場合は予算なし（Workerの外でpollされている）
+    static BUDGET: Cell<Option<u8>> = const { Cell::new(None) };
+}
+
+// タスクを1回pollする間だけ予算を設定する
+pub(crate) fn budget<R>(f: impl FnOnce() -> R) -> R {
+    struct Reset(Option<u8>);
+
+    impl Drop for Reset {
+        fn drop(&mut self) {
+            BUDGET.with(|budget| budget.set(self.0));
+        }
+    }
+
+    let _reset = Reset(BUDGET.with(|budget| budget.replace(Some(INITIAL_BUDGET))));
+    f()
+}
+
+// ランタイム提供のFutureはpollの最初に呼ぶ
+// 予算を使い切っていたらタスクを後ろに回してPendingを返す
+pub fn poll_proceed(cx: &mut Context<'_>) -> Poll<()> {
+    let exhausted = BUDGET.with(|budget| match budget.get() {
+        Some(0) => true,
+        Some(n) => {
+            budget.set(Some(n - 1));
+            false
+        }
+        None => false,
+    });
+    if exhausted {
+        // poll中のwakeは、pollが終わってからキューの最後尾に戻される
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    } else {
+        Poll::Ready(())
+    }
+}
+
+// 一度だけPendingを返し、現在のタスクをスケジューラの最後尾に並べ直す
+pub async fn yield_now() {
+    YieldNow { yielded: false }.await
+}
+
+struct YieldNow {
+    yielded: bool,
+}
+
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.yielded {
+            return Poll::Ready(());
+        }
+        self.yielded = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod test;
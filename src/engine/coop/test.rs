@@ -0,0 +1,121 @@
+use std::future::Future;
+use std::pin::pin;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Wake, Waker};
+
+use super::{INITIAL_BUDGET, budget, poll_proceed, yield_now};
+use crate::engine::schedule::fifo::Fifo;
+use crate::engine::{Engine, block_on, spawn};
+use crate::utils::mpsc;
+
+fn engine() -> Engine {
+    Engine::new(1, Fifo::new())
+}
+
+#[test]
+fn poll_proceed_is_unlimited_outside_worker() {
+    let mut cx = Context::from_waker(Waker::noop());
+    for _ in 0..INITIAL_BUDGET as usize * 2 {
+        assert_eq!(poll_proceed(&mut cx), Poll::Ready(()));
+    }
+}
+
+struct CountWaker(AtomicUsize);
+
+impl Wake for CountWaker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.0.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn poll_proceed_wakes_after_budget_is_spent() {
+    let count = Arc::new(CountWaker(AtomicUsize::new(0)));
+    let waker = Waker::from(count.clone());
+    let mut cx = Context::from_waker(&waker);
+    let ready = budget(|| {
+        (0..=INITIAL_BUDGET as usize)
+            .filter(|_| poll_proceed(&mut cx).is_ready())
+            .count()
+    });
+    assert_eq!(ready, INITIAL_BUDGET as usize);
+    assert_eq!(count.0.load(Ordering::SeqCst), 1);
+
+    // 予算はpollごとに元に戻る
+    assert_eq!(poll_proceed(&mut cx), Poll::Ready(()));
+}
+
+#[test]
+fn yield_now_returns_pending_once() {
+    let count = Arc::new(CountWaker(AtomicUsize::new(0)));
+    let waker = Waker::from(count.clone());
+    let mut cx = Context::from_waker(&waker);
+    let mut fut = pin!(yield_now());
+    assert!(fut.as_mut().poll(&mut cx).is_pending());
+    assert_eq!(count.0.load(Ordering::SeqCst), 1);
+    assert!(fut.as_mut().poll(&mut cx).is_ready());
+}
+
+#[test]
+fn yield_now_interleaves_tasks_on_one_worker() {
+    let engine = engine();
+    let log = Arc::new(Mutex::new(Vec::new()));
+
+    let cloned_log = log.clone();
+    let done = engine.spawn(async move {
+        // 両方のタスクが並んでから実行されるように、タスクの中でspawnする
+        let tasks = ["a", "b"].map(|name| {
+            let log = cloned_log.clone();
+            spawn(async move {
+                for i in 0..3 {
+                    log.lock().unwrap().push(format!("{name}{i}"));
+                    yield_now().await;
+                }
+            })
+        });
+        for task in tasks {
+            task.await.unwrap();
+        }
+    });
+    block_on(done).unwrap();
+
+    assert_eq!(*log.lock().unwrap(), ["a0", "b0", "a1", "b1", "a2", "b2"]);
+    engine.graceful_shutdown();
+}
+
+#[test]
+fn always_ready_receiver_does_not_starve_other_tasks() {
+    let engine = engine();
+    let flag = Arc::new(AtomicBool::new(false));
+
+    let cloned_flag = flag.clone();
+    let done = engine.spawn(async move {
+        let (sender, mut receiver) = mpsc::channel();
+        for i in 0..INITIAL_BUDGET as usize * 10 {
+            sender.send(i).unwrap();
+        }
+        drop(sender);
+
+        let flag = cloned_flag.clone();
+        let drain = spawn(async move {
+            // 常にReadyを返すチャネルでも、予算が尽きれば他のタスクに譲る
+            while receiver.recv().await.is_some() {}
+            flag.load(Ordering::SeqCst)
+        });
+        let flag = cloned_flag;
+        let other = spawn(async move {
+            flag.store(true, Ordering::SeqCst);
+        });
+        other.await.unwrap();
+        drain.await.unwrap()
+    });
+
+    assert!(block_on(done).unwrap());
+    assert!(flag.load(Ordering::SeqCst));
+    engine.graceful_shutdown();
+}
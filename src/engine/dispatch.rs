@@ -0,0 +1,369 @@
+use std::collections::HashMap;
+use std::sync::atomic::AtomicU64;
+use std::sync::{Arc, OnceLock};
+use std::task::{Context, Poll, Waker};
+use std::time::Duration;
+
+use crate::engine::inject::Injector;
+use crate::engine::join;
+use crate::engine::registry::Registry;
+use crate::engine::schedule::{Scheduler, SchedulerMetrics};
+use crate::engine::task::{SharedTask, Task, TaskDump, TaskId};
+use crate::engine::trace::{EventKind, Recorder};
+use crate::engine::worker;
+use crate::loom::sync::Mutex;
+use crate::loom::sync::atomic::{AtomicBool, AtomicUsize, Ordering, fence};
+use crate::loom::thread::{self, Thread};
+use crate::time::driver::Driver;
+
+pub(crate) type SharedDispatcher = Arc<Dispatcher>;
+
+struct Injected {
+    task: SharedTask,
+    woken: bool,
+}
+
+// Workerはここから直接タスクを取り出し、何もなければ眠る
+// spawnやwakeはロックを取らずにinjectorへ入れ、眠っているWorkerがいれば起こすだけ
+pub(crate) struct Dispatcher {
+    injector: Injector<Injected>,
+    scheduler: Mutex<Box<dyn Scheduler + Send>>,
+    // スケジューラがpollの時間を使うときだけ、poll後にロックを取って知らせる
+    observes_polls: bool,
+    idle: Mutex<Vec<Thread>>,
+    // idleを覗く前にロックなしで判定するためのカウンタ
+    num_idle: AtomicUsize,
+    // 実行中のWorkerの数（眠っているものを含む）
+    workers: AtomicUsize,
+    // Engine::block_onで実行中か、起こされてpollを待っているFutureの数
+    // Workerが全て眠っていても、これが0でなければ止めた時計を進めない
+    blocked_on: AtomicUsize,
+    // このEngineのタスクが使うタイマー
+    driver: Arc<Driver>,
+    // 完了していないタスク。停止するときに待つか破棄する
+    tasks: Registry,
+    // 停止が始まった後に登録されたタスクは実行せずに破棄する
+    closed: AtomicBool,
+    signals: Mutex<Signals>,
+    // キューに入ってからpollされるまでの時間の合計（ナノ秒）と回数。take_latencyで0に戻す
+    latency_sum: AtomicU64,
+    latency_count: AtomicU64,
+    // 設定されていればタスクの実行の様子を記録する
+    recorder: OnceLock<Recorder>,
+}
+
+// 停止の開始を待っているShutdownSignal
+struct Signals {
+    wakers: HashMap<u64, Waker>,
+    next: u64,
+}
+
+impl Dispatcher {
+    pub(crate) fn new(scheduler: Box<dyn Scheduler + Send>, driver: Arc<Driver>) -> Self {
+        Self {
+            injector: Injector::new(),
+            observes_polls: scheduler.observes_polls(),
+            scheduler: Mutex::new(scheduler),
+            idle: Mutex::new(Vec::new()),
+            num_idle: AtomicUsize::new(0),
+            workers: AtomicUsize::new(0),
+            blocked_on: AtomicUsize::new(0),
+            driver,
+            tasks: Registry::new(),
+            closed: AtomicBool::new(false),
+            signals: Mutex::new(Signals {
+                wakers: HashMap::new(),
+                next: 0,
+            }),
+            latency_sum: AtomicU64::new(0),
+            latency_count: AtomicU64::new(0),
+            recorder: OnceLock::new(),
+        }
+    }
+
+    pub(crate) fn driver(&self) -> &Arc<Driver> {
+        &self.driver
+    }
+
+    // タスクを受け付ける前に設定する
+    pub(crate) fn set_recorder(&self, recorder: Recorder) {
+        let _ = self.recorder.set(recorder);
+    }
+
+    pub(crate) fn record(&self, kind: EventKind, task: &Task) {
+        if let Some(recorder) = self.recorder.get() {
+            recorder.record(kind, task, worker::current_id());
+        }
+    }
+
+    pub(crate) fn add_worker(&self) {
+        self.workers.fetch_add(1, Ordering::SeqCst);
+    }
+
+    // 退役するWorkerが受け取っていたかもしれない起床を、他のWorkerへ渡す
+    pub(crate) fn remove_worker(&self) {
+        self.workers.fetch_sub(1, Ordering::SeqCst);
+        fence(Ordering::SeqCst);
+        if self.has_injected() || !self.scheduler.lock().unwrap().is_empty() {
+            self.unpark_idle(1);
+        } else if self.num_idle.load(Ordering::SeqCst) == self.workers.load(Ordering::SeqCst) {
+            self.driver.notify_idle();
+        }
+    }
+
+    // 実行を待っているタスクの数
+    pub(crate) fn queue_depth(&self) -> usize {
+        self.with_scheduler(|scheduler| scheduler.len())
+    }
+
+    pub(crate) fn record_latency(&self, latency: Duration) {
+        self.latency_sum
+            .fetch_add(latency.as_nanos() as u64, Ordering::Relaxed);
+        self.latency_count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    // 前回呼ばれてからの平均の待ち時間。一度もpollされていなければNone
+    pub(crate) fn take_latency(&self) -> Option<Duration> {
+        let count = self.latency_count.swap(0, Ordering::Relaxed);
+        let sum = self.latency_sum.swap(0, Ordering::Relaxed);
+        (count > 0).then(|| Duration::from_nanos(sum / count))
+    }
+
+    // block_onのFutureが起こされた。pollを終えるまで止めた時計を進めない
+    pub(crate) fn block_on_busy(&self) {
+        self.blocked_on.fetch_add(1, Ordering::SeqCst);
+    }
+
+    // block_onのFutureがPendingを返した
+    pub(crate) fn block_on_idle(&self) {
+        self.blocked_on.fetch_sub(1, Ordering::SeqCst);
+        self.driver.notify_idle();
+    }
+
+    // 全てのWorkerとblock_onのFutureが眠っていて、実行を待つタスクもない
+    pub(crate) fn is_idle(&self) -> bool {
+        self.blocked_on.load(Ordering::SeqCst) == 0
+            && self.num_idle.load(Ordering::SeqCst) == self.workers.load(Ordering::SeqCst)
+            && !self.has_injected()
+            && self.scheduler.lock().unwrap().is_empty()
+    }
+
+    // タスクをキューに入れ、眠っているWorkerがいれば1つ起こす
+    pub(crate) fn schedule(self: &Arc<Self>, task: SharedTask) {
+        task.bind(self);
+        // 一覧に入れてから停止を確かめる。closeの後でcancel_allが一覧を見るので、
+        // ここで停止を見逃したタスクはcancel_allが破棄する
+        self.tasks.insert(&task);
+        if self.closed.load(Ordering::SeqCst) {
+            // 完了したタスクは一覧から外される
+            join::shutting_down(|| task.abort());
+            return;
+        }
+        self.record(EventKind::Spawn, &task);
+        if task.notify() {
+            self.inject(task, false);
+        }
+    }
+
+    // Wakerから呼ばれる再スケジュール。キューへ入れるときだけ参照を増やす
+    pub(crate) fn wake(&self, task: &SharedTask) {
+        self.record(EventKind::Wake, task);
+        if task.notify() {
+            self.inject(task.clone(), true);
+        }
+    }
+
+    // poll中にwakeされたタスクを、pollの後でキューへ戻す（NOTIFIEDは立っている）
+    pub(crate) fn reschedule(&self, task: SharedTask) {
+        self.inject(task, true);
+    }
+
+    fn inject(&self, task: SharedTask, woken: bool) {
+        task.mark_queued();
+        self.record(EventKind::Schedule, &task);
+        self.injector.push(Injected { task, woken });
+        // park()側のfenceと対になる。どちらかが必ず相手の書き込みを見る
+        fence(Ordering::SeqCst);
+        self.unpark_idle(1);
+    }
+
+    // スケジューラの順番で次のタスクを取り出す
+    pub(crate) fn next_task(&self) -> Option<SharedTask> {
+        self.with_scheduler(|scheduler| scheduler.pop())
+    }
+
+    // ロックを取らずに、injectorに何か入っていそうか確かめる
+    pub(crate) fn has_injected(&self) -> bool {
+        !self.injector.is_empty()
+    }
+
+    // 実行できるタスクがなければ、schedule()で起こされるまで眠る
+    pub(crate) fn park(&self) {
+        let me = thread::current();
+        self.idle.lock().unwrap().push(me.clone());
+        self.num_idle.fetch_add(1, Ordering::SeqCst);
+        fence(Ordering::SeqCst);
+
+        // 登録する直前に入ったタスクを取りこぼさないように確かめ直す
+        if self.has_injected() || !self.scheduler.lock().unwrap().is_empty() {
+            self.unregister(&me);
+            return;
+        }
+        if self.num_idle.load(Ordering::SeqCst) == self.workers.load(Ordering::SeqCst) {
+            // 最後のWorkerが眠る。止めた時計を進められるかDriverに確かめさせる
+            self.driver.notify_idle();
+        }
+        thread::park();
+        self.unregister(&me);
+    }
+
+    // タスクが完了したか、完了しないまま破棄された
+    pub(crate) fn release(&self, id: TaskId) {
+        self.tasks.remove(id);
+    }
+
+    // 新しいタスクを受け付けなくする。登録済みのタスクはそのまま実行を続ける
+    // ShutdownSignalを待っているタスクを起こす
+    pub(crate) fn close(&self) {
+        let signals = {
+            // poll_closedが判定してからWakerを登録するまでの間に取りこぼさない
+            let mut signals = self.signals.lock().unwrap();
+            self.closed.store(true, Ordering::SeqCst);
+            std::mem::take(&mut signals.wakers)
+        };
+        signals.into_values().for_each(Waker::wake);
+    }
+
+    // 停止が始まっていればReady。keyは登録したWakerを置き換えるために使う
+    pub(crate) fn poll_closed(&self, key: &mut Option<u64>, cx: &mut Context<'_>) -> Poll<()> {
+        let mut signals = self.signals.lock().unwrap();
+        if self.closed.load(Ordering::SeqCst) {
+            *key = None;
+            return Poll::Ready(());
+        }
+        let id = *key.get_or_insert_with(|| {
+            signals.next += 1;
+            signals.next
+        });
+        match signals.wakers.get_mut(&id) {
+            Some(waker) => waker.clone_from(cx.waker()),
+            None => {
+                signals.wakers.insert(id, cx.waker().clone());
+            }
+        }
+        Poll::Pending
+    }
+
+    pub(crate) fn remove_signal(&self, key: u64) {
+        self.signals.lock().unwrap().wakers.remove(&key);
+    }
+
+    // 全てのタスクが完了するか、timeoutが過ぎるまで待つ。完了したらtrue
+    pub(crate) fn wait_drained(&self, timeout: Duration) -> bool {
+        self.tasks.wait_empty(timeout)
+    }
+
+    // 最後の参照を落としたTaskのDropがreleaseを呼ぶので、ロックを外してから写し取る
+    pub(crate) fn dump(&self) -> Vec<TaskDump> {
+        let tasks = self.tasks.tasks();
+        let mut dump: Vec<_> = tasks.iter().map(|task| task.dump()).collect();
+        dump.sort_by_key(|task| task.id);
+        dump
+    }
+
+    // 残っているタスクを全て破棄し、JoinHandleにErr(Shutdown)を返す
+    // Workerが全て止まった後で呼ぶ
+    pub(crate) fn cancel_all(&self) {
+        let tasks = self.tasks.tasks();
+        join::shutting_down(|| {
+            // 待機中のタスクはその場で、キューにいるものは取り出してpollしたときに破棄される
+            tasks.iter().for_each(|task| task.abort());
+            let mut cx = Context::from_waker(Waker::noop());
+            while let Some(task) = self.next_task() {
+                let _ = task.poll(&mut cx);
+            }
+            drop(tasks);
+        });
+    }
+
+    pub(crate) fn on_poll_complete(&self, task: &SharedTask, elapsed: Duration) {
+        if !self.observes_polls {
+            return;
+        }
+        // poll中に自分でwakeしたタスクはもうinjectorにいる
+        // 先にスケジューラへ移すと古いレベルのまま積まれるので、pollの結果を反映してから移す
+        self.locked(|scheduler| {
+            scheduler.on_poll_complete(task, elapsed);
+            self.drain_injector(scheduler);
+        });
+    }
+
+    // キューの状態に、完了していないタスクの名前とlocalの数を添える
+    pub(crate) fn metrics(&self) -> SchedulerMetrics {
+        let mut metrics = self.with_scheduler(|scheduler| scheduler.metrics());
+        for task in self.tasks.tasks() {
+            if let Some(name) = task.shared_name() {
+                *metrics.named_tasks.entry(name.clone()).or_default() += 1;
+            }
+            metrics.local_tasks += task.is_local() as usize;
+        }
+        metrics
+    }
+
+    // injectorのタスクをスケジューラへ移してからfを呼ぶ
+    fn with_scheduler<R>(&self, f: impl FnOnce(&mut Box<dyn Scheduler + Send>) -> R) -> R {
+        self.locked(|scheduler| {
+            self.drain_injector(scheduler);
+            f(scheduler)
+        })
+    }
+
+    // スケジューラのロックを取ってfを呼ぶ
+    // 残ったタスクの数だけ、ロックを手放した後でまとめてWorkerを起こす
+    fn locked<R>(&self, f: impl FnOnce(&mut Box<dyn Scheduler + Send>) -> R) -> R {
+        let mut scheduler = self.scheduler.lock().unwrap();
+        let output = f(&mut scheduler);
+        let remaining = scheduler.len();
+        drop(scheduler);
+
+        if remaining > 0 {
+            self.unpark_idle(remaining);
+        }
+        output
+    }
+
+    fn drain_injector(&self, scheduler: &mut Box<dyn Scheduler + Send>) {
+        while let Some(Injected { task, woken }) = self.injector.pop() {
+            if woken {
+                scheduler.on_task_woken(&task);
+            }
+            scheduler.push(task);
+        }
+    }
+
+    fn unpark_idle(&self, n: usize) {
+        if self.num_idle.load(Ordering::SeqCst) == 0 {
+            return;
+        }
+        let threads: Vec<Thread> = {
+            let mut idle = self.idle.lock().unwrap();
+            let n = n.min(idle.len());
+            self.num_idle.fetch_sub(n, Ordering::SeqCst);
+            let at = idle.len() - n;
+            idle.drain(at..).collect()
+        };
+        threads.into_iter().for_each(|t| t.unpark());
+    }
+
+    // 起こされずに戻った場合は自分でidleから外す
+    fn unregister(&self, me: &Thread) {
+        let mut idle = self.idle.lock().unwrap();
+        if let Some(pos) = idle.iter().position(|t| t.id() == me.id()) {
+            idle.swap_remove(pos);
+            self.num_idle.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod test;
@@ -0,0 +1,162 @@
+use crate::engine::schedule::Scheduler;
+use crate::engine::schedule::fifo::Fifo;
+use crate::engine::schedule::mlfq::Mlfq;
+use crate::engine::task::{Attributes, SharedTask, Task};
+use crate::engine::waker;
+use crate::time::driver::Driver;
+
+use super::Dispatcher;
+use std::future::poll_fn;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::task::{Context, Poll};
+use std::thread;
+use std::time::Duration;
+
+fn task() -> SharedTask {
+    Task::from_future(async {}, Attributes::default())
+}
+
+#[test]
+fn take_tasks_in_scheduler_order() {
+    let task1 = task();
+    let task2 = task();
+    let dispatcher = Arc::new(Dispatcher::new(
+        Box::new(Fifo::new()),
+        Driver::global().clone(),
+    ));
+
+    dispatcher.schedule(task1.clone());
+    dispatcher.schedule(task2.clone());
+    assert_eq!(dispatcher.metrics().queue_depths, vec![2]);
+
+    let retrieved_task = dispatcher.next_task().expect("Task should be present");
+    assert!(SharedTask::ptr_eq(&task1, &retrieved_task));
+    assert!(retrieved_task.state().is_notified());
+
+    let retrieved_task = dispatcher.next_task().expect("Task should be present");
+    assert!(SharedTask::ptr_eq(&task2, &retrieved_task));
+    assert!(dispatcher.next_task().is_none());
+}
+
+#[test]
+fn park_returns_immediately_when_work_is_queued() {
+    let dispatcher = Arc::new(Dispatcher::new(
+        Box::new(Fifo::new()),
+        Driver::global().clone(),
+    ));
+    dispatcher.schedule(task());
+
+    dispatcher.park();
+    assert!(dispatcher.next_task().is_some());
+}
+
+#[test]
+fn schedule_unparks_idle_worker() {
+    let dispatcher = Arc::new(Dispatcher::new(
+        Box::new(Fifo::new()),
+        Driver::global().clone(),
+    ));
+    let woke = Arc::new(AtomicBool::new(false));
+
+    let worker = {
+        let dispatcher = dispatcher.clone();
+        let woke = woke.clone();
+        thread::spawn(move || {
+            // parkは見かけ上起きることがあるので、タスクが取れるまで眠り直す
+            while dispatcher.next_task().is_none() {
+                dispatcher.park();
+            }
+            woke.store(true, Ordering::SeqCst);
+        })
+    };
+
+    thread::sleep(Duration::from_millis(20));
+    assert!(!woke.load(Ordering::SeqCst));
+    dispatcher.schedule(task());
+    worker.join().unwrap();
+    assert!(woke.load(Ordering::SeqCst));
+}
+
+// on_poll_completeが呼ばれた回数を数えるスケジューラ
+struct Counting {
+    inner: Fifo,
+    observes: bool,
+    polls: Arc<AtomicUsize>,
+}
+
+impl Scheduler for Counting {
+    fn push(&mut self, task: SharedTask) {
+        self.inner.push(task);
+    }
+
+    fn pop(&mut self) -> Option<SharedTask> {
+        self.inner.pop()
+    }
+
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    fn on_poll_complete(&mut self, _task: &SharedTask, _elapsed: Duration) {
+        self.polls.fetch_add(1, Ordering::SeqCst);
+    }
+
+    fn observes_polls(&self) -> bool {
+        self.observes
+    }
+}
+
+#[test]
+fn poll_complete_reaches_only_observing_schedulers() {
+    for observes in [false, true] {
+        let polls = Arc::new(AtomicUsize::new(0));
+        let dispatcher = Arc::new(Dispatcher::new(
+            Box::new(Counting {
+                inner: Fifo::new(),
+                observes,
+                polls: polls.clone(),
+            }),
+            Driver::global().clone(),
+        ));
+        dispatcher.on_poll_complete(&task(), Duration::from_millis(1));
+        assert_eq!(polls.load(Ordering::SeqCst), usize::from(observes));
+    }
+}
+
+#[test]
+fn block_on_future_keeps_dispatcher_busy() {
+    let dispatcher = Dispatcher::new(Box::new(Fifo::new()), Driver::global().clone());
+    assert!(dispatcher.is_idle());
+
+    // Workerがいなくても、block_onのFutureが動いている間は時計を進めない
+    dispatcher.block_on_busy();
+    assert!(!dispatcher.is_idle());
+    dispatcher.block_on_idle();
+    assert!(dispatcher.is_idle());
+}
+
+#[test]
+fn self_woken_task_is_requeued_at_its_new_level() {
+    let dispatcher = Arc::new(Dispatcher::new(
+        Box::new(Mlfq::new().with_quantum(Duration::from_millis(1))),
+        Driver::global().clone(),
+    ));
+    let task = Task::from_future(
+        poll_fn(|cx| {
+            cx.waker().wake_by_ref();
+            Poll::<()>::Pending
+        }),
+        Attributes::default(),
+    );
+    dispatcher.schedule(task.clone());
+    let task = dispatcher.next_task().unwrap();
+
+    // poll中に自分でwakeしたので、poll後にinjectorへ戻っている
+    let waker = waker::waker_ref(&task);
+    let _ = task.poll(&mut Context::from_waker(&waker));
+    dispatcher.on_poll_complete(&task, Duration::from_millis(5));
+
+    // quantumを使い切ったので、最上位ではなく1段下のキューに入る
+    assert_eq!(dispatcher.metrics().queue_depths, vec![0, 1, 0]);
+}
@@ -0,0 +1,162 @@
+use std::cell::RefCell;
+use std::future::Future;
+use std::sync::Arc;
+
+use crate::engine::dispatch::SharedDispatcher;
+use crate::engine::join::JoinHandle;
+use crate::engine::schedule::SchedulerMetrics;
+use crate::engine::schedule::fair_share::GroupId;
+use crate::engine::schedule::priority::Priority;
+use crate::engine::shutdown::ShutdownSignal;
+use crate::engine::task::{Attributes, SharedTask, Task, TaskBuilder, TaskDump};
+use crate::time::driver::{self, Driver};
+use crate::utils::channel::{Receiver, channel};
+
+thread_local! {
+    static CURRENT: RefCell<Option<Handle>> = const { RefCell::new(None) };
+}
+
+// Engineへの参照。タスクの中からでも新しいタスクを登録できる
+#[derive(Clone)]
+pub struct Handle {
+    dispatcher: SharedDispatcher,
+}
+
+impl Handle {
+    pub(crate) fn new(dispatcher: SharedDispatcher) -> Self {
+        Self { dispatcher }
+    }
+
+    // Workerスレッド上、またはenter()の範囲内でのみ取得できる
+    pub fn current() -> Self {
+        Self::try_current().expect("must be called from the context of an Engine")
+    }
+
+    pub fn try_current() -> Option<Self> {
+        CURRENT.with(|current| current.borrow().clone())
+    }
+
+    // タスクの登録先とともに、タイマーもこのEngineのものを使う
+    pub fn enter(&self) -> EnterGuard {
+        let driver = self.dispatcher.driver().enter();
+        let prev = CURRENT.with(|current| current.borrow_mut().replace(self.clone()));
+        EnterGuard {
+            prev,
+            _driver: driver,
+        }
+    }
+
+    pub fn reserve<V, W>(&self, task: V, deadline: Option<u64>) -> Receiver<W>
+    where
+        V: Future<Output = W> + Send + 'static,
+        W: Clone + Send + Unpin + 'static,
+    {
+        self.reserve_with(task, Attributes::with_deadline(deadline))
+    }
+
+    pub fn reserve_with_priority<V, W>(&self, task: V, priority: Priority) -> Receiver<W>
+    where
+        V: Future<Output = W> + Send + 'static,
+        W: Clone + Send + Unpin + 'static,
+    {
+        self.reserve_with(
+            task,
+            Attributes {
+                priority,
+                ..Default::default()
+            },
+        )
+    }
+
+    pub fn reserve_in_group<V, W>(&self, task: V, group: GroupId) -> Receiver<W>
+    where
+        V: Future<Output = W> + Send + 'static,
+        W: Clone + Send + Unpin + 'static,
+    {
+        self.reserve_with(
+            task,
+            Attributes {
+                group,
+                ..Default::default()
+            },
+        )
+    }
+
+    fn reserve_with<V, W>(&self, task: V, attributes: Attributes) -> Receiver<W>
+    where
+        V: Future<Output = W> + Send + 'static,
+        W: Clone + Send + Unpin + 'static,
+    {
+        let (sender, receiver) = channel();
+        let task = Task::from_future(
+            async move {
+                sender.send(task.await);
+            },
+            attributes,
+        );
+        self.schedule(task);
+        receiver
+    }
+
+    pub fn spawn<F>(&self, future: F) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        self.spawn_with(future, Attributes::default())
+    }
+
+    // 名前や優先度を付けてこのEngineに登録する
+    pub fn task_builder(&self) -> TaskBuilder {
+        TaskBuilder::on(self.clone())
+    }
+
+    pub(crate) fn spawn_with<F>(&self, future: F, attributes: Attributes) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        let (future, completer) = JoinHandle::wrap(future);
+        let task = Task::from_future(future, attributes);
+        self.schedule(task.clone());
+        completer.into_handle(task)
+    }
+
+    pub fn scheduler_metrics(&self) -> SchedulerMetrics {
+        self.dispatcher.metrics()
+    }
+
+    // まだ完了していないタスクの一覧
+    pub fn dump(&self) -> Vec<TaskDump> {
+        self.dispatcher.dump()
+    }
+
+    // Engineの停止が始まったら完了する
+    pub fn shutdown_signal(&self) -> ShutdownSignal {
+        ShutdownSignal::new(self.dispatcher.clone())
+    }
+
+    pub(crate) fn schedule(&self, task: SharedTask) {
+        self.dispatcher.schedule(task);
+    }
+
+    pub(crate) fn dispatcher(&self) -> &SharedDispatcher {
+        &self.dispatcher
+    }
+
+    pub(crate) fn driver(&self) -> &Arc<Driver> {
+        self.dispatcher.driver()
+    }
+}
+
+pub struct EnterGuard {
+    prev: Option<Handle>,
+    _driver: driver::EnterGuard,
+}
+
+impl Drop for EnterGuard {
+    fn drop(&mut self) {
+        let prev = self.prev.take();
+        CURRENT.with(|current| *current.borrow_mut() = prev);
+    }
+}
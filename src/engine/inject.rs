@@ -0,0 +1,160 @@
+use std::collections::VecDeque;
+use std::mem::MaybeUninit;
+
+use crate::loom::cell::UnsafeCell;
+use crate::loom::sync::Mutex;
+use crate::loom::sync::atomic::{AtomicUsize, Ordering};
+
+const DEFAULT_CAPACITY: usize = 1024;
+
+struct Slot<T> {
+    // pushできる周回ならpos、popできる周回ならpos + 1が入っている
+    sequence: AtomicUsize,
+    value: UnsafeCell<MaybeUninit<T>>,
+}
+
+// 複数スレッドから同時にpush/popできるキュー
+// 固定長のリングバッファ（Vyukov方式）はロックを取らず、溢れた分だけoverflowに入れる
+// overflowが空になるまでは新しい値もoverflowへ入れ、先に溢れた値を追い越さないようにする
+pub(crate) struct Injector<T> {
+    buffer: Box<[Slot<T>]>,
+    mask: usize,
+    head: AtomicUsize,
+    tail: AtomicUsize,
+    overflow: Mutex<VecDeque<T>>,
+    // overflowを覗く前にロックなしで空か判定するためのカウンタ
+    overflow_len: AtomicUsize,
+}
+
+unsafe impl<T: Send> Send for Injector<T> {}
+unsafe impl<T: Send> Sync for Injector<T> {}
+
+impl<T> Injector<T> {
+    pub(crate) fn new() -> Self {
+        Self::with_capacity(DEFAULT_CAPACITY)
+    }
+
+    pub(crate) fn with_capacity(capacity: usize) -> Self {
+        let capacity = capacity.max(2).next_power_of_two();
+        let buffer = (0..capacity)
+            .map(|i| Slot {
+                sequence: AtomicUsize::new(i),
+                value: UnsafeCell::new(MaybeUninit::uninit()),
+            })
+            .collect();
+        Self {
+            buffer,
+            mask: capacity - 1,
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+            overflow: Mutex::new(VecDeque::new()),
+            overflow_len: AtomicUsize::new(0),
+        }
+    }
+
+    pub(crate) fn push(&self, value: T) {
+        let value = if self.overflow_len.load(Ordering::Acquire) == 0 {
+            match self.try_push(value) {
+                Ok(()) => return,
+                Err(value) => value,
+            }
+        } else {
+            value
+        };
+        let mut overflow = self.overflow.lock().unwrap();
+        overflow.push_back(value);
+        // ロックを持ったまま増やし、次のpushが必ずoverflowを見るようにする
+        self.overflow_len.fetch_add(1, Ordering::Release);
+    }
+
+    pub(crate) fn pop(&self) -> Option<T> {
+        self.try_pop().or_else(|| {
+            if self.overflow_len.load(Ordering::Acquire) == 0 {
+                return None;
+            }
+            let value = self.overflow.lock().unwrap().pop_front()?;
+            self.overflow_len.fetch_sub(1, Ordering::Release);
+            Some(value)
+        })
+    }
+
+    // 同時に操作されている間はおおよその値になる
+    // headを先に読むので、間にpopが進んでもtailより大きくはならない。念のため容量で頭打ちにする
+    pub(crate) fn len(&self) -> usize {
+        let head = self.head.load(Ordering::Acquire);
+        let tail = self.tail.load(Ordering::Acquire);
+        let ring = tail.wrapping_sub(head).min(self.mask + 1);
+        ring + self.overflow_len.load(Ordering::Acquire)
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn try_push(&self, value: T) -> Result<(), T> {
+        let mut pos = self.tail.load(Ordering::Relaxed);
+        loop {
+            let slot = &self.buffer[pos & self.mask];
+            let sequence = slot.sequence.load(Ordering::Acquire);
+            match (sequence as isize).wrapping_sub(pos as isize) {
+                0 => match self.tail.compare_exchange_weak(
+                    pos,
+                    pos.wrapping_add(1),
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => {
+                        // CASに勝ったスレッドだけがこのスロットに書き込める
+                        slot.value.with_mut(|v| unsafe { (*v).write(value) });
+                        slot.sequence.store(pos.wrapping_add(1), Ordering::Release);
+                        return Ok(());
+                    }
+                    Err(actual) => pos = actual,
+                },
+                // 1周前の値がまだ取り出されていない（満杯）
+                diff if diff < 0 => return Err(value),
+                _ => pos = self.tail.load(Ordering::Relaxed),
+            }
+        }
+    }
+
+    fn try_pop(&self) -> Option<T> {
+        let mut pos = self.head.load(Ordering::Relaxed);
+        loop {
+            let slot = &self.buffer[pos & self.mask];
+            let sequence = slot.sequence.load(Ordering::Acquire);
+            match (sequence as isize).wrapping_sub(pos.wrapping_add(1) as isize) {
+                0 => match self.head.compare_exchange_weak(
+                    pos,
+                    pos.wrapping_add(1),
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => {
+                        let value = slot.value.with_mut(|v| unsafe { (*v).assume_init_read() });
+                        // 次の周回のpushに明け渡す
+                        slot.sequence
+                            .store(pos.wrapping_add(self.mask + 1), Ordering::Release);
+                        return Some(value);
+                    }
+                    Err(actual) => pos = actual,
+                },
+                // まだ書き込まれていない（空）
+                diff if diff < 0 => return None,
+                _ => pos = self.head.load(Ordering::Relaxed),
+            }
+        }
+    }
+}
+
+impl<T> Drop for Injector<T> {
+    fn drop(&mut self) {
+        while self.try_pop().is_some() {}
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod test;
+
+#[cfg(all(test, loom))]
+mod model;
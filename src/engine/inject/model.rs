@@ -0,0 +1,49 @@
+use std::sync::Arc;
+
+use loom::thread;
+
+use super::Injector;
+
+#[test]
+fn concurrent_push_pop_keeps_every_value() {
+    loom::model(|| {
+        // 容量2なので、3つ目はoverflowへ入る
+        let injector = Arc::new(Injector::with_capacity(2));
+        injector.push(0);
+
+        let pushers: Vec<_> = (1..3)
+            .map(|i| {
+                let injector = injector.clone();
+                thread::spawn(move || injector.push(i))
+            })
+            .collect();
+        let first = injector.pop();
+        pushers.into_iter().for_each(|t| t.join().unwrap());
+
+        let mut values: Vec<_> = first.into_iter().collect();
+        while let Some(v) = injector.pop() {
+            values.push(v);
+        }
+        values.sort();
+        assert_eq!(values, vec![0, 1, 2]);
+    });
+}
+
+#[test]
+fn values_from_one_producer_stay_in_order_across_overflow() {
+    loom::model(|| {
+        let injector = Arc::new(Injector::with_capacity(2));
+        let producer = {
+            let injector = injector.clone();
+            thread::spawn(move || (0..3).for_each(|i| injector.push(i)))
+        };
+        let first = injector.pop();
+        producer.join().unwrap();
+
+        let mut values: Vec<_> = first.into_iter().collect();
+        while let Some(v) = injector.pop() {
+            values.push(v);
+        }
+        assert_eq!(values, vec![0, 1, 2]);
+    });
+}
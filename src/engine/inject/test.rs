@@ -0,0 +1,136 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use super::Injector;
+
+#[test]
+fn pop_in_push_order() {
+    let injector = Injector::with_capacity(4);
+    (0..4).for_each(|i| injector.push(i));
+
+    (0..4).for_each(|i| assert_eq!(injector.pop(), Some(i)));
+    assert_eq!(injector.pop(), None);
+}
+
+#[test]
+fn spill_into_overflow_when_full() {
+    let injector = Injector::with_capacity(2);
+    (0..5).for_each(|i| injector.push(i));
+
+    let mut popped = Vec::new();
+    while let Some(i) = injector.pop() {
+        popped.push(i);
+    }
+    assert_eq!(popped, vec![0, 1, 2, 3, 4]);
+}
+
+#[test]
+fn newer_values_do_not_overtake_overflow() {
+    let injector = Injector::with_capacity(2);
+    (0..3).for_each(|i| injector.push(i));
+    // リングに空きができても、2がoverflowに残っている間は3もoverflowへ入る
+    assert_eq!(injector.pop(), Some(0));
+    injector.push(3);
+    injector.push(4);
+
+    let mut popped = Vec::new();
+    while let Some(i) = injector.pop() {
+        popped.push(i);
+    }
+    assert_eq!(popped, vec![1, 2, 3, 4]);
+    // overflowが空になれば、またリングを使う
+    injector.push(5);
+    assert_eq!(injector.len(), 1);
+    assert_eq!(injector.pop(), Some(5));
+}
+
+#[test]
+fn len_never_underflows_while_popping() {
+    let injector = Arc::new(Injector::with_capacity(64));
+    let poppers: Vec<_> = (0..2)
+        .map(|_| {
+            let injector = injector.clone();
+            thread::spawn(move || {
+                for _ in 0..10_000 {
+                    injector.push(0);
+                    let _ = injector.pop();
+                }
+            })
+        })
+        .collect();
+    for _ in 0..10_000 {
+        assert!(injector.len() <= 64);
+    }
+    poppers.into_iter().for_each(|t| t.join().unwrap());
+}
+
+#[test]
+fn reuse_slots_after_wrapping_around() {
+    let injector = Injector::with_capacity(2);
+    for i in 0..10 {
+        injector.push(i);
+        assert_eq!(injector.pop(), Some(i));
+    }
+}
+
+#[test]
+fn drop_values_left_in_queue() {
+    let value = Arc::new(());
+    let injector = Injector::with_capacity(2);
+    (0..3).for_each(|_| injector.push(value.clone()));
+    drop(injector);
+
+    assert_eq!(Arc::strong_count(&value), 1);
+}
+
+#[test]
+fn every_value_is_popped_once_across_threads() {
+    const PRODUCERS: usize = 4;
+    const CONSUMERS: usize = 4;
+    const PER_PRODUCER: usize = 10_000;
+
+    let injector = Arc::new(Injector::with_capacity(64));
+    let popped = Arc::new(AtomicUsize::new(0));
+
+    let producers: Vec<_> = (0..PRODUCERS)
+        .map(|p| {
+            let injector = injector.clone();
+            thread::spawn(move || {
+                for i in 0..PER_PRODUCER {
+                    injector.push(p * PER_PRODUCER + i);
+                }
+            })
+        })
+        .collect();
+
+    let consumers: Vec<_> = (0..CONSUMERS)
+        .map(|_| {
+            let injector = injector.clone();
+            let popped = popped.clone();
+            thread::spawn(move || {
+                let mut seen = Vec::new();
+                while popped.load(Ordering::Relaxed) < PRODUCERS * PER_PRODUCER {
+                    match injector.pop() {
+                        Some(value) => {
+                            seen.push(value);
+                            popped.fetch_add(1, Ordering::Relaxed);
+                        }
+                        None => thread::yield_now(),
+                    }
+                }
+                seen
+            })
+        })
+        .collect();
+
+    producers.into_iter().for_each(|p| p.join().unwrap());
+    let mut all = HashSet::new();
+    for consumer in consumers {
+        for value in consumer.join().unwrap() {
+            assert!(all.insert(value), "{value} popped twice");
+        }
+    }
+    assert_eq!(all.len(), PRODUCERS * PER_PRODUCER);
+}
@@ -0,0 +1,161 @@
+use std::cell::Cell;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+use crate::engine::coop;
+use crate::engine::task::{AbortHandle, JoinError, SharedTask, TaskId};
+
+thread_local! {
+    // Engineの停止でタスクを破棄している間だけtrue
+    static SHUTTING_DOWN: Cell<bool> = const { Cell::new(false) };
+}
+
+// fの中で破棄されたタスクは、JoinHandleにErr(Shutdown)を返す
+pub(crate) fn shutting_down<R>(f: impl FnOnce() -> R) -> R {
+    struct Reset(bool);
+
+    impl Drop for Reset {
+        fn drop(&mut self) {
+            SHUTTING_DOWN.with(|flag| flag.set(self.0));
+        }
+    }
+
+    let _reset = Reset(SHUTTING_DOWN.with(|flag| flag.replace(true)));
+    f()
+}
+
+// shutting_downの中で呼ばれているか
+pub(crate) fn is_shutting_down() -> bool {
+    SHUTTING_DOWN.with(Cell::get)
+}
+
+// 完了時にon_completeを呼ぶFutureで包む
+// 完了前に破棄された（abortされた）場合はErr(Cancelled)、Engineの停止ならErr(Shutdown)で呼ぶ
+pub(crate) fn with_completion<F, C>(future: F, on_complete: C) -> impl Future<Output = ()> + Send
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+    C: FnOnce(Result<F::Output, JoinError>) + Send + 'static,
+{
+    struct Guard<C: FnOnce(Result<T, JoinError>), T> {
+        on_complete: Option<C>,
+        _output: std::marker::PhantomData<fn(T)>,
+    }
+
+    impl<C: FnOnce(Result<T, JoinError>), T> Drop for Guard<C, T> {
+        fn drop(&mut self) {
+            if let Some(on_complete) = self.on_complete.take() {
+                let error = if is_shutting_down() {
+                    JoinError::Shutdown
+                } else {
+                    JoinError::Cancelled
+                };
+                on_complete(Err(error));
+            }
+        }
+    }
+
+    let mut guard = Guard {
+        on_complete: Some(on_complete),
+        _output: std::marker::PhantomData,
+    };
+    async move {
+        let output = future.await;
+        if let Some(on_complete) = guard.on_complete.take() {
+            on_complete(Ok(output));
+        }
+    }
+}
+
+struct JoinState<T> {
+    output: Option<Result<T, JoinError>>,
+    waker: Option<Waker>,
+}
+
+// タスクの結果を受け取るFuture。破棄してもタスクは止まらない
+pub struct JoinHandle<T> {
+    state: Arc<Mutex<JoinState<T>>>,
+    task: SharedTask,
+}
+
+impl<T: Send + 'static> JoinHandle<T> {
+    // タスク本体と、その結果を受け取るJoinHandleを作る
+    pub(crate) fn wrap<F>(future: F) -> (impl Future<Output = ()> + Send, JoinCompleter<T>)
+    where
+        F: Future<Output = T> + Send + 'static,
+    {
+        let state = Arc::new(Mutex::new(JoinState {
+            output: None,
+            waker: None,
+        }));
+        let completer = JoinCompleter {
+            state: state.clone(),
+        };
+        let future = with_completion(future, move |output| {
+            let waker = {
+                let mut state = state.lock().unwrap();
+                state.output = Some(output);
+                state.waker.take()
+            };
+            if let Some(waker) = waker {
+                waker.wake();
+            }
+        });
+        (future, completer)
+    }
+}
+
+impl<T> JoinHandle<T> {
+    pub fn id(&self) -> TaskId {
+        self.task.id()
+    }
+
+    pub fn abort(&self) {
+        self.task.abort();
+    }
+
+    pub fn abort_handle(&self) -> AbortHandle {
+        AbortHandle::new(self.task.clone())
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.task.is_finished()
+    }
+}
+
+impl<T> Unpin for JoinHandle<T> {}
+
+impl<T> Future for JoinHandle<T> {
+    type Output = Result<T, JoinError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if coop::poll_proceed(cx).is_pending() {
+            return Poll::Pending;
+        }
+        let mut state = self.state.lock().unwrap();
+        if let Some(output) = state.output.take() {
+            return Poll::Ready(output);
+        }
+        match &mut state.waker {
+            Some(waker) => waker.clone_from(cx.waker()),
+            None => state.waker = Some(cx.waker().clone()),
+        }
+        Poll::Pending
+    }
+}
+
+// Taskを作った後でJoinHandleに結びつけるための中間値
+pub(crate) struct JoinCompleter<T> {
+    state: Arc<Mutex<JoinState<T>>>,
+}
+
+impl<T> JoinCompleter<T> {
+    pub(crate) fn into_handle(self, task: SharedTask) -> JoinHandle<T> {
+        JoinHandle {
+            state: self.state,
+            task,
+        }
+    }
+}
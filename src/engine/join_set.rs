@@ -0,0 +1,226 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+use crate::engine::handle::Handle;
+use crate::engine::join::{JoinHandle, with_completion};
+use crate::engine::task::{AbortHandle, Attributes, JoinError, Task, TaskId};
+use crate::utils::mpsc::{self, TryRecvError};
+
+type Completed<T> = (TaskId, Result<T, JoinError>);
+
+// 登録したタスクを完了順に受け取る。破棄すると残りのタスクはabortされる
+pub struct JoinSet<T> {
+    tasks: HashMap<TaskId, AbortHandle>,
+    sender: mpsc::Sender<Completed<T>>,
+    receiver: mpsc::Receiver<Completed<T>>,
+}
+
+impl<T: Send + 'static> JoinSet<T> {
+    pub fn new() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        Self {
+            tasks: HashMap::new(),
+            sender,
+            receiver,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.tasks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tasks.is_empty()
+    }
+
+    pub fn spawn<F>(&mut self, future: F) -> AbortHandle
+    where
+        F: Future<Output = T> + Send + 'static,
+    {
+        self.spawn_on(future, &Handle::current())
+    }
+
+    pub fn spawn_on<F>(&mut self, future: F, handle: &Handle) -> AbortHandle
+    where
+        F: Future<Output = T> + Send + 'static,
+    {
+        // 結果とタスクを対応づけるため、IDを先に決めておく
+        let id = TaskId::next();
+        let sender = self.sender.clone();
+        let future = with_completion(future, move |output| {
+            let _ = sender.send((id, output));
+        });
+
+        let task = Task::with_id(id, future, Attributes::default());
+        let abort = AbortHandle::new(task.clone());
+        self.tasks.insert(id, abort.clone());
+        handle.schedule(task);
+        abort
+    }
+
+    // 完了したタスクの結果を1つ返す。タスクが残っていなければNone
+    pub async fn join_next(&mut self) -> Option<Result<T, JoinError>> {
+        std::future::poll_fn(|cx| self.poll_join_next(cx)).await
+    }
+
+    pub fn try_join_next(&mut self) -> Option<Result<T, JoinError>> {
+        loop {
+            match self.receiver.try_recv() {
+                Ok((id, output)) => {
+                    if self.tasks.remove(&id).is_some() {
+                        return Some(output);
+                    }
+                }
+                Err(TryRecvError::Empty) | Err(TryRecvError::Disconnected) => return None,
+            }
+        }
+    }
+
+    pub fn poll_join_next(&mut self, cx: &mut Context<'_>) -> Poll<Option<Result<T, JoinError>>> {
+        loop {
+            if self.tasks.is_empty() {
+                return Poll::Ready(None);
+            }
+            match self.receiver.poll_recv(cx) {
+                Poll::Ready(Some((id, output))) => {
+                    if self.tasks.remove(&id).is_some() {
+                        return Poll::Ready(Some(output));
+                    }
+                }
+                // 自身がSenderを持っているので閉じることはない
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+
+    pub fn abort_all(&mut self) {
+        self.tasks.values().for_each(AbortHandle::abort);
+    }
+
+    // 全てabortし、完了を待つ
+    pub async fn shutdown(&mut self) {
+        self.abort_all();
+        while self.join_next().await.is_some() {}
+    }
+}
+
+impl<T: Send + 'static> Default for JoinSet<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Drop for JoinSet<T> {
+    fn drop(&mut self) {
+        self.tasks.values().for_each(AbortHandle::abort);
+    }
+}
+
+struct ScopeState {
+    remaining: usize,
+    waker: Option<Waker>,
+}
+
+// scope内で登録したタスクは、scopeが返る前に全て完了する
+// scopeに渡した関数の中で借りるだけなので、タスクへ持ち出してscopeが返った後に使うことはできない
+pub struct Scope {
+    handle: Handle,
+    state: Arc<Mutex<ScopeState>>,
+    children: Mutex<Vec<AbortHandle>>,
+}
+
+impl Scope {
+    pub fn spawn<F>(&self, future: F) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        self.state.lock().unwrap().remaining += 1;
+        let state = self.state.clone();
+        let (future, completer) = JoinHandle::wrap(future);
+        // JoinHandleへ結果を渡した後で、残りの子タスク数を減らす
+        let future = with_completion(future, move |_| {
+            let waker = {
+                let mut state = state.lock().unwrap();
+                state.remaining -= 1;
+                if state.remaining > 0 {
+                    return;
+                }
+                state.waker.take()
+            };
+            if let Some(waker) = waker {
+                waker.wake();
+            }
+        });
+
+        let task = Task::from_future(future, Attributes::default());
+        self.children
+            .lock()
+            .unwrap()
+            .push(AbortHandle::new(task.clone()));
+        self.handle.schedule(task.clone());
+        completer.into_handle(task)
+    }
+
+    fn poll_children(&self, cx: &mut Context<'_>) -> Poll<()> {
+        let mut state = self.state.lock().unwrap();
+        if state.remaining == 0 {
+            return Poll::Ready(());
+        }
+        match &mut state.waker {
+            Some(waker) => waker.clone_from(cx.waker()),
+            None => state.waker = Some(cx.waker().clone()),
+        }
+        Poll::Pending
+    }
+}
+
+// scope自体が途中で破棄された場合は、残りの子タスクをabortする
+struct ScopeGuard<'a> {
+    scope: &'a Scope,
+}
+
+impl Drop for ScopeGuard<'_> {
+    fn drop(&mut self) {
+        self.scope
+            .children
+            .lock()
+            .unwrap()
+            .iter()
+            .for_each(AbortHandle::abort);
+    }
+}
+
+pub async fn scope<F, R>(f: F) -> R
+where
+    F: for<'s> AsyncFnOnce(&'s Scope) -> R,
+{
+    scope_on(&Handle::current(), f).await
+}
+
+pub async fn scope_on<F, R>(handle: &Handle, f: F) -> R
+where
+    F: for<'s> AsyncFnOnce(&'s Scope) -> R,
+{
+    let scope = Scope {
+        handle: handle.clone(),
+        state: Arc::new(Mutex::new(ScopeState {
+            remaining: 0,
+            waker: None,
+        })),
+        children: Mutex::new(Vec::new()),
+    };
+    let guard = ScopeGuard { scope: &scope };
+
+    let output = f(&scope).await;
+    std::future::poll_fn(|cx| scope.poll_children(cx)).await;
+    // 全て完了しているのでabortは何もしない
+    drop(guard);
+    output
+}
+
+#[cfg(all(test, not(loom)))]
+mod test;
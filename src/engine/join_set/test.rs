@@ -0,0 +1,141 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use crate::engine::schedule::fifo::Fifo;
+use crate::engine::{Engine, JoinSet, block_on, scope, scope_on};
+use crate::time::sleep;
+
+fn engine() -> Engine {
+    Engine::new(2, Fifo::new())
+}
+
+#[test]
+fn join_next_yields_in_completion_order() {
+    let engine = engine();
+    let mut set = JoinSet::new();
+
+    for ms in [30u64, 10, 20] {
+        set.spawn_on(
+            async move {
+                sleep(Duration::from_millis(ms)).await;
+                ms
+            },
+            engine.handle(),
+        );
+    }
+    assert_eq!(set.len(), 3);
+
+    let results = block_on(async {
+        let mut results = Vec::new();
+        while let Some(res) = set.join_next().await {
+            results.push(res.unwrap());
+        }
+        results
+    });
+
+    assert_eq!(results, vec![10, 20, 30]);
+    assert!(set.is_empty());
+    engine.graceful_shutdown();
+}
+
+#[test]
+fn abort_all_reports_cancelled() {
+    let engine = engine();
+    let mut set = JoinSet::new();
+
+    for _ in 0..3 {
+        set.spawn_on(sleep(Duration::from_secs(10)), engine.handle());
+    }
+    set.abort_all();
+
+    let results = block_on(async {
+        let mut results = Vec::new();
+        while let Some(res) = set.join_next().await {
+            results.push(res);
+        }
+        results
+    });
+
+    assert_eq!(results.len(), 3);
+    assert!(results.iter().all(|res| res.unwrap_err().is_cancelled()));
+    engine.graceful_shutdown();
+}
+
+#[test]
+fn drop_aborts_remaining_tasks() {
+    let engine = engine();
+    let finished = Arc::new(AtomicUsize::new(0));
+
+    let mut set = JoinSet::new();
+    for _ in 0..3 {
+        let finished = finished.clone();
+        set.spawn_on(
+            async move {
+                sleep(Duration::from_millis(30)).await;
+                finished.fetch_add(1, Ordering::SeqCst);
+            },
+            engine.handle(),
+        );
+    }
+    drop(set);
+
+    block_on(sleep(Duration::from_millis(60)));
+    assert_eq!(finished.load(Ordering::SeqCst), 0);
+    engine.graceful_shutdown();
+}
+
+#[test]
+fn scope_waits_for_all_children() {
+    let engine = engine();
+    let finished = Arc::new(AtomicUsize::new(0));
+
+    let cloned = finished.clone();
+    let output = block_on(scope_on(engine.handle(), async move |s| {
+        for ms in [20u64, 10, 30] {
+            let finished = cloned.clone();
+            // JoinHandleは待たずに捨てる
+            s.spawn(async move {
+                sleep(Duration::from_millis(ms)).await;
+                finished.fetch_add(1, Ordering::SeqCst);
+            });
+        }
+        "body done"
+    }));
+
+    assert_eq!(output, "body done");
+    assert_eq!(finished.load(Ordering::SeqCst), 3);
+    engine.graceful_shutdown();
+}
+
+#[test]
+fn scoped_join_handle_returns_output() {
+    let engine = engine();
+
+    let sum = block_on(scope_on(engine.handle(), async |s| {
+        let a = s.spawn(async { 1 });
+        let b = s.spawn(async { 2 });
+        a.await.unwrap() + b.await.unwrap()
+    }));
+
+    assert_eq!(sum, 3);
+    engine.graceful_shutdown();
+}
+
+#[test]
+fn scope_runs_inside_spawned_task() {
+    let engine = engine();
+
+    // Scopeは借りるだけなので、scopeを待つタスクもSendのまま登録できる
+    let handle = engine.spawn(async {
+        scope(async |s| {
+            let a = s.spawn(async { 20 });
+            let b = s.spawn(async { 22 });
+            a.await.unwrap() + b.await.unwrap()
+        })
+        .await
+    });
+
+    assert_eq!(block_on(handle).unwrap(), 42);
+    engine.graceful_shutdown();
+}
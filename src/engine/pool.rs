@@ -0,0 +1,346 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::engine::affinity::{self, Affinity};
+use crate::engine::handle::Handle;
+use crate::engine::worker::{Worker, WorkerId, WorkerMetrics, WorkerStats};
+
+// Workerの数を負荷に合わせて増減させる方針
+// 実行待ちのタスクが溜まり、スケジュールの遅延も大きい状態が続いたらWorkerを1つ増やす
+// keep_aliveの間ずっと眠っていたWorkerは退役させる
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScalingPolicy {
+    min_workers: usize,
+    max_workers: usize,
+    queue_depth: usize,
+    schedule_latency: Duration,
+    sustain: Duration,
+    keep_alive: Duration,
+    interval: Duration,
+}
+
+impl ScalingPolicy {
+    pub fn new(min_workers: usize, max_workers: usize) -> Self {
+        // Workerが1つもないと遅延を測れず、増やすきっかけがなくなる
+        assert!(min_workers > 0, "min_workers must be at least 1");
+        assert!(
+            min_workers <= max_workers,
+            "min_workers must not exceed max_workers"
+        );
+        Self {
+            min_workers,
+            max_workers,
+            queue_depth: 1,
+            schedule_latency: Duration::from_millis(1),
+            sustain: Duration::from_millis(100),
+            keep_alive: Duration::from_secs(10),
+            interval: Duration::from_millis(10),
+        }
+    }
+
+    // 実行待ちのタスクがこの数以上あれば混んでいるとみなす
+    pub fn with_queue_depth(mut self, queue_depth: usize) -> Self {
+        self.queue_depth = queue_depth;
+        self
+    }
+
+    // キューに入ってからpollされるまでの平均時間がこれ以上なら混んでいるとみなす
+    pub fn with_schedule_latency(mut self, latency: Duration) -> Self {
+        self.schedule_latency = latency;
+        self
+    }
+
+    // 混んだ状態がこの時間続いたらWorkerを増やす
+    pub fn with_sustain(mut self, sustain: Duration) -> Self {
+        self.sustain = sustain;
+        self
+    }
+
+    pub fn with_keep_alive(mut self, keep_alive: Duration) -> Self {
+        self.keep_alive = keep_alive;
+        self
+    }
+
+    // 負荷を確かめる間隔
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    pub fn min_workers(&self) -> usize {
+        self.min_workers
+    }
+
+    pub fn max_workers(&self) -> usize {
+        self.max_workers
+    }
+}
+
+// Engineが持つWorkerスレッドの集まり。実行中でも増減できる
+pub(crate) struct Pool {
+    handle: Handle,
+    shutdown: Arc<AtomicBool>,
+    // WorkerIdの採番。thread-per-coreでは全てのコアで共有する
+    ids: Arc<AtomicUsize>,
+    affinity: Affinity,
+    state: Mutex<State>,
+    // 動いているScalerの世代。変わったら古いScalerは止まる
+    generation: AtomicUsize,
+}
+
+struct State {
+    workers: Vec<WorkerThread>,
+    // 退役したWorkerの統計も残しておく
+    retired: Vec<Arc<WorkerStats>>,
+    scaler: Option<thread::JoinHandle<()>>,
+}
+
+struct WorkerThread {
+    stats: Arc<WorkerStats>,
+    thread: thread::JoinHandle<()>,
+}
+
+impl Pool {
+    pub(crate) fn new(handle: Handle, ids: Arc<AtomicUsize>, affinity: Affinity) -> Self {
+        Self {
+            handle,
+            shutdown: Arc::new(AtomicBool::new(false)),
+            ids,
+            affinity,
+            state: Mutex::new(State {
+                workers: Vec::new(),
+                retired: Vec::new(),
+                scaler: None,
+            }),
+            generation: AtomicUsize::new(0),
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.state.lock().unwrap().workers.len()
+    }
+
+    // 足りなければ新しいWorkerを起動し、多ければ新しいものから退役させる
+    pub(crate) fn resize(&self, n: usize) {
+        let mut state = self.state.lock().unwrap();
+        if self.shutdown.load(Ordering::Acquire) {
+            return;
+        }
+        while state.workers.len() < n {
+            self.spawn_worker(&mut state);
+        }
+        let retiring = state.workers.split_off(n);
+        retire(state, retiring);
+    }
+
+    pub(crate) fn retire_ids(&self, ids: &[WorkerId]) {
+        let mut state = self.state.lock().unwrap();
+        let (retiring, staying) = std::mem::take(&mut state.workers)
+            .into_iter()
+            .partition(|worker| ids.contains(&worker.stats.id()));
+        state.workers = staying;
+        retire(state, retiring);
+    }
+
+    // 退役したものを含め、ID順に並べる
+    pub(crate) fn metrics(&self) -> Vec<WorkerMetrics> {
+        let state = self.state.lock().unwrap();
+        let mut metrics: Vec<_> = state
+            .workers
+            .iter()
+            .map(|worker| worker.stats.metrics())
+            .chain(state.retired.iter().map(|stats| stats.metrics()))
+            .collect();
+        metrics.sort_by_key(|m| m.id);
+        metrics
+    }
+
+    // 前のScalerは止めてから、新しい方針で動かす
+    pub(crate) fn set_policy(self: &Arc<Self>, policy: Option<ScalingPolicy>) {
+        let generation = self.generation.fetch_add(1, Ordering::AcqRel) + 1;
+        let prev = self.state.lock().unwrap().scaler.take();
+        if let Some(prev) = prev {
+            prev.thread().unpark();
+            let _ = prev.join();
+        }
+        let Some(policy) = policy else {
+            return;
+        };
+
+        self.resize(self.len().clamp(policy.min_workers, policy.max_workers));
+        let pool = self.clone();
+        let scaler = thread::Builder::new()
+            .name("async-runtime-scaler".to_string())
+            .spawn(move || pool.run_scaler(generation, Scaler::new(policy)))
+            .expect("failed to spawn scaler thread");
+        self.state.lock().unwrap().scaler = Some(scaler);
+    }
+
+    // Scalerを止め、全てのWorkerが実行中のpollを終えて止まるまで待つ
+    pub(crate) fn stop(&self) {
+        self.generation.fetch_add(1, Ordering::AcqRel);
+        self.shutdown.store(true, Ordering::Release);
+        let scaler = self.state.lock().unwrap().scaler.take();
+        if let Some(scaler) = scaler {
+            scaler.thread().unpark();
+            let _ = scaler.join();
+        }
+
+        let workers = std::mem::take(&mut self.state.lock().unwrap().workers);
+        // まだ眠っていなくても、先にunparkしておけばparkはすぐに戻る
+        workers
+            .iter()
+            .for_each(|worker| worker.thread.thread().unpark());
+        for worker in workers {
+            let _ = worker.thread.join();
+        }
+    }
+
+    fn spawn_worker(&self, state: &mut State) {
+        let id = WorkerId::new(self.ids.fetch_add(1, Ordering::Relaxed));
+        let cpus = self.affinity.for_worker(id.index());
+        let stats = Arc::new(WorkerStats::new(id));
+        // スレッドが動き出す前にWorkerの数に入れておく
+        let worker = Worker::new(
+            self.handle.dispatcher().clone(),
+            self.shutdown.clone(),
+            stats.clone(),
+        );
+        let handle = self.handle.clone();
+        let thread = thread::Builder::new()
+            .name(format!("async-runtime-worker-{id}"))
+            .spawn(move || {
+                if let Some(cpus) = cpus
+                    && let Err(e) = affinity::pin_current(&cpus)
+                {
+                    eprintln!("failed to pin worker {id} to {cpus:?}: {e}");
+                }
+                // タスクの中からHandle::current()で参照できるようにする
+                let _guard = handle.enter();
+                worker.execute();
+            })
+            .expect("failed to spawn worker thread");
+        state.workers.push(WorkerThread { stats, thread });
+    }
+
+    fn run_scaler(&self, generation: usize, mut scaler: Scaler) {
+        loop {
+            thread::park_timeout(scaler.policy.interval);
+            if self.generation.load(Ordering::Acquire) != generation {
+                return;
+            }
+            let dispatcher = self.handle.dispatcher();
+            let sample = {
+                let state = self.state.lock().unwrap();
+                Sample {
+                    workers: state.workers.len(),
+                    queue_depth: dispatcher.queue_depth(),
+                    latency: dispatcher.take_latency(),
+                    idle: state
+                        .workers
+                        .iter()
+                        .filter_map(|w| w.stats.idle_for().map(|idle| (w.stats.id(), idle)))
+                        .collect(),
+                }
+            };
+            match scaler.tick(Instant::now(), &sample) {
+                Decision::Grow => self.resize(sample.workers + 1),
+                Decision::Retire(ids) => self.retire_ids(&ids),
+                Decision::Keep => {}
+            }
+        }
+    }
+}
+
+// 止まるよう頼んでから起こし、ロックを外してから止まるのを待つ
+// 退役するWorkerのpollがPoolのロックを取っても、待っている側と取り合いにならない
+// 自分自身を退役させる場合は待たずに、実行中のpollが終わったら止まる
+fn retire(mut state: MutexGuard<'_, State>, workers: Vec<WorkerThread>) {
+    for worker in &workers {
+        worker.stats.request_retire();
+        worker.thread.thread().unpark();
+        state.retired.push(worker.stats.clone());
+    }
+    drop(state);
+    for worker in workers {
+        if worker.thread.thread().id() != thread::current().id() {
+            let _ = worker.thread.join();
+        }
+        worker.stats.mark_retired();
+    }
+}
+
+// Scalerが見る負荷の様子
+pub(crate) struct Sample {
+    pub(crate) workers: usize,
+    pub(crate) queue_depth: usize,
+    // 前回から今回までの平均のスケジュール遅延
+    pub(crate) latency: Option<Duration>,
+    // 眠っているWorkerと、眠り続けている時間
+    pub(crate) idle: Vec<(WorkerId, Duration)>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Decision {
+    Grow,
+    Retire(Vec<WorkerId>),
+    Keep,
+}
+
+// 負荷の様子からWorkerを増やすか減らすかを決める
+pub(crate) struct Scaler {
+    policy: ScalingPolicy,
+    // 混んだ状態が始まった時刻
+    congested_since: Option<Instant>,
+}
+
+impl Scaler {
+    pub(crate) fn new(policy: ScalingPolicy) -> Self {
+        Self {
+            policy,
+            congested_since: None,
+        }
+    }
+
+    pub(crate) fn tick(&mut self, now: Instant, sample: &Sample) -> Decision {
+        if sample.workers < self.policy.min_workers {
+            return Decision::Grow;
+        }
+
+        let congested = sample.queue_depth >= self.policy.queue_depth
+            && sample
+                .latency
+                .is_some_and(|latency| latency >= self.policy.schedule_latency);
+        if congested {
+            let since = *self.congested_since.get_or_insert(now);
+            if now.duration_since(since) >= self.policy.sustain
+                && sample.workers < self.policy.max_workers
+            {
+                self.congested_since = None;
+                return Decision::Grow;
+            }
+            return Decision::Keep;
+        }
+        self.congested_since = None;
+
+        // 新しく増やしたWorkerから退役させる
+        let mut idle: Vec<WorkerId> = sample
+            .idle
+            .iter()
+            .filter(|(_, idle)| *idle >= self.policy.keep_alive)
+            .map(|(id, _)| *id)
+            .collect();
+        idle.sort_by(|a, b| b.cmp(a));
+        idle.truncate(sample.workers.saturating_sub(self.policy.min_workers));
+        if idle.is_empty() {
+            Decision::Keep
+        } else {
+            Decision::Retire(idle)
+        }
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod test;
@@ -0,0 +1,220 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, mpsc};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::{Decision, Sample, Scaler, ScalingPolicy};
+use crate::engine::schedule::fifo::Fifo;
+use crate::engine::worker::WorkerId;
+use crate::engine::{Engine, block_on};
+
+fn policy() -> ScalingPolicy {
+    ScalingPolicy::new(1, 3)
+        .with_queue_depth(4)
+        .with_schedule_latency(Duration::from_millis(5))
+        .with_sustain(Duration::from_millis(50))
+        .with_keep_alive(Duration::from_secs(1))
+}
+
+fn sample(workers: usize, queue_depth: usize, latency_ms: u64) -> Sample {
+    Sample {
+        workers,
+        queue_depth,
+        latency: Some(Duration::from_millis(latency_ms)),
+        idle: Vec::new(),
+    }
+}
+
+// 条件が満たされるまで最大5秒待つ
+fn wait_until(mut f: impl FnMut() -> bool) -> bool {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while Instant::now() < deadline {
+        if f() {
+            return true;
+        }
+        thread::sleep(Duration::from_millis(5));
+    }
+    false
+}
+
+#[test]
+fn scaler_grows_only_after_sustained_congestion() {
+    let mut scaler = Scaler::new(policy());
+    let start = Instant::now();
+
+    assert_eq!(scaler.tick(start, &sample(1, 10, 10)), Decision::Keep);
+    let later = start + Duration::from_millis(60);
+    assert_eq!(scaler.tick(later, &sample(1, 10, 10)), Decision::Grow);
+    // 増やした後はもう一度sustainだけ待つ
+    assert_eq!(scaler.tick(later, &sample(2, 10, 10)), Decision::Keep);
+}
+
+#[test]
+fn scaler_needs_both_depth_and_latency() {
+    let mut scaler = Scaler::new(policy());
+    let start = Instant::now();
+    let later = start + Duration::from_millis(60);
+
+    // キューは長いが、すぐにpollされている
+    scaler.tick(start, &sample(1, 10, 1));
+    assert_eq!(scaler.tick(later, &sample(1, 10, 1)), Decision::Keep);
+    // 遅延は大きいが、キューは短い
+    scaler.tick(start, &sample(1, 1, 10));
+    assert_eq!(scaler.tick(later, &sample(1, 1, 10)), Decision::Keep);
+}
+
+#[test]
+fn scaler_respects_max_and_min_workers() {
+    let mut scaler = Scaler::new(policy());
+    let start = Instant::now();
+    scaler.tick(start, &sample(3, 10, 10));
+    let later = start + Duration::from_millis(60);
+    assert_eq!(scaler.tick(later, &sample(3, 10, 10)), Decision::Keep);
+
+    assert_eq!(scaler.tick(later, &sample(0, 0, 0)), Decision::Grow);
+}
+
+#[test]
+fn scaler_retires_newest_idle_workers_down_to_min() {
+    let mut scaler = Scaler::new(policy());
+    let idle = Duration::from_secs(2);
+    let sample = Sample {
+        workers: 3,
+        queue_depth: 0,
+        latency: None,
+        idle: vec![
+            (WorkerId::new(0), idle),
+            (WorkerId::new(1), idle),
+            (WorkerId::new(2), idle),
+        ],
+    };
+    assert_eq!(
+        scaler.tick(Instant::now(), &sample),
+        Decision::Retire(vec![WorkerId::new(2), WorkerId::new(1)])
+    );
+}
+
+#[test]
+fn scaler_keeps_workers_idle_shorter_than_keep_alive() {
+    let mut scaler = Scaler::new(policy());
+    let sample = Sample {
+        workers: 2,
+        queue_depth: 0,
+        latency: None,
+        idle: vec![(WorkerId::new(1), Duration::from_millis(10))],
+    };
+    assert_eq!(scaler.tick(Instant::now(), &sample), Decision::Keep);
+}
+
+#[test]
+fn set_worker_count_keeps_worker_identity() {
+    let engine = Engine::new(2, Fifo::new());
+    assert_eq!(block_on(engine.spawn(async { 1 })), Ok(1));
+
+    engine.set_worker_count(4);
+    assert_eq!(engine.worker_count(), 4);
+    engine.set_worker_count(1);
+    assert_eq!(engine.worker_count(), 1);
+    // 減らした後もタスクは実行される
+    assert_eq!(block_on(engine.spawn(async { 2 })), Ok(2));
+    engine.set_worker_count(2);
+
+    let metrics = engine.worker_metrics();
+    let ids: Vec<_> = metrics.iter().map(|m| m.id).collect();
+    assert_eq!(ids, (0..5).map(WorkerId::new).collect::<Vec<_>>());
+    // 新しく増やしたものから退役し、IDは使い回さない
+    let retired: Vec<_> = metrics.iter().map(|m| m.retired).collect();
+    assert_eq!(retired, vec![false, true, true, true, false]);
+    // pollの回数はpollを終えた後に数える
+    assert!(wait_until(|| {
+        let metrics = engine.worker_metrics();
+        metrics.iter().map(|m| m.polls).sum::<u64>() >= 2
+    }));
+
+    engine.shutdown_now();
+}
+
+#[test]
+fn scaling_policy_grows_under_load_and_retires_idle_workers() {
+    let engine = Engine::new(1, Fifo::new());
+    engine.set_scaling_policy(
+        ScalingPolicy::new(1, 3)
+            .with_queue_depth(1)
+            .with_schedule_latency(Duration::ZERO)
+            .with_sustain(Duration::ZERO)
+            .with_keep_alive(Duration::from_millis(50))
+            .with_interval(Duration::from_millis(5)),
+    );
+
+    // Workerを占有し続けるタスク
+    let handles: Vec<_> = (0..30)
+        .map(|_| engine.spawn(async { thread::sleep(Duration::from_millis(10)) }))
+        .collect();
+    assert!(wait_until(|| engine.worker_count() == 3));
+    for handle in handles {
+        block_on(handle).unwrap();
+    }
+
+    assert!(wait_until(|| engine.worker_count() == 1));
+    assert_eq!(
+        engine.worker_metrics().iter().filter(|m| m.retired).count(),
+        2
+    );
+
+    engine.clear_scaling_policy();
+    engine.shutdown_now();
+}
+
+#[test]
+fn retiring_worker_may_touch_pool_while_being_joined() {
+    let engine = Arc::new(Engine::new(2, Fifo::new()));
+    let started = Arc::new(AtomicUsize::new(0));
+
+    // 両方のWorkerを、Poolのロックを取るタスクで塞ぐ
+    let (go, tasks): (Vec<_>, Vec<_>) = (0..2)
+        .map(|_| {
+            let (go, wait) = mpsc::channel::<()>();
+            let engine = engine.clone();
+            let started = started.clone();
+            let task = engine.clone().spawn(async move {
+                started.fetch_add(1, Ordering::SeqCst);
+                wait.recv().unwrap();
+                engine.worker_metrics().len()
+            });
+            (go, task)
+        })
+        .unzip();
+    assert!(wait_until(|| started.load(Ordering::SeqCst) == 2));
+
+    // 退役を待っている間に、退役するWorkerのタスクがworker_metricsを呼ぶ
+    let (done, resized) = mpsc::channel();
+    let resizer = {
+        let engine = engine.clone();
+        thread::spawn(move || {
+            engine.set_worker_count(1);
+            done.send(()).unwrap();
+        })
+    };
+    thread::sleep(Duration::from_millis(20));
+    go.iter().for_each(|go| go.send(()).unwrap());
+
+    assert!(resized.recv_timeout(Duration::from_secs(5)).is_ok());
+    resizer.join().unwrap();
+    for task in tasks {
+        assert_eq!(block_on(task), Ok(2));
+    }
+    assert_eq!(engine.worker_count(), 1);
+}
+
+#[test]
+#[should_panic(expected = "at least 1")]
+fn set_worker_count_rejects_zero() {
+    let engine = Engine::new(1, Fifo::new());
+    engine.set_worker_count(0);
+}
+
+#[test]
+#[should_panic(expected = "min_workers must be at least 1")]
+fn scaling_policy_rejects_zero_min_workers() {
+    ScalingPolicy::new(0, 4);
+}
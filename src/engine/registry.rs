@@ -0,0 +1,90 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::engine::task::{SharedTask, TaskId, WeakTask};
+use crate::loom::sync::atomic::{AtomicUsize, Ordering};
+use crate::loom::sync::{Condvar, Mutex};
+
+// 分割数。spawnと完了が別々のタスクなら、ほとんど別のロックを取る
+const SHARDS: usize = 32;
+
+// 完了していないタスクの一覧。停止するときに待つか破棄する
+// spawnと完了のたびに触るので、idで分けたロックで持ち、数はアトミックに数える
+pub(crate) struct Registry {
+    shards: Box<[Mutex<HashMap<TaskId, WeakTask>>]>,
+    len: AtomicUsize,
+    // 空になるのを待つ側だけが使う
+    drain: Mutex<()>,
+    drained: Condvar,
+}
+
+impl Registry {
+    pub(crate) fn new() -> Self {
+        Self {
+            shards: (0..SHARDS).map(|_| Mutex::new(HashMap::new())).collect(),
+            len: AtomicUsize::new(0),
+            drain: Mutex::new(()),
+            drained: Condvar::new(),
+        }
+    }
+
+    fn shard(&self, id: TaskId) -> &Mutex<HashMap<TaskId, WeakTask>> {
+        &self.shards[id.as_u64() as usize % SHARDS]
+    }
+
+    pub(crate) fn insert(&self, task: &SharedTask) {
+        self.len.fetch_add(1, Ordering::SeqCst);
+        self.shard(task.id())
+            .lock()
+            .unwrap()
+            .insert(task.id(), SharedTask::downgrade(task));
+    }
+
+    // 一覧から外す。最後の1つなら空になるのを待っている側を起こす
+    pub(crate) fn remove(&self, id: TaskId) {
+        if self.shard(id).lock().unwrap().remove(&id).is_none() {
+            return;
+        }
+        if self.len.fetch_sub(1, Ordering::SeqCst) == 1 {
+            // ロックを取ってから起こし、待つ側が判定してから眠るまでの間に取りこぼさない
+            let _drain = self.drain.lock().unwrap();
+            self.drained.notify_all();
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.len.load(Ordering::SeqCst) == 0
+    }
+
+    // 空になるか、timeoutが過ぎるまで待つ。空になったらtrue
+    pub(crate) fn wait_empty(&self, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        let mut drain = self.drain.lock().unwrap();
+        while !self.is_empty() {
+            let now = Instant::now();
+            if now >= deadline {
+                return false;
+            }
+            drain = self.drained.wait_timeout(drain, deadline - now).unwrap().0;
+        }
+        true
+    }
+
+    // 残っているタスクへの参照を集める
+    pub(crate) fn tasks(&self) -> Vec<SharedTask> {
+        self.shards
+            .iter()
+            .flat_map(|shard| {
+                let shard = shard.lock().unwrap();
+                // 解放される前にremoveで一覧から外されるので、ロックを持っている間は指す先が残っている
+                shard
+                    .values()
+                    .filter_map(|task| unsafe { task.upgrade() })
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod test;
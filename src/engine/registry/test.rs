@@ -0,0 +1,45 @@
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use super::Registry;
+use crate::engine::task::{Attributes, SharedTask, Task};
+
+fn task() -> SharedTask {
+    Task::from_future(async {}, Attributes::default())
+}
+
+#[test]
+fn insert_and_remove_track_live_tasks() {
+    let registry = Registry::new();
+    let tasks: Vec<_> = (0..100).map(|_| task()).collect();
+    tasks.iter().for_each(|task| registry.insert(task));
+    assert_eq!(registry.tasks().len(), 100);
+
+    tasks.iter().for_each(|task| registry.remove(task.id()));
+    assert!(registry.is_empty());
+    assert!(registry.tasks().is_empty());
+
+    // 2回目は何もしない
+    registry.remove(tasks[0].id());
+    assert!(registry.is_empty());
+}
+
+#[test]
+fn wait_empty_wakes_on_last_removal() {
+    let registry = Arc::new(Registry::new());
+    let tasks: Vec<_> = (0..4).map(|_| task()).collect();
+    tasks.iter().for_each(|task| registry.insert(task));
+    assert!(!registry.wait_empty(Duration::ZERO));
+
+    let remover = {
+        let registry = registry.clone();
+        let ids: Vec<_> = tasks.iter().map(|task| task.id()).collect();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(10));
+            ids.into_iter().for_each(|id| registry.remove(id));
+        })
+    };
+    assert!(registry.wait_empty(Duration::from_secs(5)));
+    remover.join().unwrap();
+}
@@ -1,66 +1,84 @@
 pub mod deadline;
+pub mod fair_share;
 pub mod fifo;
+pub mod mlfq;
+pub mod priority;
+pub mod random;
 use crate::engine::task::SharedTask;
-use crate::engine::worker::WorkerInfo;
-use std::collections::VecDeque;
-use std::sync::mpsc::Receiver;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
 
-pub trait Scheduler {
-    // スケジューリングアルゴリズムの実装：タスクを登録
-    fn register(&mut self, task: SharedTask);
+// スケジューラ内部のキューの状態。キューが複数あるものはレベル順に並べる
+// 完了していないタスクのうち、名前の付いたものは名前ごとに、localなものはその数を数える
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SchedulerMetrics {
+    pub queue_depths: Vec<usize>,
+    pub named_tasks: BTreeMap<Arc<str>, usize>,
+    pub local_tasks: usize,
+}
 
-    // スケジューリングアルゴリズムの実装：次に実行するタスクを取得
-    fn take(&mut self) -> Option<SharedTask>;
+// 実行待ちキューの方針だけを決める。Workerへの配布はEngine側で行う
+pub trait Scheduler {
+    // 実行可能になったタスクをキューに入れる
+    fn push(&mut self, task: SharedTask);
 
-    // pending_workersへのアクセス
-    fn get_pending_workers(&mut self) -> &mut VecDeque<WorkerInfo>;
+    // 次に実行するタスクを取り出す
+    fn pop(&mut self) -> Option<SharedTask>;
 
-    // worker_receiverへのアクセス
-    fn get_worker_receiver(&mut self) -> &mut Receiver<WorkerInfo>;
+    // キューに入っているタスクの数
+    fn len(&self) -> usize;
 
-    // デフォルト実装：タスクをスケジュールし、通知
-    fn schedule(&mut self, task: SharedTask) {
-        eprintln!("[Scheduler::schedule] Setting task state to SCHEDULED");
-        task.set_state(crate::engine::task::SCHEDULED);
-        self.register(task);
-        self.notify();
+    fn is_empty(&self) -> bool {
+        self.len() == 0
     }
 
-    // デフォルト実装：新しいWorkerInfoを収集し、タスクを配布
-    fn notify(&mut self) {
-        // 新しい WorkerInfo を全て取得
-        while let Ok(worker_info) = self.get_worker_receiver().try_recv() {
-            self.get_pending_workers().push_back(worker_info);
-        }
+    // Wakerで起こされたタスクがpushされる直前に呼ばれる（新規のタスクでは呼ばれない）
+    fn on_task_woken(&mut self, _task: &SharedTask) {}
+
+    // Workerがタスクを1回pollし終えるたびに、かかった時間とともに呼ばれる
+    // observes_pollsがtrueを返すスケジューラでだけ呼ばれる
+    fn on_poll_complete(&mut self, _task: &SharedTask, _elapsed: Duration) {}
+
+    // on_poll_completeを使うならtrue。falseならpollのたびにスケジューラのロックを取らない
+    fn observes_polls(&self) -> bool {
+        false
+    }
 
-        // pending_workers からタスクを配布
-        while let Some(worker_info) = self.get_pending_workers().pop_front() {
-            if let Some(task) = self.take() {
-                let _ = worker_info.sender.send(task);
-                worker_info.t.unpark();
-            } else {
-                // タスクがないので WorkerInfo を戻す
-                self.get_pending_workers().push_front(worker_info);
-                break;
-            }
+    fn metrics(&self) -> SchedulerMetrics {
+        SchedulerMetrics {
+            queue_depths: vec![self.len()],
+            ..Default::default()
         }
     }
 }
 
 impl Scheduler for Box<dyn Scheduler + Send> {
-    fn register(&mut self, task: SharedTask) {
-        (**self).register(task)
+    fn push(&mut self, task: SharedTask) {
+        (**self).push(task)
+    }
+
+    fn pop(&mut self) -> Option<SharedTask> {
+        (**self).pop()
+    }
+
+    fn len(&self) -> usize {
+        (**self).len()
+    }
+
+    fn on_task_woken(&mut self, task: &SharedTask) {
+        (**self).on_task_woken(task)
     }
 
-    fn take(&mut self) -> Option<SharedTask> {
-        (**self).take()
+    fn on_poll_complete(&mut self, task: &SharedTask, elapsed: Duration) {
+        (**self).on_poll_complete(task, elapsed)
     }
 
-    fn get_pending_workers(&mut self) -> &mut VecDeque<WorkerInfo> {
-        (**self).get_pending_workers()
+    fn observes_polls(&self) -> bool {
+        (**self).observes_polls()
     }
 
-    fn get_worker_receiver(&mut self) -> &mut Receiver<WorkerInfo> {
-        (**self).get_worker_receiver()
+    fn metrics(&self) -> SchedulerMetrics {
+        (**self).metrics()
     }
 }
@@ -1,38 +1,32 @@
-use std::{collections::VecDeque, sync::mpsc::Receiver};
-
-use crate::engine::{schedule::Scheduler, task::SharedTask, worker::WorkerInfo};
+use crate::engine::{schedule::Scheduler, task::SharedTask};
 
 pub struct DeadLineScheduler {
-    receiver: Receiver<WorkerInfo>,
-    pending_workers: VecDeque<WorkerInfo>,
     heap: Heap<SharedTask>,
 }
 
 impl DeadLineScheduler {
-    pub fn new(receiver: Receiver<WorkerInfo>) -> Self {
-        Self {
-            receiver,
-            pending_workers: VecDeque::new(),
-            heap: Heap::new(),
-        }
+    pub fn new() -> Self {
+        Self { heap: Heap::new() }
+    }
+}
+
+impl Default for DeadLineScheduler {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
 impl Scheduler for DeadLineScheduler {
-    fn register(&mut self, task: SharedTask) {
+    fn push(&mut self, task: SharedTask) {
         self.heap.insert(task);
     }
 
-    fn take(&mut self) -> Option<SharedTask> {
+    fn pop(&mut self) -> Option<SharedTask> {
         self.heap.delete()
     }
 
-    fn get_pending_workers(&mut self) -> &mut VecDeque<WorkerInfo> {
-        &mut self.pending_workers
-    }
-
-    fn get_worker_receiver(&mut self) -> &mut Receiver<WorkerInfo> {
-        &mut self.receiver
+    fn len(&self) -> usize {
+        self.heap.0.len()
     }
 }
 
@@ -89,7 +83,6 @@ where
         let mut child_base = idx * 2;
 
         while let Some(offset) = (1..=2)
-            .into_iter()
             .filter(|x| (child_base + x) < self.0.len())
             .min_by(|&a, &b| {
                 self.0[child_base + a]
@@ -106,5 +99,5 @@ where
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, not(loom)))]
 mod test;
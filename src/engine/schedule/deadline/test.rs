@@ -4,11 +4,11 @@ use crate::engine::schedule::deadline::Heap;
 fn insert_heap_asc() {
     let mut heap = Heap::new();
 
-    (0..10).into_iter().for_each(|v| {
+    (0..10).for_each(|v| {
         heap.insert(v);
     });
 
-    (0..10).into_iter().for_each(|v| {
+    (0..10).for_each(|v| {
         assert_eq!(v, heap.delete().unwrap());
     });
 }
@@ -17,11 +17,9 @@ fn insert_heap_asc() {
 fn insert_heap_desc() {
     let mut heap = Heap::new();
 
-    (0..10).into_iter().rev().for_each(|v| heap.insert(v));
+    (0..10).rev().for_each(|v| heap.insert(v));
 
-    (0..10)
-        .into_iter()
-        .for_each(|v| assert_eq!(v, heap.delete().unwrap()));
+    (0..10).for_each(|v| assert_eq!(v, heap.delete().unwrap()));
 }
 
 #[test]
@@ -31,9 +29,7 @@ fn insert_heap_random() {
 
     values.into_iter().for_each(|v| heap.insert(v));
 
-    (0..10)
-        .into_iter()
-        .for_each(|v| assert_eq!(v, heap.delete().unwrap()));
+    (0..10).for_each(|v| assert_eq!(v, heap.delete().unwrap()));
 }
 
 #[test]
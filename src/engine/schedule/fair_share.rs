@@ -0,0 +1,122 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::time::Duration;
+
+use crate::engine::{
+    schedule::{Scheduler, SchedulerMetrics},
+    task::SharedTask,
+};
+
+// タスクが属するグループ（テナント）。指定しなければGroupId(0)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct GroupId(pub u64);
+
+const DEFAULT_WEIGHT: u32 = 1;
+
+struct Group {
+    weight: u32,
+    // 実際のpoll時間をweightで割った仮想実行時間（ナノ秒）
+    vruntime: u128,
+    runtime: Duration,
+    queue: VecDeque<SharedTask>,
+}
+
+impl Group {
+    fn new(weight: u32) -> Self {
+        Self {
+            weight,
+            vruntime: 0,
+            runtime: Duration::ZERO,
+            queue: VecDeque::new(),
+        }
+    }
+}
+
+// 仮想実行時間が最も小さいグループから取り出す
+// 各グループはweightに比例したCPU時間を得る（タスク数ではなくpoll時間で測る）
+#[derive(Default)]
+pub struct FairShare {
+    groups: BTreeMap<GroupId, Group>,
+    // これまでに選ばれたグループのvruntimeの最大値
+    // しばらく空だったグループが、溜めた分で他を締め出さないようにする
+    min_vruntime: u128,
+}
+
+impl FairShare {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_weight(mut self, group: GroupId, weight: u32) -> Self {
+        self.set_weight(group, weight);
+        self
+    }
+
+    pub fn set_weight(&mut self, group: GroupId, weight: u32) {
+        assert!(weight > 0, "weight must be non-zero");
+        self.groups
+            .entry(group)
+            .or_insert_with(|| Group::new(weight))
+            .weight = weight;
+    }
+
+    // グループが実際に使ったpoll時間の累計
+    pub fn runtime(&self, group: GroupId) -> Duration {
+        self.groups
+            .get(&group)
+            .map_or(Duration::ZERO, |group| group.runtime)
+    }
+}
+
+impl Scheduler for FairShare {
+    fn push(&mut self, task: SharedTask) {
+        let min_vruntime = self.min_vruntime;
+        let group = self
+            .groups
+            .entry(task.group())
+            .or_insert_with(|| Group::new(DEFAULT_WEIGHT));
+        if group.queue.is_empty() {
+            group.vruntime = group.vruntime.max(min_vruntime);
+        }
+        group.queue.push_back(task);
+    }
+
+    fn pop(&mut self) -> Option<SharedTask> {
+        let group = self
+            .groups
+            .values_mut()
+            .filter(|group| !group.queue.is_empty())
+            .min_by_key(|group| group.vruntime)?;
+        self.min_vruntime = self.min_vruntime.max(group.vruntime);
+        group.queue.pop_front()
+    }
+
+    fn len(&self) -> usize {
+        self.groups.values().map(|group| group.queue.len()).sum()
+    }
+
+    fn observes_polls(&self) -> bool {
+        true
+    }
+
+    fn on_poll_complete(&mut self, task: &SharedTask, elapsed: Duration) {
+        if let Some(group) = self.groups.get_mut(&task.group()) {
+            group.vruntime += elapsed.as_nanos() / group.weight as u128;
+            group.runtime += elapsed;
+        }
+    }
+
+    // グループID順に各グループのキューの長さを返す
+    fn metrics(&self) -> SchedulerMetrics {
+        SchedulerMetrics {
+            queue_depths: self
+                .groups
+                .values()
+                .map(|group| group.queue.len())
+                .collect(),
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod test;
@@ -0,0 +1,90 @@
+use std::time::Duration;
+
+use super::{FairShare, GroupId};
+use crate::engine::schedule::Scheduler;
+use crate::engine::task::{Attributes, SharedTask, Task};
+
+fn task(group: GroupId) -> SharedTask {
+    Task::from_future(
+        std::future::pending(),
+        Attributes {
+            group,
+            ..Default::default()
+        },
+    )
+}
+
+fn scheduler() -> FairShare {
+    FairShare::new()
+}
+
+// CPUを使い続けるタスクを想定し、pollのたびに再登録する
+fn run(scheduler: &mut FairShare, rounds: usize, poll_time: impl Fn(GroupId) -> Duration) {
+    for _ in 0..rounds {
+        let task = scheduler.pop().expect("task should be present");
+        scheduler.on_poll_complete(&task, poll_time(task.group()));
+        scheduler.push(task);
+    }
+}
+
+#[test]
+fn cpu_share_is_proportional_to_weight() {
+    let a = GroupId(1);
+    let b = GroupId(2);
+    let mut scheduler = scheduler().with_weight(a, 1).with_weight(b, 3);
+
+    scheduler.push(task(a));
+    scheduler.push(task(b));
+    run(&mut scheduler, 400, |_| Duration::from_millis(1));
+
+    let a_time = scheduler.runtime(a).as_millis() as f64;
+    let b_time = scheduler.runtime(b).as_millis() as f64;
+    assert!((b_time / a_time - 3.0).abs() < 0.1, "a={a_time} b={b_time}");
+}
+
+#[test]
+fn share_is_measured_by_poll_time_not_task_count() {
+    let a = GroupId(1);
+    let b = GroupId(2);
+    let mut scheduler = scheduler();
+
+    // aは短いタスクを大量に、bは重いタスクを1つだけ持つ
+    for _ in 0..10 {
+        scheduler.push(task(a));
+    }
+    scheduler.push(task(b));
+    run(&mut scheduler, 200, |group| {
+        if group == a {
+            Duration::from_millis(1)
+        } else {
+            Duration::from_millis(10)
+        }
+    });
+
+    let a_time = scheduler.runtime(a).as_millis() as f64;
+    let b_time = scheduler.runtime(b).as_millis() as f64;
+    assert!((b_time / a_time - 1.0).abs() < 0.1, "a={a_time} b={b_time}");
+}
+
+#[test]
+fn idle_group_does_not_monopolize_after_returning() {
+    let a = GroupId(1);
+    let b = GroupId(2);
+    let mut scheduler = scheduler();
+
+    scheduler.push(task(a));
+    run(&mut scheduler, 100, |_| Duration::from_millis(1));
+
+    // しばらく空だったbが戻ってきても、aと交互に選ばれる
+    scheduler.push(task(b));
+    let mut picked_a = 0;
+    for _ in 0..4 {
+        let task = scheduler.pop().unwrap();
+        if task.group() == a {
+            picked_a += 1;
+        }
+        scheduler.on_poll_complete(&task, Duration::from_millis(1));
+        scheduler.push(task);
+    }
+    assert_eq!(picked_a, 2);
+}
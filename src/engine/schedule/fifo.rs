@@ -1,44 +1,33 @@
 use crate::engine::task::SharedTask;
-use crate::engine::worker::WorkerInfo;
 
 use super::Scheduler;
 
 use std::collections::VecDeque;
-use std::sync::mpsc::Receiver;
 
+#[derive(Default)]
 pub struct Fifo {
     queue: VecDeque<SharedTask>,
-    worker_receiver: Receiver<WorkerInfo>,
-    pending_workers: VecDeque<WorkerInfo>,
 }
 
 impl Fifo {
-    pub fn new(worker_receiver: Receiver<WorkerInfo>) -> Self {
-        Self {
-            queue: VecDeque::new(),
-            worker_receiver,
-            pending_workers: VecDeque::new(),
-        }
+    pub fn new() -> Self {
+        Self::default()
     }
 }
 
 impl Scheduler for Fifo {
-    fn register(&mut self, task: SharedTask) {
+    fn push(&mut self, task: SharedTask) {
         self.queue.push_back(task);
     }
 
-    fn take(&mut self) -> Option<SharedTask> {
+    fn pop(&mut self) -> Option<SharedTask> {
         self.queue.pop_front()
     }
 
-    fn get_pending_workers(&mut self) -> &mut VecDeque<WorkerInfo> {
-        &mut self.pending_workers
-    }
-
-    fn get_worker_receiver(&mut self) -> &mut Receiver<WorkerInfo> {
-        &mut self.worker_receiver
+    fn len(&self) -> usize {
+        self.queue.len()
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, not(loom)))]
 mod test;
@@ -0,0 +1,142 @@
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+use crate::engine::{
+    schedule::{Scheduler, SchedulerMetrics},
+    task::{SharedTask, Task, TaskId},
+};
+
+const DEFAULT_LEVELS: usize = 3;
+const DEFAULT_QUANTUM: Duration = Duration::from_millis(2);
+const DEFAULT_BOOST_INTERVAL: Duration = Duration::from_millis(100);
+
+struct Level {
+    level: usize,
+    // 現在のレベルで使ったpoll時間
+    used: Duration,
+}
+
+// 新しいタスクは最上位のキューに入り、quantumを使い切るたびに1段下がる
+// boost_intervalごとに全タスクを最上位へ戻すので、下位のタスクも飢餓にならない
+pub struct Mlfq {
+    queues: Vec<VecDeque<SharedTask>>,
+    levels: HashMap<TaskId, Level>,
+    quantum: Duration,
+    boost_interval: Duration,
+    last_boost: Instant,
+}
+
+impl Mlfq {
+    pub fn new() -> Self {
+        Self {
+            queues: (0..DEFAULT_LEVELS).map(|_| VecDeque::new()).collect(),
+            levels: HashMap::new(),
+            quantum: DEFAULT_QUANTUM,
+            boost_interval: DEFAULT_BOOST_INTERVAL,
+            last_boost: Instant::now(),
+        }
+    }
+
+    pub fn with_levels(mut self, levels: usize) -> Self {
+        assert!(levels > 0, "mlfq needs at least one level");
+        self.queues = (0..levels).map(|_| VecDeque::new()).collect();
+        self
+    }
+
+    // 最上位のquantum。下のレベルほど2倍ずつ長くなる
+    pub fn with_quantum(mut self, quantum: Duration) -> Self {
+        self.quantum = quantum;
+        self
+    }
+
+    pub fn with_boost_interval(mut self, boost_interval: Duration) -> Self {
+        self.boost_interval = boost_interval;
+        self
+    }
+
+    pub fn level_of(&self, task: &Task) -> usize {
+        self.levels.get(&task.id()).map_or(0, |level| level.level)
+    }
+
+    fn boost_if_needed(&mut self, now: Instant) {
+        if now.duration_since(self.last_boost) < self.boost_interval {
+            return;
+        }
+        self.last_boost = now;
+
+        // 上位のキューにいたものから順に並べ直す
+        let mut top = VecDeque::new();
+        for queue in &mut self.queues {
+            top.append(queue);
+        }
+        self.queues[0] = top;
+
+        // 最上位で何も使っていない状態は記録がないのと同じなので、全て消す
+        // 完了したタスクや破棄されたタスクの記録もここで掃除される
+        self.levels.clear();
+    }
+}
+
+// levelでのquantum。レベルが深いか元のquantumが大きければDuration::MAXで頭打ちにする
+fn quantum_at(quantum: Duration, level: usize) -> Duration {
+    let factor = u32::try_from(level)
+        .ok()
+        .and_then(|level| 1u32.checked_shl(level))
+        .unwrap_or(u32::MAX);
+    quantum.saturating_mul(factor)
+}
+
+impl Default for Mlfq {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Scheduler for Mlfq {
+    fn push(&mut self, task: SharedTask) {
+        let level = self.level_of(&task);
+        self.queues[level].push_back(task);
+    }
+
+    fn pop(&mut self) -> Option<SharedTask> {
+        self.boost_if_needed(Instant::now());
+        self.queues.iter_mut().find_map(VecDeque::pop_front)
+    }
+
+    fn len(&self) -> usize {
+        self.queues.iter().map(VecDeque::len).sum()
+    }
+
+    fn observes_polls(&self) -> bool {
+        true
+    }
+
+    fn on_poll_complete(&mut self, task: &SharedTask, elapsed: Duration) {
+        if task.is_finished() {
+            self.levels.remove(&task.id());
+            return;
+        }
+
+        let lowest = self.queues.len() - 1;
+        let quantum = self.quantum;
+        let entry = self.levels.entry(task.id()).or_insert_with(|| Level {
+            level: 0,
+            used: Duration::ZERO,
+        });
+        entry.used = entry.used.saturating_add(elapsed);
+        if entry.level < lowest && entry.used >= quantum_at(quantum, entry.level) {
+            entry.level += 1;
+            entry.used = Duration::ZERO;
+        }
+    }
+
+    fn metrics(&self) -> SchedulerMetrics {
+        SchedulerMetrics {
+            queue_depths: self.queues.iter().map(VecDeque::len).collect(),
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod test;
@@ -0,0 +1,113 @@
+use std::thread;
+use std::time::Duration;
+
+use super::Mlfq;
+use crate::engine::schedule::{Scheduler, SchedulerMetrics};
+use crate::engine::task::{Attributes, SharedTask, Task};
+
+fn task() -> SharedTask {
+    Task::from_future(std::future::pending(), Attributes::default())
+}
+
+fn scheduler() -> Mlfq {
+    Mlfq::new()
+        .with_levels(3)
+        .with_quantum(Duration::from_millis(1))
+        .with_boost_interval(Duration::from_secs(60))
+}
+
+#[test]
+fn new_task_starts_at_top_level() {
+    let mut scheduler = scheduler();
+    let task = task();
+    scheduler.push(task.clone());
+
+    assert_eq!(scheduler.level_of(&task), 0);
+    assert_eq!(
+        scheduler.metrics(),
+        SchedulerMetrics {
+            queue_depths: vec![1, 0, 0],
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn task_exceeding_quantum_is_demoted() {
+    let mut scheduler = scheduler();
+    let heavy = task();
+
+    // level0のquantumは1ms、level1は2ms
+    scheduler.on_poll_complete(&heavy, Duration::from_millis(1));
+    assert_eq!(scheduler.level_of(&heavy), 1);
+    scheduler.on_poll_complete(&heavy, Duration::from_millis(1));
+    assert_eq!(scheduler.level_of(&heavy), 1);
+    scheduler.on_poll_complete(&heavy, Duration::from_millis(1));
+    assert_eq!(scheduler.level_of(&heavy), 2);
+
+    // 最下位より下には下がらない
+    scheduler.on_poll_complete(&heavy, Duration::from_millis(100));
+    assert_eq!(scheduler.level_of(&heavy), 2);
+}
+
+#[test]
+fn deep_levels_and_large_quanta_do_not_overflow() {
+    let mut scheduler = Mlfq::new()
+        .with_levels(40)
+        .with_quantum(Duration::from_secs(u64::MAX / 4))
+        .with_boost_interval(Duration::from_secs(60));
+    let heavy = task();
+
+    // quantumがDuration::MAXで頭打ちになっても、使った時間も頭打ちなので最下位まで下がる
+    for _ in 0..64 {
+        scheduler.on_poll_complete(&heavy, Duration::MAX);
+    }
+    assert_eq!(scheduler.level_of(&heavy), 39);
+
+    let mut scheduler = Mlfq::new()
+        .with_levels(40)
+        .with_quantum(Duration::from_nanos(1))
+        .with_boost_interval(Duration::from_secs(60));
+    // 1nsのquantumでも倍率はu32::MAX（約4.3秒）までなので、10秒ずつ使えば毎回下がる
+    for _ in 0..64 {
+        scheduler.on_poll_complete(&heavy, Duration::from_secs(10));
+    }
+    assert_eq!(scheduler.level_of(&heavy), 39);
+}
+
+#[test]
+fn short_tasks_run_before_demoted_ones() {
+    let mut scheduler = scheduler();
+    let heavy = task();
+    let light = task();
+
+    scheduler.on_poll_complete(&heavy, Duration::from_millis(5));
+    scheduler.push(heavy.clone());
+    scheduler.push(light.clone());
+
+    assert_eq!(
+        scheduler.metrics(),
+        SchedulerMetrics {
+            queue_depths: vec![1, 1, 0],
+            ..Default::default()
+        }
+    );
+    assert!(SharedTask::ptr_eq(&light, &scheduler.pop().unwrap()));
+    assert!(SharedTask::ptr_eq(&heavy, &scheduler.pop().unwrap()));
+}
+
+#[test]
+fn boost_moves_every_task_back_to_top() {
+    let mut scheduler = Mlfq::new()
+        .with_quantum(Duration::from_millis(1))
+        .with_boost_interval(Duration::from_millis(10));
+    let heavy = task();
+
+    scheduler.on_poll_complete(&heavy, Duration::from_millis(100));
+    scheduler.push(heavy.clone());
+    assert_eq!(scheduler.level_of(&heavy), 1);
+
+    thread::sleep(Duration::from_millis(15));
+    assert!(SharedTask::ptr_eq(&heavy, &scheduler.pop().unwrap()));
+    assert_eq!(scheduler.level_of(&heavy), 0);
+}
@@ -0,0 +1,123 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use crate::engine::{
+    schedule::{Scheduler, SchedulerMetrics},
+    task::SharedTask,
+};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Priority {
+    Critical,
+    High,
+    #[default]
+    Normal,
+    Background,
+}
+
+impl Priority {
+    pub const ALL: [Priority; 4] = [
+        Priority::Critical,
+        Priority::High,
+        Priority::Normal,
+        Priority::Background,
+    ];
+
+    fn level(self) -> usize {
+        self as usize
+    }
+}
+
+const DEFAULT_AGING: Duration = Duration::from_millis(100);
+
+struct Entry {
+    task: SharedTask,
+    // 現在のレベルで待ち始めた時刻。昇格するたびにaging分進める
+    enqueued_at: Instant,
+}
+
+// レベル間は厳密に優先度順で取り出す
+// agingを超えて待たされたタスクは1段ずつ昇格するので、Backgroundも必ず実行される
+pub struct PriorityScheduler {
+    queues: [VecDeque<Entry>; Priority::ALL.len()],
+    aging: Option<Duration>,
+}
+
+impl PriorityScheduler {
+    pub fn new() -> Self {
+        Self {
+            queues: Default::default(),
+            aging: Some(DEFAULT_AGING),
+        }
+    }
+
+    // Noneを渡すと昇格しない（厳密な優先度のみ）
+    // 0は待った時間をagingで割れないので受け付けない
+    pub fn with_aging(mut self, aging: Option<Duration>) -> Self {
+        assert!(aging != Some(Duration::ZERO), "aging must be greater than zero");
+        self.aging = aging;
+        self
+    }
+
+    pub fn len_at(&self, priority: Priority) -> usize {
+        self.queues[priority.level()].len()
+    }
+
+    fn age(&mut self, now: Instant) {
+        let Some(aging) = self.aging else {
+            return;
+        };
+        // 各キューはおおむね入った順に並んでいるので、先頭から見ればよい
+        for level in 1..self.queues.len() {
+            while let Some(waited) = self.queues[level]
+                .front()
+                .map(|entry| now.duration_since(entry.enqueued_at))
+                .filter(|waited| *waited >= aging)
+            {
+                // 前回のtakeから時間が空いていても、待った分だけまとめて昇格させる
+                let steps = ((waited.as_nanos() / aging.as_nanos()) as usize).min(level);
+                let mut entry = self.queues[level].pop_front().unwrap();
+                entry.enqueued_at += aging * steps as u32;
+                self.queues[level - steps].push_back(entry);
+            }
+        }
+    }
+}
+
+impl Default for PriorityScheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Scheduler for PriorityScheduler {
+    fn push(&mut self, task: SharedTask) {
+        let level = task.priority().level();
+        self.queues[level].push_back(Entry {
+            task,
+            enqueued_at: Instant::now(),
+        });
+    }
+
+    fn pop(&mut self) -> Option<SharedTask> {
+        self.age(Instant::now());
+        self.queues
+            .iter_mut()
+            .find_map(VecDeque::pop_front)
+            .map(|entry| entry.task)
+    }
+
+    fn len(&self) -> usize {
+        self.queues.iter().map(VecDeque::len).sum()
+    }
+
+    fn metrics(&self) -> SchedulerMetrics {
+        SchedulerMetrics {
+            queue_depths: self.queues.iter().map(VecDeque::len).collect(),
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod test;
@@ -0,0 +1,90 @@
+use std::thread;
+use std::time::Duration;
+
+use super::{Priority, PriorityScheduler};
+use crate::engine::schedule::Scheduler;
+use crate::engine::task::{Attributes, SharedTask, Task};
+
+fn task(priority: Priority) -> SharedTask {
+    Task::from_future(
+        async {},
+        Attributes {
+            priority,
+            ..Default::default()
+        },
+    )
+}
+
+fn scheduler(aging: Option<Duration>) -> PriorityScheduler {
+    PriorityScheduler::new().with_aging(aging)
+}
+
+#[test]
+fn take_higher_priority_first() {
+    let mut scheduler = scheduler(None);
+
+    let background = task(Priority::Background);
+    let normal = task(Priority::Normal);
+    let critical = task(Priority::Critical);
+    let high = task(Priority::High);
+
+    scheduler.push(background.clone());
+    scheduler.push(normal.clone());
+    scheduler.push(critical.clone());
+    scheduler.push(high.clone());
+
+    for expected in [critical, high, normal, background] {
+        let taken = scheduler.pop().expect("task should be present");
+        assert!(SharedTask::ptr_eq(&expected, &taken));
+    }
+    assert!(scheduler.pop().is_none());
+}
+
+#[test]
+fn same_priority_is_fifo() {
+    let mut scheduler = scheduler(None);
+
+    let first = task(Priority::High);
+    let second = task(Priority::High);
+    scheduler.push(first.clone());
+    scheduler.push(second.clone());
+
+    assert!(SharedTask::ptr_eq(&first, &scheduler.pop().unwrap()));
+    assert!(SharedTask::ptr_eq(&second, &scheduler.pop().unwrap()));
+}
+
+#[test]
+fn background_is_promoted_after_aging() {
+    let mut scheduler = scheduler(Some(Duration::from_millis(10)));
+
+    let background = task(Priority::Background);
+    scheduler.push(background.clone());
+    thread::sleep(Duration::from_millis(35));
+
+    // 3段昇格してCriticalに並んだので、後から来たHighより先に取り出される
+    let high = task(Priority::High);
+    scheduler.push(high.clone());
+
+    assert!(SharedTask::ptr_eq(&background, &scheduler.pop().unwrap()));
+    assert!(SharedTask::ptr_eq(&high, &scheduler.pop().unwrap()));
+}
+
+#[test]
+fn aging_promotes_one_level_at_a_time() {
+    let mut scheduler = scheduler(Some(Duration::from_millis(20)));
+
+    scheduler.push(task(Priority::Background));
+    thread::sleep(Duration::from_millis(25));
+    scheduler.push(task(Priority::Critical));
+
+    // 1回分の待ち時間ではNormalまでしか上がらない
+    scheduler.pop();
+    assert_eq!(scheduler.len_at(Priority::Background), 0);
+    assert_eq!(scheduler.len_at(Priority::Normal), 1);
+}
+
+#[test]
+#[should_panic(expected = "aging must be greater than zero")]
+fn zero_aging_is_rejected() {
+    PriorityScheduler::new().with_aging(Some(Duration::ZERO));
+}
@@ -0,0 +1,59 @@
+use crate::engine::task::SharedTask;
+
+use super::Scheduler;
+
+// 実行可能なタスクの中から、シードで決まる擬似乱数で次の1つを選ぶ
+// 同じシードで同じ順番にpushされれば、いつも同じ順番で取り出される
+pub struct Random {
+    seed: u64,
+    rng: SplitMix64,
+    tasks: Vec<SharedTask>,
+}
+
+impl Random {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: SplitMix64(seed),
+            tasks: Vec::new(),
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+impl Scheduler for Random {
+    fn push(&mut self, task: SharedTask) {
+        self.tasks.push(task);
+    }
+
+    fn pop(&mut self) -> Option<SharedTask> {
+        if self.tasks.is_empty() {
+            return None;
+        }
+        let index = (self.rng.next() % self.tasks.len() as u64) as usize;
+        Some(self.tasks.swap_remove(index))
+    }
+
+    fn len(&self) -> usize {
+        self.tasks.len()
+    }
+}
+
+// 外部のクレートに頼らない小さな擬似乱数生成器
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod test;
@@ -0,0 +1,51 @@
+use super::Random;
+use crate::engine::schedule::Scheduler;
+use crate::engine::task::{Attributes, SharedTask, Task, TaskId};
+
+fn tasks(n: usize) -> Vec<SharedTask> {
+    (0..n)
+        .map(|_| Task::from_future(std::future::pending(), Attributes::default()))
+        .collect()
+}
+
+// 同じタスクを同じ順番でpushし、取り出された順番をタスクの添字で返す
+fn pop_order(seed: u64, tasks: &[SharedTask]) -> Vec<usize> {
+    let ids: Vec<TaskId> = tasks.iter().map(|t| t.id()).collect();
+    let mut scheduler = Random::new(seed);
+    tasks.iter().for_each(|t| scheduler.push(t.clone()));
+
+    let mut order = Vec::new();
+    while let Some(task) = scheduler.pop() {
+        order.push(ids.iter().position(|id| *id == task.id()).unwrap());
+    }
+    order
+}
+
+#[test]
+fn pops_every_task_once() {
+    let tasks = tasks(16);
+    let mut order = pop_order(7, &tasks);
+    order.sort();
+    assert_eq!(order, (0..16).collect::<Vec<_>>());
+}
+
+#[test]
+fn same_seed_same_order() {
+    let tasks = tasks(16);
+    assert_eq!(pop_order(42, &tasks), pop_order(42, &tasks));
+}
+
+#[test]
+fn different_seeds_change_order() {
+    let tasks = tasks(16);
+    let orders: Vec<_> = (0..4).map(|seed| pop_order(seed, &tasks)).collect();
+    assert!(orders.windows(2).any(|w| w[0] != w[1]));
+}
+
+#[test]
+fn empty_returns_none() {
+    let mut scheduler = Random::new(0);
+    assert!(scheduler.pop().is_none());
+    assert!(scheduler.is_empty());
+    assert_eq!(scheduler.seed(), 0);
+}
@@ -0,0 +1,38 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use crate::engine::dispatch::SharedDispatcher;
+
+// Engineの停止が始まったら完了するFuture
+// 停止はtimeoutまでタスクの完了を待つので、その間に後片付けをする
+pub struct ShutdownSignal {
+    dispatcher: SharedDispatcher,
+    key: Option<u64>,
+}
+
+impl ShutdownSignal {
+    pub(crate) fn new(dispatcher: SharedDispatcher) -> Self {
+        Self {
+            dispatcher,
+            key: None,
+        }
+    }
+}
+
+impl Future for ShutdownSignal {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let this = self.get_mut();
+        this.dispatcher.poll_closed(&mut this.key, cx)
+    }
+}
+
+impl Drop for ShutdownSignal {
+    fn drop(&mut self) {
+        if let Some(key) = self.key {
+            self.dispatcher.remove_signal(key);
+        }
+    }
+}
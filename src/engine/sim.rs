@@ -0,0 +1,147 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll, Waker};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::engine::coop;
+use crate::engine::dispatch::{Dispatcher, SharedDispatcher};
+use crate::engine::handle::Handle;
+use crate::engine::join::JoinHandle;
+use crate::engine::schedule::random::Random;
+use crate::engine::task::SharedTask;
+use crate::engine::waker;
+use crate::time::clock::Clock;
+use crate::time::driver::Driver;
+
+// 失敗したシードを渡して再実行するための環境変数
+pub const SEED_ENV: &str = "ASYNC_RUNTIME_SEED";
+
+// 全てのタスクを呼び出したスレッドの上で1つずつ実行するEngine
+// 次に実行するタスクはシードから決まる擬似乱数で選び、時刻は仮想時計を使う
+// 実行できるタスクがなくなったら、次のタイマーの期限まで時計を進める
+// タスクを起こすのがシミュレーション内のタスクとタイマーだけなら、同じシードで同じ順番に実行される
+// sleepなどのタイマーは作られたときの時計に結びつくので、block_onに渡すFutureの中で作る
+pub struct Simulation {
+    seed: u64,
+    handle: Handle,
+    dispatcher: SharedDispatcher,
+    driver: Arc<Driver>,
+    started: Instant,
+}
+
+impl Simulation {
+    pub fn new(seed: u64) -> Self {
+        let driver = Arc::new(Driver::new(Clock::paused()));
+        let dispatcher = Arc::new(Dispatcher::new(Box::new(Random::new(seed)), driver.clone()));
+        Self {
+            seed,
+            handle: Handle::new(dispatcher.clone()),
+            dispatcher,
+            started: driver.now(),
+            driver,
+        }
+    }
+
+    // 環境変数にシードがあればそれを使い、なければ現在時刻から作る
+    pub fn from_env() -> Self {
+        let seed = match std::env::var(SEED_ENV) {
+            Ok(seed) => seed
+                .parse()
+                .unwrap_or_else(|_| panic!("{SEED_ENV} must be an unsigned integer: {seed}")),
+            Err(_) => SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_nanos() as u64),
+        };
+        Self::new(seed)
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn handle(&self) -> &Handle {
+        &self.handle
+    }
+
+    // block_onを呼ぶまでは実行されない
+    pub fn spawn<F>(&self, future: F) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        self.handle.spawn(future)
+    }
+
+    // シミュレーション開始から進んだ仮想時間
+    pub fn elapsed(&self) -> Duration {
+        self.driver.now() - self.started
+    }
+
+    // futureが完了するまで、他のタスクも含めてこのスレッドで実行する
+    // 実行できるタスクもタイマーもないのに完了していなければパニックする
+    pub fn block_on<F>(&mut self, future: F) -> F::Output
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        let _report = ReportSeed(self.seed);
+        let _handle = self.handle.enter();
+        let mut main = self.handle.spawn(future);
+
+        loop {
+            while !main.is_finished()
+                && let Some(task) = self.dispatcher.next_task()
+            {
+                run(&task);
+            }
+
+            let mut cx = Context::from_waker(Waker::noop());
+            if let Poll::Ready(output) = Pin::new(&mut main).poll(&mut cx) {
+                return output.expect("simulation main task was cancelled");
+            }
+
+            match self.driver.next_deadline() {
+                Some(deadline) => {
+                    self.driver.clock().advance_to(deadline);
+                    self.driver.fire_expired();
+                }
+                None => panic!("simulation stalled: no runnable tasks and no pending timers"),
+            }
+        }
+    }
+}
+
+// 残ったタスクを破棄し、タイマーが持つWakerも捨てる
+// タスク→Future→Sleep→Driver→Waker→タスクと参照が循環しているので、ここで切らないと解放されない
+impl Drop for Simulation {
+    fn drop(&mut self) {
+        self.dispatcher.close();
+        self.dispatcher.cancel_all();
+        self.driver.clear();
+    }
+}
+
+fn run(task: &SharedTask) {
+    let waker = waker::waker_ref(task);
+    let mut cx = Context::from_waker(&waker);
+    let _ = coop::budget(|| task.poll(&mut cx));
+}
+
+// パニックで抜けたときに、再現に使うシードを表示する
+struct ReportSeed(u64);
+
+impl Drop for ReportSeed {
+    fn drop(&mut self) {
+        if thread::panicking() {
+            eprintln!(
+                "simulation failed with seed {0}; rerun with {SEED_ENV}={0}",
+                self.0
+            );
+        }
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod test;
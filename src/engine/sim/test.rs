@@ -0,0 +1,136 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use super::Simulation;
+use crate::engine::{spawn, yield_now};
+use crate::time::{self, sleep, timeout};
+
+// 8つのタスクが3回ずつyieldしながら、実行された順番を記録する
+fn trace(seed: u64) -> Vec<(usize, usize)> {
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let cloned = log.clone();
+    Simulation::new(seed).block_on(async move {
+        let handles: Vec<_> = (0..8)
+            .map(|id| {
+                let log = cloned.clone();
+                spawn(async move {
+                    for step in 0..3 {
+                        log.lock().unwrap().push((id, step));
+                        yield_now().await;
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.await.unwrap();
+        }
+    });
+    Arc::try_unwrap(log).unwrap().into_inner().unwrap()
+}
+
+#[test]
+fn block_on_returns_output() {
+    let mut sim = Simulation::new(0);
+    let res = sim.block_on(async {
+        let a = spawn(async { 20 });
+        let b = spawn(async { 22 });
+        a.await.unwrap() + b.await.unwrap()
+    });
+    assert_eq!(res, 42);
+}
+
+#[test]
+fn same_seed_replays_same_order() {
+    for seed in [0, 1, 12345] {
+        assert_eq!(trace(seed), trace(seed));
+    }
+}
+
+#[test]
+fn different_seeds_interleave_differently() {
+    let traces: Vec<_> = (0..4).map(trace).collect();
+    assert!(traces.windows(2).any(|w| w[0] != w[1]));
+}
+
+#[test]
+fn sleep_advances_virtual_time_only() {
+    let started = Instant::now();
+    let mut sim = Simulation::new(0);
+    sim.block_on(async { sleep(Duration::from_secs(3600)).await });
+
+    assert_eq!(sim.elapsed(), Duration::from_secs(3600));
+    assert!(started.elapsed() < Duration::from_secs(1));
+}
+
+#[test]
+fn timers_fire_in_deadline_order() {
+    for seed in 0..4 {
+        let fired = Arc::new(Mutex::new(Vec::new()));
+        let cloned = fired.clone();
+        let elapsed = Simulation::new(seed).block_on(async move {
+            let start = time::now();
+            let handles: Vec<_> = [30, 10, 20]
+                .into_iter()
+                .map(|ms| {
+                    let fired = cloned.clone();
+                    spawn(async move {
+                        sleep(Duration::from_millis(ms)).await;
+                        fired.lock().unwrap().push(ms);
+                    })
+                })
+                .collect();
+            for handle in handles {
+                handle.await.unwrap();
+            }
+            time::now() - start
+        });
+
+        assert_eq!(*fired.lock().unwrap(), vec![10, 20, 30]);
+        assert_eq!(elapsed, Duration::from_millis(30));
+    }
+}
+
+#[test]
+fn timeout_uses_virtual_time() {
+    let mut sim = Simulation::new(0);
+    // タイマーはシミュレーションの中で作る
+    let res = sim.block_on(async {
+        timeout(Duration::from_millis(10), sleep(Duration::from_secs(3600))).await
+    });
+    assert!(res.is_err());
+    assert_eq!(sim.elapsed(), Duration::from_millis(10));
+}
+
+#[test]
+#[should_panic(expected = "simulation stalled")]
+fn stalled_simulation_panics() {
+    Simulation::new(0).block_on(std::future::pending::<()>());
+}
+
+// 破棄された回数を数える
+struct DropCounter(Arc<AtomicUsize>);
+
+impl Drop for DropCounter {
+    fn drop(&mut self) {
+        self.0.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn drop_releases_sleeping_tasks() {
+    let drops = Arc::new(AtomicUsize::new(0));
+    let mut sim = Simulation::new(0);
+    let counter = DropCounter(drops.clone());
+    sim.block_on(async move {
+        spawn(async move {
+            let _counter = counter;
+            sleep(Duration::from_secs(3600)).await;
+        });
+        yield_now().await;
+    });
+    assert_eq!(drops.load(Ordering::SeqCst), 0);
+
+    drop(sim);
+    assert_eq!(drops.load(Ordering::SeqCst), 1);
+}
@@ -1,33 +1,172 @@
-use std::sync::atomic::Ordering;
-use std::sync::{Arc, Mutex};
-use std::{pin::Pin, sync::atomic::AtomicU8, task::Poll};
+use std::fmt;
+use std::ops::Deref;
+use std::pin::Pin;
+use std::ptr::NonNull;
+use std::sync::atomic::AtomicU64;
+use std::sync::{Arc, OnceLock, Weak};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
 
+use crate::engine::dispatch::Dispatcher;
+use crate::engine::schedule::fair_share::GroupId;
+use crate::engine::schedule::priority::Priority;
+use crate::engine::trace::EventKind;
+use crate::loom::sync::Mutex;
+use crate::loom::sync::atomic::Ordering;
 use crate::utils::channel::Sender;
 
-pub const PENDING: u8 = 0;
-pub const SCHEDULED: u8 = 1;
-pub const RUNNING: u8 = 2;
-pub const COMPLETED: u8 = 3;
+mod builder;
+mod state;
 
-pub type SharedTask = Arc<Task>;
+pub use builder::TaskBuilder;
+pub use state::Snapshot;
+use state::{Idle, State};
 
-fn state_name(state: u8) -> &'static str {
-    match state {
-        PENDING => "PENDING",
-        SCHEDULED => "SCHEDULED",
-        RUNNING => "RUNNING",
-        COMPLETED => "COMPLETED",
-        _ => "UNKNOWN",
+static NEXT_TASK_ID: AtomicU64 = AtomicU64::new(0);
+
+// キューに入った時刻を整数で持つための基準
+static EPOCH: OnceLock<Instant> = OnceLock::new();
+
+fn nanos_since_epoch() -> u64 {
+    EPOCH.get_or_init(Instant::now).elapsed().as_nanos() as u64
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TaskId(u64);
+
+impl TaskId {
+    pub(crate) fn next() -> Self {
+        TaskId(NEXT_TASK_ID.fetch_add(1, Ordering::Relaxed))
+    }
+
+    pub(crate) fn as_u64(self) -> u64 {
+        self.0
+    }
+}
+
+impl fmt::Display for TaskId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+// スケジューラが参照するタスクの属性
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Attributes {
+    pub deadline: Option<u64>,
+    pub priority: Priority,
+    pub group: GroupId,
+    // タスクダンプやログでタスクを見分けるための名前
+    pub name: Option<Arc<str>>,
+    // 登録したスレッドのEngine（thread-per-coreではそのコア）に留める
+    pub local: bool,
+}
+
+impl Attributes {
+    pub fn with_deadline(deadline: Option<u64>) -> Self {
+        Self {
+            deadline,
+            ..Default::default()
+        }
     }
 }
 
+// タスクのメモリの先頭に置くヘッダ。参照カウント・状態・vtableを持つ
+// ヘッダのアドレスがそのままWakerのデータになり、Futureは同じアロケーションの後ろに置く
 pub struct Task {
-    inner: Mutex<Pin<Box<dyn Future<Output = ()> + Send>>>,
-    state: AtomicU8,
+    // 実行権とSharedTask・Wakerの数。数が0になったらvtable.deallocで解放する
+    state: State,
+    // Futureの型ごとの関数表
+    vtable: &'static Vtable,
+    id: TaskId,
+    // 最初にスケジュールされたEngine。wakeされたらここへ戻す
+    dispatcher: OnceLock<Weak<Dispatcher>>,
     deadline: Option<u64>,
+    priority: Priority,
+    group: GroupId,
+    name: Option<Arc<str>>,
+    local: bool,
+    // Workerが計測したpollの累計時間（ナノ秒）と回数
+    poll_time: AtomicU64,
+    poll_count: AtomicU64,
+    // 最後にキューへ入った時刻（EPOCHからのナノ秒）
+    queued_at: AtomicU64,
+}
+
+// 1つのアロケーションにヘッダとFutureを並べる
+// ヘッダを先頭に置くので、ヘッダへのポインタをそのままCell<F>へのポインタに戻せる
+#[repr(C)]
+struct Cell<F> {
+    header: Task,
+    // 完了・キャンセル後はその場でNoneにしてFutureを解放する
+    future: Mutex<Option<F>>,
+}
+
+// Futureの型を知らないヘッダから、Futureに触るための関数表
+struct Vtable {
+    poll: unsafe fn(NonNull<Task>, &mut Context<'_>) -> Poll<()>,
+    drop_future: unsafe fn(NonNull<Task>),
+    dealloc: unsafe fn(NonNull<Task>),
+}
+
+fn vtable<F>() -> &'static Vtable
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    &Vtable {
+        poll: poll_future::<F>,
+        drop_future: drop_future::<F>,
+        dealloc: dealloc::<F>,
+    }
+}
+
+// ptrはCell<F>のヘッダを指している
+unsafe fn poll_future<F>(ptr: NonNull<Task>, cx: &mut Context<'_>) -> Poll<()>
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    let cell = unsafe { ptr.cast::<Cell<F>>().as_ref() };
+    let mut slot = cell.future.lock().unwrap();
+    let Some(future) = slot.as_mut() else {
+        return Poll::Ready(());
+    };
+    // ヒープに置いたFutureは解放されるまで動かない
+    let poll = unsafe { Pin::new_unchecked(future) }.poll(cx);
+    if poll.is_ready() {
+        // 動かさずにその場で破棄する
+        *slot = None;
+    }
+    poll
+}
+
+unsafe fn drop_future<F>(ptr: NonNull<Task>)
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    let cell = unsafe { ptr.cast::<Cell<F>>().as_ref() };
+    *cell.future.lock().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+// 最後の参照が破棄された
+unsafe fn dealloc<F>(ptr: NonNull<Task>)
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    unsafe { drop_future::<F>(ptr) };
+    // 完了しないまま誰からも参照されなくなった
+    // Dispatcherの一覧はWeakTaskで指しているので、メモリを解放する前に外す
+    let task = unsafe { ptr.as_ref() };
+    if !task.state.load().is_complete()
+        && let Some(dispatcher) = task.dispatcher()
+    {
+        dispatcher.release(task.id);
+    }
+    drop(unsafe { Box::from_raw(ptr.cast::<Cell<F>>().as_ptr()) });
 }
 
 impl Task {
+    // タスクはいつもSharedTaskを通して扱う
+    #[allow(clippy::new_ret_no_self)]
     pub fn new<T, U>(inner: T, sender: Sender<U>, deadline: Option<u64>) -> SharedTask
     where
         T: Future<Output = U> + Send + 'static,
@@ -38,79 +177,326 @@ impl Task {
             let res = inner.await;
             sender.send(res);
         };
-        Arc::new(Self {
-            inner: Mutex::new(Box::pin(task)),
-            state: AtomicU8::new(PENDING),
-            deadline,
-        })
-    }
-
-    pub fn set_state(&self, val: u8) {
-        self.state.store(val, Ordering::Release);
-    }
-
-    pub fn get_state(&self) -> u8 {
-        self.state.load(Ordering::Acquire)
-    }
-
-    pub fn is_scheduled(&self) -> bool {
-        self.get_state() == SCHEDULED
-    }
-
-    pub fn poll(&self, cx: &mut std::task::Context<'_>) -> Poll<()> {
-        // SCHEDULED -> RUNNING の遷移のみ許可
-        // これによりCOMPLETEDやRUNNING中のタスクがpollされるのを防ぐ
-        let before_state = self.state.load(Ordering::Acquire);
-        eprintln!("[Task::poll] Before: state={}", state_name(before_state));
-
-        match self
-            .state
-            .compare_exchange(SCHEDULED, RUNNING, Ordering::Release, Ordering::Acquire)
-        {
-            Ok(_) => {
-                eprintln!("[Task::poll] State transition: SCHEDULED -> RUNNING");
-                // 状態遷移成功、pollを実行
-                let mut inner = self.inner.lock().unwrap();
-                match inner.as_mut().poll(cx) {
-                    Poll::Pending => {
-                        eprintln!("[Task::poll] Future returned Pending");
-                        // RUNNING -> PENDING の遷移を試みる
-                        // もしwake()が既に呼ばれてSCHEDULEDになっていたら、そのまま
-                        match self.state.compare_exchange(
-                            RUNNING,
-                            PENDING,
-                            Ordering::Release,
-                            Ordering::Acquire,
-                        ) {
-                            Ok(_) => eprintln!("[Task::poll] State transition: RUNNING -> PENDING"),
-                            Err(actual) => eprintln!(
-                                "[Task::poll] State transition failed: RUNNING -> PENDING (actual: {})",
-                                state_name(actual)
-                            ),
+        Self::from_future(task, Attributes::with_deadline(deadline))
+    }
+
+    pub(crate) fn from_future<T>(inner: T, attributes: Attributes) -> SharedTask
+    where
+        T: Future<Output = ()> + Send + 'static,
+    {
+        Self::with_id(TaskId::next(), inner, attributes)
+    }
+
+    pub(crate) fn with_id<T>(id: TaskId, inner: T, attributes: Attributes) -> SharedTask
+    where
+        T: Future<Output = ()> + Send + 'static,
+    {
+        let cell = Box::new(Cell {
+            header: Task {
+                state: State::new(),
+                vtable: vtable::<T>(),
+                id,
+                dispatcher: OnceLock::new(),
+                deadline: attributes.deadline,
+                priority: attributes.priority,
+                group: attributes.group,
+                name: attributes.name,
+                local: attributes.local,
+                poll_time: AtomicU64::new(0),
+                poll_count: AtomicU64::new(0),
+                queued_at: AtomicU64::new(0),
+            },
+            future: Mutex::new(Some(inner)),
+        });
+        SharedTask {
+            ptr: NonNull::from(Box::leak(cell)).cast(),
+        }
+    }
+
+    pub fn id(&self) -> TaskId {
+        self.id
+    }
+
+    pub fn deadline(&self) -> Option<u64> {
+        self.deadline
+    }
+
+    pub fn priority(&self) -> Priority {
+        self.priority
+    }
+
+    pub fn group(&self) -> GroupId {
+        self.group
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    // 名前を写すときは文字列ではなく参照カウントを増やす
+    pub(crate) fn shared_name(&self) -> Option<&Arc<str>> {
+        self.name.as_ref()
+    }
+
+    pub fn is_local(&self) -> bool {
+        self.local
+    }
+
+    pub fn record_poll(&self, elapsed: Duration) {
+        self.poll_time
+            .fetch_add(elapsed.as_nanos() as u64, Ordering::Relaxed);
+        self.poll_count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn total_poll_time(&self) -> Duration {
+        Duration::from_nanos(self.poll_time.load(Ordering::Relaxed))
+    }
+
+    pub fn poll_count(&self) -> u64 {
+        self.poll_count.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn mark_queued(&self) {
+        self.queued_at.store(nanos_since_epoch(), Ordering::Relaxed);
+    }
+
+    // 最後にキューへ入ってから今までの時間
+    pub fn queue_latency(&self) -> Duration {
+        let queued_at = self.queued_at.load(Ordering::Relaxed);
+        Duration::from_nanos(nanos_since_epoch().saturating_sub(queued_at))
+    }
+
+    pub(crate) fn bind(&self, dispatcher: &Arc<Dispatcher>) {
+        self.dispatcher.get_or_init(|| Arc::downgrade(dispatcher));
+    }
+
+    pub(crate) fn dispatcher(&self) -> Option<Arc<Dispatcher>> {
+        self.dispatcher.get().and_then(Weak::upgrade)
+    }
+
+    pub fn state(&self) -> Snapshot {
+        self.state.load()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.state().is_complete()
+    }
+
+    // 今の状態と統計を写し取る
+    pub fn dump(&self) -> TaskDump {
+        TaskDump {
+            id: self.id,
+            name: self.name.clone(),
+            deadline: self.deadline,
+            priority: self.priority,
+            group: self.group,
+            local: self.local,
+            state: self.state(),
+            polls: self.poll_count(),
+            busy_time: self.total_poll_time(),
+        }
+    }
+
+    // 実行キューに入れるべきならtrue。RUNNING中ならpollの後で戻される
+    pub(crate) fn notify(&self) -> bool {
+        self.state.transition_to_notified()
+    }
+
+    // RUNNINGを持っている側がFutureを破棄した後に呼ぶ
+    fn complete(&self) {
+        self.state.transition_to_complete();
+        if let Some(dispatcher) = self.dispatcher() {
+            dispatcher.record(EventKind::Complete, self);
+            dispatcher.release(self.id);
+        }
+    }
+}
+
+// タスクへの参照。参照カウントを1つ持ち、最後の1つが破棄されたらタスクを解放する
+pub struct SharedTask {
+    ptr: NonNull<Task>,
+}
+
+// Futureは作るときにSendを要求し、触るときはMutexを通す
+unsafe impl Send for SharedTask {}
+unsafe impl Sync for SharedTask {}
+
+impl SharedTask {
+    pub fn ptr_eq(this: &Self, other: &Self) -> bool {
+        this.ptr == other.ptr
+    }
+
+    pub fn as_ptr(this: &Self) -> *const Task {
+        this.ptr.as_ptr()
+    }
+
+    // 参照カウントを手放さずにポインタにする。from_rawで戻す
+    pub(crate) fn into_raw(this: Self) -> *const () {
+        let ptr = this.ptr.as_ptr().cast();
+        std::mem::forget(this);
+        ptr
+    }
+
+    // into_rawで得たポインタか、参照カウントを1つ増やしたポインタから戻す
+    pub(crate) unsafe fn from_raw(ptr: *const ()) -> Self {
+        SharedTask {
+            ptr: unsafe { NonNull::new_unchecked(ptr.cast_mut().cast()) },
+        }
+    }
+
+    pub fn ref_count(this: &Self) -> usize {
+        this.state.load().ref_count()
+    }
+
+    pub(crate) fn downgrade(this: &Self) -> WeakTask {
+        WeakTask(this.ptr)
+    }
+
+    // 待機中ならその場でFutureを破棄し、実行中・スケジュール済みならpoll側で破棄させる
+    pub fn abort(&self) {
+        if self.state.transition_to_cancelled() {
+            eprintln!("[Task::abort] {self}: State transition: IDLE -> CANCELLED");
+            self.cancel();
+        }
+    }
+
+    // RUNNINGを持っている側が呼ぶ
+    fn cancel(&self) {
+        unsafe { (self.vtable.drop_future)(self.ptr) };
+        self.complete();
+    }
+
+    // キューから取り出したタスクをpollする
+    // poll中にwakeされていたら、pollを終えてからキューへ戻す
+    pub fn poll(&self, cx: &mut Context<'_>) -> Poll<()> {
+        match self.state.transition_to_running() {
+            Ok(snapshot) if snapshot.is_cancelled() => {
+                // キューにいる間にabortされた
+                self.cancel();
+                eprintln!("[Task::poll] {self}: State transition: NOTIFIED -> CANCELLED");
+                return Poll::Ready(());
+            }
+            Ok(_) => eprintln!("[Task::poll] {self}: State transition: NOTIFIED -> RUNNING"),
+            Err(actual) => {
+                // 既にRUNNINGかCOMPLETE、またはキューに二重に入っていた
+                eprintln!("[Task::poll] {self}: State transition failed: {actual:?}");
+                return Poll::Pending;
+            }
+        }
+
+        match unsafe { (self.vtable.poll)(self.ptr, cx) } {
+            Poll::Pending => {
+                match self.state.transition_to_idle() {
+                    Idle::Ok => eprintln!("[Task::poll] {self}: State transition: RUNNING -> IDLE"),
+                    Idle::Notified => {
+                        eprintln!("[Task::poll] {self}: Woken while running, rescheduling");
+                        if let Some(dispatcher) = self.dispatcher() {
+                            dispatcher.reschedule(self.clone());
                         }
-                        Poll::Pending
                     }
-                    Poll::Ready(v) => {
-                        eprintln!("[Task::poll] Future returned Ready");
-                        self.state.store(COMPLETED, Ordering::Release);
-                        eprintln!("[Task::poll] State transition: RUNNING -> COMPLETED");
-                        Poll::Ready(v)
+                    Idle::Cancelled => {
+                        // poll中にabortされていたら、ここで破棄する
+                        self.cancel();
+                        eprintln!("[Task::poll] {self}: State transition: RUNNING -> CANCELLED");
+                        return Poll::Ready(());
                     }
                 }
-            }
-            Err(actual) => {
-                eprintln!(
-                    "[Task::poll] State transition failed: SCHEDULED -> RUNNING (actual: {})",
-                    state_name(actual)
-                );
-                // 状態遷移失敗（既にRUNNINGかCOMPLETED）
-                // Pendingを返して何もしない
                 Poll::Pending
             }
+            Poll::Ready(()) => {
+                self.complete();
+                eprintln!("[Task::poll] {self}: State transition: RUNNING -> COMPLETE");
+                Poll::Ready(())
+            }
+        }
+    }
+}
+
+impl Deref for SharedTask {
+    type Target = Task;
+
+    fn deref(&self) -> &Task {
+        unsafe { self.ptr.as_ref() }
+    }
+}
+
+impl Clone for SharedTask {
+    fn clone(&self) -> Self {
+        self.state.ref_inc();
+        SharedTask { ptr: self.ptr }
+    }
+}
+
+impl Drop for SharedTask {
+    fn drop(&mut self) {
+        if !self.state.ref_dec() {
+            return;
+        }
+        unsafe { (self.vtable.dealloc)(self.ptr) };
+    }
+}
+
+impl fmt::Display for SharedTask {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&**self, f)
+    }
+}
+
+impl PartialEq for SharedTask {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl PartialOrd for SharedTask {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        (**self).partial_cmp(&**other)
+    }
+}
+
+// 参照カウントを持たないタスクへのポインタ。Dispatcherが完了していないタスクの一覧に使う
+// タスクは解放される前に一覧から外されるので、一覧のロックを持っている間はupgradeできる
+pub(crate) struct WeakTask(NonNull<Task>);
+
+unsafe impl Send for WeakTask {}
+unsafe impl Sync for WeakTask {}
+
+impl WeakTask {
+    // 呼び出し側は、タスクのメモリがまだ解放されていないことを保証する
+    // 最後の参照が破棄された後（解放の途中）ならNone
+    pub(crate) unsafe fn upgrade(&self) -> Option<SharedTask> {
+        let task = unsafe { self.0.as_ref() };
+        task.state
+            .try_ref_inc()
+            .then_some(SharedTask { ptr: self.0 })
+    }
+}
+
+// ログに出すときの表記。名前があれば添える
+impl fmt::Display for Task {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.name {
+            Some(name) => write!(f, "task {} ({name})", self.id),
+            None => write!(f, "task {}", self.id),
         }
     }
 }
 
+// Engineに残っているタスクの様子
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaskDump {
+    pub id: TaskId,
+    pub name: Option<Arc<str>>,
+    pub deadline: Option<u64>,
+    pub priority: Priority,
+    pub group: GroupId,
+    pub local: bool,
+    pub state: Snapshot,
+    // pollした回数とその累計時間
+    pub polls: u64,
+    pub busy_time: Duration,
+}
+
 impl PartialEq for Task {
     fn eq(&self, other: &Self) -> bool {
         self.deadline == other.deadline
@@ -122,3 +508,60 @@ impl PartialOrd for Task {
         self.deadline.partial_cmp(&other.deadline)
     }
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JoinError {
+    Cancelled,
+    // 完了する前にEngineが停止した
+    Shutdown,
+}
+
+impl JoinError {
+    pub fn is_cancelled(&self) -> bool {
+        matches!(self, JoinError::Cancelled)
+    }
+
+    pub fn is_shutdown(&self) -> bool {
+        matches!(self, JoinError::Shutdown)
+    }
+}
+
+impl fmt::Display for JoinError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JoinError::Cancelled => write!(f, "task was cancelled"),
+            JoinError::Shutdown => write!(f, "engine was shut down before the task completed"),
+        }
+    }
+}
+
+impl std::error::Error for JoinError {}
+
+#[derive(Clone)]
+pub struct AbortHandle {
+    task: SharedTask,
+}
+
+impl AbortHandle {
+    pub(crate) fn new(task: SharedTask) -> Self {
+        Self { task }
+    }
+
+    pub fn id(&self) -> TaskId {
+        self.task.id()
+    }
+
+    pub fn abort(&self) {
+        self.task.abort();
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.task.is_finished()
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod test;
+
+#[cfg(all(test, loom))]
+mod model;
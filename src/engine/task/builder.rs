@@ -0,0 +1,71 @@
+use std::future::Future;
+
+use crate::engine::handle::Handle;
+use crate::engine::join::JoinHandle;
+use crate::engine::schedule::fair_share::GroupId;
+use crate::engine::schedule::priority::Priority;
+use crate::engine::task::Attributes;
+
+// 属性を付けてタスクを登録する
+// Handleを指定しなければ、spawnを呼んだときのHandle::current()に登録する
+// localにすると、Handleを指定していても呼んだスレッドのEngine（コア）があればそちらに登録する
+#[derive(Clone, Default)]
+pub struct TaskBuilder {
+    handle: Option<Handle>,
+    attributes: Attributes,
+}
+
+impl TaskBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn on(handle: Handle) -> Self {
+        Self {
+            handle: Some(handle),
+            attributes: Attributes::default(),
+        }
+    }
+
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.attributes.name = Some(name.into().into());
+        self
+    }
+
+    pub fn deadline(mut self, deadline: u64) -> Self {
+        self.attributes.deadline = Some(deadline);
+        self
+    }
+
+    pub fn priority(mut self, priority: Priority) -> Self {
+        self.attributes.priority = priority;
+        self
+    }
+
+    pub fn group(mut self, group: GroupId) -> Self {
+        self.attributes.group = group;
+        self
+    }
+
+    // thread-per-coreで、他のコアへ振り分けずに今のコアで実行する
+    pub fn local(mut self) -> Self {
+        self.attributes.local = true;
+        self
+    }
+
+    pub fn spawn<F>(self, future: F) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        let handle = match self.handle {
+            Some(handle) if self.attributes.local => Handle::try_current().unwrap_or(handle),
+            Some(handle) => handle,
+            None => Handle::current(),
+        };
+        handle.spawn_with(future, self.attributes)
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod test;
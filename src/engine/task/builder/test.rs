@@ -0,0 +1,145 @@
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::TaskBuilder;
+use crate::engine::schedule::fair_share::GroupId;
+use crate::engine::schedule::fifo::Fifo;
+use crate::engine::schedule::priority::Priority;
+use crate::engine::{Engine, Simulation, block_on};
+
+fn wait_until(mut f: impl FnMut() -> bool) -> bool {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while Instant::now() < deadline {
+        if f() {
+            return true;
+        }
+        thread::sleep(Duration::from_millis(5));
+    }
+    false
+}
+
+#[test]
+fn metadata_appears_in_dump() {
+    let engine = Engine::new(1, Fifo::new());
+    let handle = engine
+        .task_builder()
+        .name("worker-loop")
+        .deadline(10)
+        .priority(Priority::High)
+        .group(GroupId(3))
+        .spawn(std::future::pending::<()>());
+
+    assert!(wait_until(|| engine
+        .dump()
+        .iter()
+        .any(|task| task.polls > 0)));
+    let dump = engine.dump();
+    assert_eq!(dump.len(), 1);
+    let task = &dump[0];
+    assert_eq!(task.id, handle.id());
+    assert_eq!(task.name.as_deref(), Some("worker-loop"));
+    assert_eq!(task.deadline, Some(10));
+    assert_eq!(task.priority, Priority::High);
+    assert_eq!(task.group, GroupId(3));
+    assert!(!task.state.is_complete());
+
+    handle.abort();
+    assert!(wait_until(|| engine.dump().is_empty()));
+    engine.shutdown_now();
+}
+
+#[test]
+fn completed_tasks_leave_dump() {
+    let engine = Engine::new(2, Fifo::new());
+    let out = block_on(engine.task_builder().name("short").spawn(async { 3 }));
+    assert_eq!(out, Ok(3));
+    assert!(wait_until(|| engine.dump().is_empty()));
+    engine.shutdown_now();
+}
+
+#[test]
+fn spawns_on_current_handle() {
+    let engine = Engine::new(1, Fifo::new());
+    let name = block_on(engine.spawn(async {
+        let child = TaskBuilder::new()
+            .name("child")
+            .spawn(std::future::pending::<()>());
+        let name = crate::engine::Handle::current()
+            .dump()
+            .into_iter()
+            .find(|task| task.id == child.id())
+            .and_then(|task| task.name);
+        child.abort();
+        name
+    }));
+    assert_eq!(name.unwrap().as_deref(), Some("child"));
+    engine.shutdown_now();
+}
+
+#[test]
+fn spawns_on_simulation() {
+    let mut sim = Simulation::new(1);
+    let handle = sim.handle().task_builder().name("sim").spawn(async { 1 });
+    assert_eq!(sim.block_on(handle), Ok(1));
+}
+
+#[test]
+fn named_tasks_appear_in_metrics() {
+    let engine = Engine::new(1, Fifo::new());
+    let handles: Vec<_> = ["poller", "poller", "reader"]
+        .into_iter()
+        .map(|name| {
+            engine
+                .task_builder()
+                .name(name)
+                .spawn(std::future::pending::<()>())
+        })
+        .chain([engine.spawn(std::future::pending::<()>())])
+        .collect();
+
+    let metrics = engine.scheduler_metrics();
+    let named: Vec<_> = metrics
+        .named_tasks
+        .iter()
+        .map(|(name, count)| (&**name, *count))
+        .collect();
+    assert_eq!(named, vec![("poller", 2), ("reader", 1)]);
+    assert_eq!(metrics.local_tasks, 0);
+
+    handles.iter().for_each(|handle| handle.abort());
+    assert!(wait_until(|| engine
+        .scheduler_metrics()
+        .named_tasks
+        .is_empty()));
+    engine.shutdown_now();
+}
+
+#[test]
+fn local_spawn_stays_on_current_engine() {
+    let here = Engine::new(1, Fifo::new());
+    let there = Engine::new(1, Fifo::new());
+    let other = there.handle().clone();
+
+    // 別のEngineのHandleを指定していても、localなら呼んだスレッドのEngineに登録する
+    let pinned = {
+        let _guard = here.handle().enter();
+        other
+            .task_builder()
+            .name("pinned")
+            .local()
+            .spawn(std::future::pending::<()>())
+    };
+
+    let dump = here.dump();
+    assert!(dump.iter().any(|task| task.id == pinned.id() && task.local));
+    assert!(there.dump().is_empty());
+    assert_eq!(here.scheduler_metrics().local_tasks, 1);
+
+    // Engineの外では指定したHandleに登録する
+    let outside = there.task_builder().local().spawn(async { 1 });
+    assert_eq!(block_on(outside), Ok(1));
+
+    pinned.abort();
+    here.shutdown_now();
+    there.shutdown_now();
+}
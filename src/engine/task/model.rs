@@ -0,0 +1,168 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll, Waker};
+
+use loom::sync::atomic::{AtomicUsize, Ordering};
+use loom::thread;
+
+use super::{Attributes, SharedTask, Task};
+
+// pollされた回数とFutureが破棄された回数を数える
+#[derive(Clone)]
+struct Counts {
+    polls: Arc<AtomicUsize>,
+    drops: Arc<AtomicUsize>,
+}
+
+impl Counts {
+    fn new() -> Self {
+        Self {
+            polls: Arc::new(AtomicUsize::new(0)),
+            drops: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+}
+
+struct Counted {
+    counts: Counts,
+    ready: bool,
+}
+
+impl Future for Counted {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<()> {
+        self.counts.polls.fetch_add(1, Ordering::SeqCst);
+        if self.ready {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+impl Drop for Counted {
+    fn drop(&mut self) {
+        self.counts.drops.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+fn notified_task(counts: &Counts, ready: bool) -> SharedTask {
+    let task = Task::from_future(
+        Counted {
+            counts: counts.clone(),
+            ready,
+        },
+        Attributes::default(),
+    );
+    assert!(task.notify());
+    task
+}
+
+fn poll(task: &SharedTask) -> Poll<()> {
+    task.poll(&mut Context::from_waker(Waker::noop()))
+}
+
+#[test]
+fn abort_racing_poll_drops_future_once() {
+    loom::model(|| {
+        let counts = Counts::new();
+        let task = notified_task(&counts, false);
+
+        let aborter = {
+            let task = task.clone();
+            thread::spawn(move || task.abort())
+        };
+        let _ = poll(&task);
+        aborter.join().unwrap();
+
+        // abortがpollの前後どちらに入っても、Futureはちょうど1回だけ破棄される
+        assert!(task.is_finished());
+        assert_eq!(counts.drops.load(Ordering::SeqCst), 1);
+        assert!(counts.polls.load(Ordering::SeqCst) <= 1);
+    });
+}
+
+#[test]
+fn queued_twice_is_polled_once() {
+    loom::model(|| {
+        let counts = Counts::new();
+        let task = notified_task(&counts, false);
+
+        // 2つのWorkerが同じタスクを取り出してしまった場合
+        let other = {
+            let task = task.clone();
+            thread::spawn(move || poll(&task))
+        };
+        let _ = poll(&task);
+        let _ = other.join().unwrap();
+
+        assert_eq!(counts.polls.load(Ordering::SeqCst), 1);
+        assert_eq!(counts.drops.load(Ordering::SeqCst), 0);
+    });
+}
+
+#[test]
+fn no_poll_after_complete() {
+    loom::model(|| {
+        let counts = Counts::new();
+        let task = notified_task(&counts, true);
+
+        let other = {
+            let task = task.clone();
+            thread::spawn(move || {
+                // 完了後に届いた通知とpollは何もしない
+                task.notify();
+                poll(&task)
+            })
+        };
+        let _ = poll(&task);
+        let _ = other.join().unwrap();
+
+        assert!(task.is_finished());
+        assert_eq!(counts.polls.load(Ordering::SeqCst), 1);
+        assert_eq!(counts.drops.load(Ordering::SeqCst), 1);
+    });
+}
+
+#[test]
+fn last_reference_frees_task_once() {
+    loom::model(|| {
+        let counts = Counts::new();
+        let task = notified_task(&counts, false);
+        let waker = crate::engine::waker::waker(task.clone());
+
+        // どちらのスレッドが最後の参照を落としても、Futureはちょうど1回だけ破棄される
+        let other = thread::spawn(move || drop(waker));
+        drop(task);
+        other.join().unwrap();
+
+        assert_eq!(counts.drops.load(Ordering::SeqCst), 1);
+    });
+}
+
+#[test]
+fn waker_drop_racing_abort_frees_once() {
+    loom::model(|| {
+        let counts = Counts::new();
+        let task = Task::from_future(
+            Counted {
+                counts: counts.clone(),
+                ready: false,
+            },
+            Attributes::default(),
+        );
+        let waker = crate::engine::waker::waker(task.clone());
+
+        // abortでFutureを破棄する間に、別のスレッドが最後から2番目の参照を落とす
+        let other = thread::spawn(move || drop(waker));
+        task.abort();
+        other.join().unwrap();
+
+        assert!(task.is_finished());
+        assert_eq!(SharedTask::ref_count(&task), 1);
+        drop(task);
+        assert_eq!(counts.drops.load(Ordering::SeqCst), 1);
+    });
+}
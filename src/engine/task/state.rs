@@ -0,0 +1,210 @@
+use std::fmt;
+
+use crate::loom::sync::atomic::{AtomicUsize, Ordering};
+
+// pollしている最中
+const RUNNING: usize = 0b0001;
+// 実行キューに入っている。RUNNING中に立った場合はpollの後でキューへ戻す
+const NOTIFIED: usize = 0b0010;
+// Futureは破棄済み。これ以降pollもwakeもされない
+const COMPLETE: usize = 0b0100;
+// abortが要求された。RUNNINGやNOTIFIEDを持っている側がFutureを破棄する
+const CANCELLED: usize = 0b1000;
+
+// 下位のビットが状態、それより上がSharedTaskとWakerの数
+const REF_SHIFT: usize = 4;
+const REF_ONE: usize = 1 << REF_SHIFT;
+const STATE_MASK: usize = REF_ONE - 1;
+
+// 実行権と参照カウントを1つのアトミックな値で持つ
+// 同じ値なので、状態の遷移と参照の増減がどう入り組んでも互いのビットを壊さない
+pub(crate) struct State(AtomicUsize);
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Snapshot(usize);
+
+impl Snapshot {
+    pub fn is_running(self) -> bool {
+        self.0 & RUNNING != 0
+    }
+
+    pub fn is_notified(self) -> bool {
+        self.0 & NOTIFIED != 0
+    }
+
+    pub fn is_complete(self) -> bool {
+        self.0 & COMPLETE != 0
+    }
+
+    pub fn is_cancelled(self) -> bool {
+        self.0 & CANCELLED != 0
+    }
+
+    pub fn ref_count(self) -> usize {
+        self.0 >> REF_SHIFT
+    }
+}
+
+impl fmt::Debug for Snapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Snapshot")
+            .field("running", &self.is_running())
+            .field("notified", &self.is_notified())
+            .field("complete", &self.is_complete())
+            .field("cancelled", &self.is_cancelled())
+            .field("refs", &self.ref_count())
+            .finish()
+    }
+}
+
+// poll後にRUNNINGを外した結果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Idle {
+    Ok,
+    // poll中にwakeされたので、呼び出し側がキューへ戻す
+    Notified,
+    // poll中にabortされたので、RUNNINGのまま呼び出し側がFutureを破棄する
+    Cancelled,
+}
+
+impl State {
+    // 作った側が最初の参照を持つ
+    pub(crate) fn new() -> Self {
+        State(AtomicUsize::new(REF_ONE))
+    }
+
+    pub(crate) fn load(&self) -> Snapshot {
+        Snapshot(self.0.load(Ordering::Acquire))
+    }
+
+    // wake。trueなら呼び出し側がキューに入れる
+    pub(crate) fn transition_to_notified(&self) -> bool {
+        let mut enqueue = false;
+        let _ = self.fetch_update(|curr| {
+            enqueue = false;
+            if curr.is_complete() || curr.is_notified() {
+                return None;
+            }
+            // RUNNING中ならpollを終えた側がキューへ戻す
+            enqueue = !curr.is_running();
+            Some(curr.0 | NOTIFIED)
+        });
+        enqueue
+    }
+
+    // キューから取り出したタスクのpollを始める
+    // NOTIFIEDを持っていなければ（二重にキューに入っていた等）pollしない
+    pub(crate) fn transition_to_running(&self) -> Result<Snapshot, Snapshot> {
+        self.fetch_update(|curr| {
+            if !curr.is_notified() || curr.is_running() || curr.is_complete() {
+                return None;
+            }
+            Some((curr.0 & !NOTIFIED) | RUNNING)
+        })
+    }
+
+    // Pendingを返したpollの後
+    pub(crate) fn transition_to_idle(&self) -> Idle {
+        let mut idle = Idle::Ok;
+        let _ = self.fetch_update(|curr| {
+            debug_assert!(curr.is_running());
+            if curr.is_cancelled() {
+                idle = Idle::Cancelled;
+                return None;
+            }
+            idle = if curr.is_notified() {
+                Idle::Notified
+            } else {
+                Idle::Ok
+            };
+            Some(curr.0 & !RUNNING)
+        });
+        idle
+    }
+
+    // Futureを破棄した後。RUNNINGを持っている側だけが呼ぶ
+    pub(crate) fn transition_to_complete(&self) {
+        let _ = self.fetch_update(|curr| {
+            debug_assert!(curr.is_running());
+            Some((curr.0 & !(RUNNING | NOTIFIED)) | COMPLETE)
+        });
+    }
+
+    // abort。trueなら呼び出し側がRUNNINGを得たので、Futureを破棄してcompleteにする
+    pub(crate) fn transition_to_cancelled(&self) -> bool {
+        let mut claimed = false;
+        let _ = self.fetch_update(|curr| {
+            claimed = false;
+            if curr.is_complete() || curr.is_cancelled() {
+                return None;
+            }
+            if curr.is_running() || curr.is_notified() {
+                // pollする側に任せる
+                return Some(curr.0 | CANCELLED);
+            }
+            claimed = true;
+            Some(curr.0 | CANCELLED | RUNNING)
+        });
+        claimed
+    }
+
+    // 参照を持っている側だけが呼ぶので、0から増えることはない
+    pub(crate) fn ref_inc(&self) {
+        let prev = self.0.fetch_add(REF_ONE, Ordering::Relaxed);
+        debug_assert!(prev >> REF_SHIFT > 0);
+    }
+
+    // 参照を持っていない側が増やす。最後の参照が破棄された後（解放の途中）ならfalse
+    pub(crate) fn try_ref_inc(&self) -> bool {
+        let mut curr = self.0.load(Ordering::Relaxed);
+        loop {
+            if curr >> REF_SHIFT == 0 {
+                return false;
+            }
+            match self.0.compare_exchange_weak(
+                curr,
+                curr + REF_ONE,
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return true,
+                Err(actual) => curr = actual,
+            }
+        }
+    }
+
+    // trueなら最後の参照だったので、呼び出し側がタスクを解放する
+    // 解放する側が、他のスレッドが最後に触った内容を見られるようにAcqRelで減らす
+    pub(crate) fn ref_dec(&self) -> bool {
+        let prev = self.0.fetch_sub(REF_ONE, Ordering::AcqRel);
+        debug_assert!(prev >> REF_SHIFT > 0);
+        prev & !STATE_MASK == REF_ONE
+    }
+
+    fn fetch_update(
+        &self,
+        mut f: impl FnMut(Snapshot) -> Option<usize>,
+    ) -> Result<Snapshot, Snapshot> {
+        let mut curr = self.0.load(Ordering::Acquire);
+        loop {
+            // 何も変えない場合も同じ値でCASし、最新の値を見て判断したことを確かめる
+            // 古い値を見たまま諦めると、poll中のwakeがRUNNINGの外れる前の状態を見て取りこぼされる
+            let next = f(Snapshot(curr));
+            match self.0.compare_exchange(
+                curr,
+                next.unwrap_or(curr),
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => return next.map(Snapshot).ok_or(Snapshot(curr)),
+                Err(actual) => curr = actual,
+            }
+        }
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod test;
+
+#[cfg(all(test, loom))]
+mod model;
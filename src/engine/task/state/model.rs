@@ -0,0 +1,208 @@
+use loom::sync::Arc;
+use loom::sync::atomic::{AtomicUsize, Ordering};
+use loom::thread;
+
+use super::{Idle, State};
+
+#[test]
+fn wake_during_poll_is_not_lost() {
+    loom::model(|| {
+        // poll中のタスクにwakeが来る
+        let state = Arc::new(State::new());
+        assert!(state.transition_to_notified());
+        state.transition_to_running().unwrap();
+
+        let waker = {
+            let state = state.clone();
+            thread::spawn(move || state.transition_to_notified())
+        };
+        let idle = state.transition_to_idle();
+        let enqueued = waker.join().unwrap();
+
+        // wakeした側かpollした側の、ちょうど一方がキューへ戻す
+        let rescheduled = enqueued as usize + (idle == Idle::Notified) as usize;
+        assert_eq!(rescheduled, 1);
+        assert!(state.load().is_notified());
+    });
+}
+
+#[test]
+fn concurrent_wakes_enqueue_once() {
+    loom::model(|| {
+        let state = Arc::new(State::new());
+        let enqueued = Arc::new(AtomicUsize::new(0));
+
+        let threads: Vec<_> = (0..2)
+            .map(|_| {
+                let state = state.clone();
+                let enqueued = enqueued.clone();
+                thread::spawn(move || {
+                    if state.transition_to_notified() {
+                        enqueued.fetch_add(1, Ordering::SeqCst);
+                    }
+                })
+            })
+            .collect();
+        threads.into_iter().for_each(|t| t.join().unwrap());
+
+        assert_eq!(enqueued.load(Ordering::SeqCst), 1);
+    });
+}
+
+#[test]
+fn only_one_poller_runs() {
+    loom::model(|| {
+        let state = Arc::new(State::new());
+        assert!(state.transition_to_notified());
+
+        let other = {
+            let state = state.clone();
+            thread::spawn(move || state.transition_to_running().is_ok())
+        };
+        let mine = state.transition_to_running().is_ok();
+        let theirs = other.join().unwrap();
+
+        assert!(mine ^ theirs);
+    });
+}
+
+#[test]
+fn abort_during_poll_drops_future_once() {
+    loom::model(|| {
+        let state = Arc::new(State::new());
+        assert!(state.transition_to_notified());
+        let drops = Arc::new(AtomicUsize::new(0));
+
+        let aborter = {
+            let state = state.clone();
+            let drops = drops.clone();
+            thread::spawn(move || {
+                if state.transition_to_cancelled() {
+                    drops.fetch_add(1, Ordering::SeqCst);
+                    state.transition_to_complete();
+                }
+            })
+        };
+
+        let snapshot = state.transition_to_running().unwrap();
+        if snapshot.is_cancelled() || state.transition_to_idle() == Idle::Cancelled {
+            drops.fetch_add(1, Ordering::SeqCst);
+            state.transition_to_complete();
+        }
+        aborter.join().unwrap();
+
+        // pollした側がabortを見逃した場合は、abortした側が破棄している
+        assert_eq!(drops.load(Ordering::SeqCst), 1);
+        assert!(state.load().is_complete());
+    });
+}
+
+#[test]
+fn wake_after_complete_is_ignored() {
+    loom::model(|| {
+        let state = Arc::new(State::new());
+        assert!(state.transition_to_notified());
+
+        let waker = {
+            let state = state.clone();
+            thread::spawn(move || state.transition_to_notified())
+        };
+        state.transition_to_running().unwrap();
+        state.transition_to_complete();
+
+        assert!(!waker.join().unwrap());
+        let snapshot = state.load();
+        assert!(snapshot.is_complete() && !snapshot.is_notified());
+    });
+}
+
+#[test]
+fn ref_dec_racing_complete_keeps_both() {
+    loom::model(|| {
+        let state = Arc::new(State::new());
+        state.ref_inc();
+        assert!(state.transition_to_notified());
+        state.transition_to_running().unwrap();
+
+        // pollを終えたタスクの完了と、Wakerの破棄が同時に起きる
+        let dropper = {
+            let state = state.clone();
+            thread::spawn(move || state.ref_dec())
+        };
+        state.transition_to_complete();
+        assert!(!dropper.join().unwrap());
+
+        let snapshot = state.load();
+        assert!(snapshot.is_complete());
+        assert!(!snapshot.is_running() && !snapshot.is_notified());
+        assert_eq!(snapshot.ref_count(), 1);
+        assert!(state.ref_dec());
+    });
+}
+
+#[test]
+fn ref_inc_racing_cancel_keeps_both() {
+    loom::model(|| {
+        let state = Arc::new(State::new());
+
+        // 待機中のタスクのabortと、Wakerの複製からのwakeが同時に起きる
+        let waker = {
+            let state = state.clone();
+            thread::spawn(move || {
+                state.ref_inc();
+                state.transition_to_notified()
+            })
+        };
+        let claimed = state.transition_to_cancelled();
+        if claimed {
+            state.transition_to_complete();
+        }
+        let enqueued = waker.join().unwrap();
+
+        // どちらか一方だけがFutureの破棄に責任を持つ
+        assert!(claimed ^ enqueued);
+        let snapshot = state.load();
+        assert!(snapshot.is_cancelled());
+        assert_eq!(snapshot.ref_count(), 2);
+    });
+}
+
+#[test]
+fn last_ref_is_seen_once() {
+    loom::model(|| {
+        let state = Arc::new(State::new());
+        state.ref_inc();
+        assert!(state.transition_to_notified());
+
+        let other = {
+            let state = state.clone();
+            thread::spawn(move || state.ref_dec())
+        };
+        // 参照の減少と状態の遷移が交互に入っても数はずれない
+        state.transition_to_running().unwrap();
+        let mine = state.ref_dec();
+        let theirs = other.join().unwrap();
+
+        assert!(mine ^ theirs);
+        assert!(state.load().is_running());
+        assert_eq!(state.load().ref_count(), 0);
+    });
+}
+
+#[test]
+fn upgrade_racing_last_ref_dec() {
+    loom::model(|| {
+        let state = Arc::new(State::new());
+
+        let upgrader = {
+            let state = state.clone();
+            thread::spawn(move || state.try_ref_inc())
+        };
+        let last = state.ref_dec();
+        let upgraded = upgrader.join().unwrap();
+
+        // 先に増やせたなら最後の参照ではなく、解放が始まった後なら増やせない
+        assert!(last ^ upgraded);
+        assert_eq!(state.load().ref_count(), upgraded as usize);
+    });
+}
@@ -0,0 +1,123 @@
+use super::{Idle, State};
+
+fn notified() -> State {
+    let state = State::new();
+    assert!(state.transition_to_notified());
+    state
+}
+
+fn running() -> State {
+    let state = notified();
+    assert!(state.transition_to_running().is_ok());
+    state
+}
+
+#[test]
+fn notify_idle_enqueues_once() {
+    let state = State::new();
+    assert!(state.transition_to_notified());
+    assert!(!state.transition_to_notified());
+    assert!(state.load().is_notified());
+}
+
+#[test]
+fn run_requires_notification() {
+    let state = State::new();
+    assert!(state.transition_to_running().is_err());
+
+    let state = notified();
+    let snapshot = state.transition_to_running().unwrap();
+    assert!(snapshot.is_running());
+    assert!(!snapshot.is_notified());
+
+    // 二重にキューに入っていても2回目はpollしない
+    assert!(state.transition_to_running().is_err());
+}
+
+#[test]
+fn idle_after_poll() {
+    let state = running();
+    assert_eq!(state.transition_to_idle(), Idle::Ok);
+    let snapshot = state.load();
+    assert!(!snapshot.is_running() && !snapshot.is_notified());
+}
+
+#[test]
+fn notify_while_running_is_kept_for_after_poll() {
+    let state = running();
+    assert!(!state.transition_to_notified());
+    assert!(state.load().is_notified());
+
+    assert_eq!(state.transition_to_idle(), Idle::Notified);
+    let snapshot = state.load();
+    assert!(!snapshot.is_running() && snapshot.is_notified());
+    assert!(state.transition_to_running().is_ok());
+}
+
+#[test]
+fn complete_clears_notification() {
+    let state = running();
+    assert!(!state.transition_to_notified());
+    state.transition_to_complete();
+
+    let snapshot = state.load();
+    assert!(snapshot.is_complete());
+    assert!(!snapshot.is_running() && !snapshot.is_notified());
+    assert!(!state.transition_to_notified());
+    assert!(state.transition_to_running().is_err());
+}
+
+#[test]
+fn cancel_idle_claims_task() {
+    let state = State::new();
+    assert!(state.transition_to_cancelled());
+    assert!(state.load().is_running());
+    assert!(!state.transition_to_notified());
+
+    state.transition_to_complete();
+    assert!(state.load().is_complete());
+    assert!(!state.transition_to_cancelled());
+}
+
+#[test]
+fn cancel_notified_is_handled_by_poll() {
+    let state = notified();
+    assert!(!state.transition_to_cancelled());
+    let snapshot = state.transition_to_running().unwrap();
+    assert!(snapshot.is_cancelled());
+}
+
+#[test]
+fn cancel_running_is_handled_after_poll() {
+    let state = running();
+    assert!(!state.transition_to_cancelled());
+    assert_eq!(state.transition_to_idle(), Idle::Cancelled);
+    // Futureを破棄するまでRUNNINGのまま
+    assert!(state.load().is_running());
+}
+
+#[test]
+fn cancel_complete_does_nothing() {
+    let state = running();
+    state.transition_to_complete();
+    assert!(!state.transition_to_cancelled());
+    assert!(!state.load().is_cancelled());
+}
+
+#[test]
+fn refs_live_beside_state_bits() {
+    let state = running();
+    assert_eq!(state.load().ref_count(), 1);
+    state.ref_inc();
+    state.transition_to_complete();
+
+    let snapshot = state.load();
+    assert!(snapshot.is_complete() && !snapshot.is_running());
+    assert_eq!(snapshot.ref_count(), 2);
+
+    assert!(!state.ref_dec());
+    assert!(state.ref_dec());
+    // 解放が始まった後はupgradeできない
+    assert!(!state.try_ref_inc());
+    assert!(state.load().is_complete());
+}
@@ -0,0 +1,124 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::task::{Context, Poll, Waker};
+
+use super::{Attributes, SharedTask, Task};
+
+struct DropFlag(Arc<AtomicBool>);
+
+impl Drop for DropFlag {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+}
+
+fn pending_task(dropped: Arc<AtomicBool>) -> SharedTask {
+    let flag = DropFlag(dropped);
+    Task::from_future(
+        async move {
+            let _flag = flag;
+            std::future::pending::<()>().await;
+        },
+        Attributes::default(),
+    )
+}
+
+#[test]
+fn task_ids_are_unique() {
+    let a = Task::from_future(async {}, Attributes::default());
+    let b = Task::from_future(async {}, Attributes::default());
+    assert_ne!(a.id(), b.id());
+}
+
+#[test]
+fn abort_idle_task_drops_future() {
+    let dropped = Arc::new(AtomicBool::new(false));
+    let task = pending_task(dropped.clone());
+
+    task.abort();
+
+    assert!(task.state().is_cancelled());
+    assert!(task.is_finished());
+    assert!(dropped.load(Ordering::SeqCst));
+}
+
+#[test]
+fn abort_notified_task_cancels_on_next_poll() {
+    let dropped = Arc::new(AtomicBool::new(false));
+    let task = pending_task(dropped.clone());
+    assert!(task.notify());
+
+    task.abort();
+    assert!(!dropped.load(Ordering::SeqCst));
+    assert!(!task.is_finished());
+
+    let mut cx = Context::from_waker(Waker::noop());
+    assert_eq!(task.poll(&mut cx), Poll::Ready(()));
+    assert!(task.is_finished());
+    assert!(dropped.load(Ordering::SeqCst));
+}
+
+#[test]
+fn abort_after_poll_pending() {
+    let dropped = Arc::new(AtomicBool::new(false));
+    let task = pending_task(dropped.clone());
+    assert!(task.notify());
+
+    let mut cx = Context::from_waker(Waker::noop());
+    assert_eq!(task.poll(&mut cx), Poll::Pending);
+    let state = task.state();
+    assert!(!state.is_running() && !state.is_notified() && !state.is_complete());
+
+    task.abort();
+    assert!(task.is_finished());
+    assert!(dropped.load(Ordering::SeqCst));
+}
+
+#[test]
+fn abort_completed_task_does_nothing() {
+    let task = Task::from_future(async {}, Attributes::default());
+    assert!(task.notify());
+
+    let mut cx = Context::from_waker(Waker::noop());
+    assert_eq!(task.poll(&mut cx), Poll::Ready(()));
+
+    task.abort();
+    assert!(task.is_finished());
+    assert!(!task.state().is_cancelled());
+}
+
+#[test]
+fn poll_without_notification_does_nothing() {
+    let task = Task::from_future(async {}, Attributes::default());
+
+    let mut cx = Context::from_waker(Waker::noop());
+    assert_eq!(task.poll(&mut cx), Poll::Pending);
+    assert!(!task.is_finished());
+}
+
+#[test]
+fn notify_is_idempotent_until_polled() {
+    let task = Task::from_future(async {}, Attributes::default());
+    assert!(task.notify());
+    assert!(!task.notify());
+
+    let mut cx = Context::from_waker(Waker::noop());
+    assert_eq!(task.poll(&mut cx), Poll::Ready(()));
+    assert!(!task.notify());
+}
+
+#[test]
+fn display_includes_name() {
+    let unnamed = Task::from_future(async {}, Attributes::default());
+    assert_eq!(unnamed.to_string(), format!("task {}", unnamed.id()));
+
+    let named = Task::from_future(
+        async {},
+        Attributes {
+            name: Some("fetch".into()),
+            ..Default::default()
+        },
+    );
+    assert_eq!(named.to_string(), format!("task {} (fetch)", named.id()));
+    assert_eq!(named.dump().name.as_deref(), Some("fetch"));
+}
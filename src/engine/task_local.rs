@@ -0,0 +1,159 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::thread;
+
+// タスクごとの値を宣言する
+// scopeで包んだFutureがpollされている間だけ、pollしているスレッドに値が置かれる
+// await中にほかのWorkerへ移っても、次のpollではそのWorkerで同じ値が見える
+#[macro_export]
+macro_rules! task_local {
+    ($(#[$attr:meta])* $vis:vis static $name:ident: $t:ty; $($rest:tt)*) => {
+        $crate::task_local!($(#[$attr])* $vis static $name: $t);
+        $crate::task_local!($($rest)*);
+    };
+    ($(#[$attr:meta])* $vis:vis static $name:ident: $t:ty) => {
+        $(#[$attr])*
+        $vis static $name: $crate::engine::task_local::LocalKey<$t> =
+            $crate::engine::task_local::LocalKey::new({
+                ::std::thread_local! {
+                    static SLOT: ::std::cell::RefCell<::std::option::Option<$t>> =
+                        const { ::std::cell::RefCell::new(::std::option::Option::None) };
+                }
+                &SLOT
+            });
+    };
+    () => {};
+}
+
+pub struct LocalKey<T: 'static> {
+    slot: &'static thread::LocalKey<RefCell<Option<T>>>,
+}
+
+impl<T: 'static> LocalKey<T> {
+    // task_local!から使う
+    #[doc(hidden)]
+    pub const fn new(slot: &'static thread::LocalKey<RefCell<Option<T>>>) -> Self {
+        Self { slot }
+    }
+
+    // futureの中からだけ値が見える。spawnしたタスクには引き継がれない
+    pub fn scope<F: Future>(&'static self, value: T, future: F) -> TaskLocalFuture<T, F> {
+        TaskLocalFuture {
+            key: self,
+            value: Some(value),
+            future: Some(Box::pin(future)),
+        }
+    }
+
+    // fを呼んでいる間だけ値を置く
+    pub fn sync_scope<R>(&'static self, value: T, f: impl FnOnce() -> R) -> R {
+        let mut value = Some(value);
+        self.enter(&mut value, f)
+    }
+
+    // scopeの外で呼ぶとパニックする
+    pub fn with<R>(&'static self, f: impl FnOnce(&T) -> R) -> R {
+        self.try_with(f)
+            .expect("cannot access a task-local value outside of its scope")
+    }
+
+    pub fn try_with<R>(&'static self, f: impl FnOnce(&T) -> R) -> Result<R, AccessError> {
+        self.slot.with(|slot| {
+            let slot = slot.borrow();
+            slot.as_ref().map(f).ok_or(AccessError)
+        })
+    }
+
+    pub fn get(&'static self) -> T
+    where
+        T: Clone,
+    {
+        self.with(T::clone)
+    }
+
+    // valueをスレッドに置いてfを呼び、終わったら（パニックしても）前の値と入れ替えて戻す
+    fn enter<R>(&'static self, value: &mut Option<T>, f: impl FnOnce() -> R) -> R {
+        struct Restore<'a, T: 'static> {
+            key: &'static LocalKey<T>,
+            value: &'a mut Option<T>,
+        }
+
+        impl<T: 'static> Drop for Restore<'_, T> {
+            fn drop(&mut self) {
+                self.key.swap(self.value);
+            }
+        }
+
+        self.swap(value);
+        let _restore = Restore { key: self, value };
+        f()
+    }
+
+    fn swap(&'static self, value: &mut Option<T>) {
+        self.slot.with(|slot| {
+            let mut slot = slot
+                .try_borrow_mut()
+                .expect("cannot enter a task-local scope while the value is borrowed");
+            std::mem::swap(&mut *slot, value);
+        });
+    }
+}
+
+impl<T: 'static> fmt::Debug for LocalKey<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LocalKey").finish_non_exhaustive()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AccessError;
+
+impl fmt::Display for AccessError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "task-local value is not set")
+    }
+}
+
+impl std::error::Error for AccessError {}
+
+// pollのたびに値を置き、pollが終わったら取り戻して持ち歩く
+pub struct TaskLocalFuture<T: 'static, F> {
+    key: &'static LocalKey<T>,
+    value: Option<T>,
+    // 完了したらNoneにして、値を置かずに破棄されないようにする
+    future: Option<Pin<Box<F>>>,
+}
+
+impl<T: 'static, F: Future> Future for TaskLocalFuture<T, F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<F::Output> {
+        let this = self.get_mut();
+        let future = this
+            .future
+            .as_mut()
+            .expect("TaskLocalFuture polled after completion");
+        let poll = this.key.enter(&mut this.value, || future.as_mut().poll(cx));
+        if poll.is_ready() {
+            this.future = None;
+        }
+        poll
+    }
+}
+
+impl<T: 'static, F> Unpin for TaskLocalFuture<T, F> {}
+
+// 途中で破棄されたときも、futureのDropからは値が見えるようにする
+impl<T: 'static, F> Drop for TaskLocalFuture<T, F> {
+    fn drop(&mut self) {
+        if let Some(future) = self.future.take() {
+            self.key.enter(&mut self.value, || drop(future));
+        }
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod test;
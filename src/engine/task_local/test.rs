@@ -0,0 +1,117 @@
+use std::collections::HashSet;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::thread;
+
+use super::AccessError;
+use crate::engine::schedule::fifo::Fifo;
+use crate::engine::{Engine, block_on, spawn, yield_now};
+
+task_local! {
+    static REQUEST_ID: u64;
+    static TENANT: String;
+}
+
+#[test]
+fn with_outside_scope_is_an_error() {
+    assert_eq!(REQUEST_ID.try_with(|id| *id), Err(AccessError));
+}
+
+#[test]
+#[should_panic(expected = "outside of its scope")]
+fn with_outside_scope_panics() {
+    REQUEST_ID.with(|_| ());
+}
+
+#[test]
+fn value_is_visible_across_awaits() {
+    let engine = Engine::new(4, Fifo::new());
+    let handles: Vec<_> = (0..8)
+        .map(|i| {
+            engine.spawn(REQUEST_ID.scope(i, async move {
+                let mut threads = HashSet::new();
+                for _ in 0..100 {
+                    assert_eq!(REQUEST_ID.get(), i);
+                    threads.insert(thread::current().id());
+                    yield_now().await;
+                }
+                threads.len()
+            }))
+        })
+        .collect();
+    for handle in handles {
+        assert!(block_on(handle).unwrap() >= 1);
+    }
+    // pollが終われば、Workerスレッドには値が残らない
+    let leaked = block_on(engine.spawn(async { REQUEST_ID.try_with(|_| ()).is_ok() }));
+    assert_eq!(leaked, Ok(false));
+    engine.shutdown_now();
+}
+
+#[test]
+fn nested_scope_restores_outer_value() {
+    let out = block_on(TENANT.scope("outer".to_string(), async {
+        let inner = TENANT
+            .scope("inner".to_string(), async {
+                yield_now().await;
+                TENANT.get()
+            })
+            .await;
+        (inner, TENANT.get())
+    }));
+    assert_eq!(out, ("inner".to_string(), "outer".to_string()));
+    assert!(TENANT.try_with(|_| ()).is_err());
+}
+
+#[test]
+fn spawned_task_does_not_inherit() {
+    let engine = Engine::new(1, Fifo::new());
+    let inherited = block_on(engine.spawn(REQUEST_ID.scope(1, async {
+        spawn(async { REQUEST_ID.try_with(|id| *id).ok() })
+            .await
+            .unwrap()
+    })));
+    assert_eq!(inherited, Ok(None));
+    engine.shutdown_now();
+}
+
+#[test]
+fn sync_scope_sets_value() {
+    let id = REQUEST_ID.sync_scope(5, || REQUEST_ID.with(|id| id * 2));
+    assert_eq!(id, 10);
+    assert!(REQUEST_ID.try_with(|_| ()).is_err());
+}
+
+#[test]
+fn value_is_restored_after_panic() {
+    let result = std::panic::catch_unwind(|| REQUEST_ID.sync_scope(1, || panic!("boom")));
+    assert!(result.is_err());
+    assert!(REQUEST_ID.try_with(|_| ()).is_err());
+}
+
+// 破棄されるときにタスクローカルの値を読む
+struct ReadOnDrop(Arc<Mutex<Option<u64>>>);
+
+impl Future for ReadOnDrop {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<()> {
+        Poll::Pending
+    }
+}
+
+impl Drop for ReadOnDrop {
+    fn drop(&mut self) {
+        *self.0.lock().unwrap() = REQUEST_ID.try_with(|id| *id).ok();
+    }
+}
+
+#[test]
+fn value_is_visible_while_dropping() {
+    let seen = Arc::new(Mutex::new(None));
+    let future = REQUEST_ID.scope(9, ReadOnDrop(seen.clone()));
+    drop(future);
+    assert_eq!(*seen.lock().unwrap(), Some(9));
+}
@@ -1,7 +1,13 @@
 use super::block_on;
+use crate::engine::Engine;
+use crate::engine::schedule::fifo::Fifo;
+use crate::engine::spawn;
+use crate::engine::task::JoinError;
+use crate::time::sleep;
 
-use std::future::Future;
+use std::future::{Future, pending};
 use std::task::Poll;
+use std::time::{Duration, Instant};
 
 #[test]
 fn block_on_normally() {
@@ -34,3 +40,141 @@ fn block_on_normally() {
 
     assert_eq!(res, 42);
 }
+
+#[test]
+fn shutdown_waits_for_pending_tasks() {
+    let engine = Engine::new(2, Fifo::new());
+    let handle = engine.spawn(async {
+        sleep(Duration::from_millis(30)).await;
+        42
+    });
+
+    assert!(engine.shutdown(Duration::from_secs(5)));
+    assert_eq!(block_on(handle), Ok(42));
+}
+
+#[test]
+fn graceful_shutdown_lets_pending_tasks_finish() {
+    let engine = Engine::new(2, Fifo::new());
+    let handle = engine.spawn(async {
+        sleep(Duration::from_millis(30)).await;
+        42
+    });
+
+    assert!(engine.graceful_shutdown());
+    assert_eq!(block_on(handle), Ok(42));
+}
+
+#[test]
+fn shutdown_cancels_tasks_after_timeout() {
+    let engine = Engine::new(2, Fifo::new());
+    let finished = engine.spawn(async { 1 });
+    let stuck = engine.spawn(pending::<()>());
+
+    let started = Instant::now();
+    assert!(!engine.shutdown(Duration::from_millis(30)));
+    assert!(started.elapsed() >= Duration::from_millis(30));
+
+    assert_eq!(block_on(finished), Ok(1));
+    assert_eq!(block_on(stuck), Err(JoinError::Shutdown));
+}
+
+#[test]
+fn shutdown_now_cancels_sleeping_and_queued_tasks() {
+    // Workerがいないので、登録したタスクはキューに残ったまま
+    let engine = Engine::new(0, Fifo::new());
+    let queued = engine.spawn(async { 1 });
+    let engine_with_timer = Engine::new(1, Fifo::new());
+    let sleeping = engine_with_timer.spawn(sleep(Duration::from_secs(3600)));
+
+    let started = Instant::now();
+    engine.shutdown_now();
+    engine_with_timer.shutdown_now();
+
+    assert_eq!(block_on(queued), Err(JoinError::Shutdown));
+    assert_eq!(block_on(sleeping), Err(JoinError::Shutdown));
+    assert!(started.elapsed() < Duration::from_secs(1));
+}
+
+#[test]
+fn spawn_during_shutdown_is_rejected() {
+    let engine = Engine::new(2, Fifo::new());
+    let handle = engine.spawn(async {
+        // 停止が始まってから新しいタスクを登録する
+        sleep(Duration::from_millis(30)).await;
+        spawn(async { 1 }).await
+    });
+
+    assert!(engine.shutdown(Duration::from_secs(5)));
+    assert_eq!(block_on(handle), Ok(Err(JoinError::Shutdown)));
+}
+
+#[test]
+fn receiver_of_cancelled_task_does_not_hang() {
+    let mut engine = Engine::new(1, Fifo::new());
+    let receiver = engine.reserve(pending::<i32>(), None);
+    engine.shutdown_now();
+
+    assert_eq!(block_on(receiver), Err(JoinError::Shutdown));
+}
+
+#[test]
+fn receiver_awaited_across_shutdown_now() {
+    let mut engine = Engine::new(1, Fifo::new());
+    let receiver = engine.reserve(
+        async {
+            sleep(Duration::from_secs(3600)).await;
+            1
+        },
+        None,
+    );
+
+    // 待っている最中にEngineが止まっても、パニックせずにErr(Shutdown)を受け取る
+    let waiter = std::thread::spawn(move || block_on(receiver));
+    std::thread::sleep(Duration::from_millis(20));
+    engine.shutdown_now();
+
+    assert_eq!(waiter.join().unwrap(), Err(JoinError::Shutdown));
+}
+
+#[test]
+fn drop_shuts_down_engine() {
+    let engine = Engine::new(2, Fifo::new());
+    let handle = engine.handle().clone();
+    let finished = engine.spawn(async {
+        sleep(Duration::from_millis(10)).await;
+        1
+    });
+    drop(engine);
+
+    assert_eq!(block_on(finished), Ok(1));
+    // 停止した後に登録したタスクは実行されない
+    assert_eq!(
+        block_on(handle.spawn(async { 2 })),
+        Err(JoinError::Shutdown)
+    );
+}
+
+#[test]
+fn shutdown_signal_lets_tasks_clean_up() {
+    let engine = Engine::new(2, Fifo::new());
+    let signal = engine.handle().shutdown_signal();
+    let handle = engine.spawn(async move {
+        signal.await;
+        // 停止が始まってからでもタイマーは使える
+        sleep(Duration::from_millis(10)).await;
+        "cleaned up"
+    });
+
+    assert!(engine.shutdown(Duration::from_secs(5)));
+    assert_eq!(block_on(handle), Ok("cleaned up"));
+}
+
+#[test]
+fn shutdown_signal_is_ready_after_shutdown() {
+    let engine = Engine::new(1, Fifo::new());
+    let handle = engine.handle().clone();
+    engine.shutdown_now();
+
+    block_on(handle.shutdown_signal());
+}
@@ -0,0 +1,192 @@
+use std::fmt::Write as _;
+use std::io;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::engine::task::{Task, TaskId};
+use crate::engine::worker::WorkerId;
+
+// タスクがどのWorkerでいつ動いたかを記録する
+// Builder::with_recorderで渡したEngineだけが記録する。複製しても同じ記録を共有する
+#[derive(Clone)]
+pub struct Recorder {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    started: Instant,
+    events: Mutex<Vec<TraceEvent>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventKind {
+    // Engineに登録された
+    Spawn,
+    // 実行キューに入った
+    Schedule,
+    PollStart,
+    PollEnd,
+    // Wakerが呼ばれた
+    Wake,
+    // 完了するかキャンセルされて、Futureが破棄された
+    Complete,
+}
+
+impl EventKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            EventKind::Spawn => "spawn",
+            EventKind::Schedule => "schedule",
+            EventKind::PollStart => "poll",
+            EventKind::PollEnd => "poll",
+            EventKind::Wake => "wake",
+            EventKind::Complete => "complete",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceEvent {
+    pub kind: EventKind,
+    pub task: TaskId,
+    pub name: Option<Arc<str>>,
+    pub deadline: Option<u64>,
+    // Worker以外のスレッドで起きたものはNone
+    pub worker: Option<WorkerId>,
+    // Recorderを作ってからの時間
+    pub at: Duration,
+}
+
+impl Recorder {
+    pub fn new() -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                started: Instant::now(),
+                events: Mutex::new(Vec::new()),
+            }),
+        }
+    }
+
+    // 記録した順に返す
+    pub fn events(&self) -> Vec<TraceEvent> {
+        self.inner.events.lock().unwrap().clone()
+    }
+
+    pub fn clear(&self) {
+        self.inner.events.lock().unwrap().clear();
+    }
+
+    // chrome://tracingやPerfettoで開けるTrace Event形式のJSONを書き出す
+    // pollはWorkerごとの区間として、それ以外は瞬間のイベントとして並ぶ
+    pub fn write_chrome_trace(&self, mut writer: impl io::Write) -> io::Result<()> {
+        writer.write_all(self.to_chrome_trace().as_bytes())
+    }
+
+    pub fn to_chrome_trace(&self) -> String {
+        let events = self.events();
+        let mut threads: Vec<Option<WorkerId>> = events.iter().map(|e| e.worker).collect();
+        threads.sort();
+        threads.dedup();
+
+        let mut entries = vec![
+            r#"{"name":"process_name","ph":"M","pid":1,"tid":0,"args":{"name":"async_runtime"}}"#
+                .to_string(),
+        ];
+        for worker in threads {
+            let name = match worker {
+                Some(id) => format!("worker {id}"),
+                None => "external".to_string(),
+            };
+            entries.push(format!(
+                r#"{{"name":"thread_name","ph":"M","pid":1,"tid":{},"args":{{"name":{}}}}}"#,
+                tid(worker),
+                quote(&name)
+            ));
+        }
+        for event in &events {
+            entries.push(chrome_event(event));
+        }
+
+        let mut out = String::from("{\"traceEvents\":[\n");
+        out.push_str(&entries.join(",\n"));
+        out.push_str("\n]}\n");
+        out
+    }
+
+    pub(crate) fn record(&self, kind: EventKind, task: &Task, worker: Option<WorkerId>) {
+        let event = TraceEvent {
+            kind,
+            task: task.id(),
+            name: task.shared_name().cloned(),
+            deadline: task.deadline(),
+            worker,
+            at: self.inner.started.elapsed(),
+        };
+        self.inner.events.lock().unwrap().push(event);
+    }
+}
+
+impl Default for Recorder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Worker以外のスレッドは0にまとめる
+fn tid(worker: Option<WorkerId>) -> usize {
+    worker.map_or(0, |id| id.index() + 1)
+}
+
+fn chrome_event(event: &TraceEvent) -> String {
+    // pollの区間にはタスクの名前を付けて、タイムライン上で見分けられるようにする
+    let (name, phase) = match event.kind {
+        EventKind::PollStart => (label(event), "\"ph\":\"B\""),
+        EventKind::PollEnd => (label(event), "\"ph\":\"E\""),
+        kind => (kind.as_str().to_string(), "\"ph\":\"i\",\"s\":\"t\""),
+    };
+    let mut args = format!("\"task\":{}", event.task);
+    if let Some(task_name) = &event.name {
+        let _ = write!(args, ",\"name\":{}", quote(task_name));
+    }
+    if let Some(deadline) = event.deadline {
+        let _ = write!(args, ",\"deadline\":{deadline}");
+    }
+    format!(
+        r#"{{"name":{},"cat":{},{phase},"ts":{:.3},"pid":1,"tid":{},"args":{{{args}}}}}"#,
+        quote(&name),
+        quote(event.kind.as_str()),
+        event.at.as_nanos() as f64 / 1000.0,
+        tid(event.worker),
+    )
+}
+
+fn label(event: &TraceEvent) -> String {
+    match &event.name {
+        Some(name) => format!("task {} ({name})", event.task),
+        None => format!("task {}", event.task),
+    }
+}
+
+// JSONの文字列リテラルにする
+fn quote(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(all(test, not(loom)))]
+mod test;
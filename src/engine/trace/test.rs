@@ -0,0 +1,143 @@
+use std::sync::{Arc, mpsc};
+
+use super::{EventKind, Recorder, TraceEvent, quote};
+use crate::engine::schedule::deadline::DeadLineScheduler;
+use crate::engine::task::TaskId;
+use crate::engine::{Engine, block_on, yield_now};
+
+fn kinds_of(events: &[TraceEvent], task: TaskId) -> Vec<EventKind> {
+    events
+        .iter()
+        .filter(|event| event.task == task)
+        .map(|event| event.kind)
+        .collect()
+}
+
+#[test]
+fn records_task_lifecycle() {
+    let recorder = Recorder::new();
+    let engine = Engine::builder()
+        .with_worker_threads(1)
+        .with_recorder(recorder.clone())
+        .build()
+        .unwrap();
+
+    let handle = engine.task_builder().name("lifecycle").spawn(async {
+        yield_now().await;
+    });
+    let id = handle.id();
+    block_on(handle).unwrap();
+    engine.shutdown_now();
+
+    let events = recorder.events();
+    use EventKind::*;
+    // poll中にwakeされたタスクは、pollを抜ける前にキューへ戻される
+    assert_eq!(
+        kinds_of(&events, id),
+        vec![
+            Spawn, Schedule, PollStart, Wake, Schedule, PollEnd, PollStart, Complete, PollEnd
+        ]
+    );
+    let polls: Vec<_> = events
+        .iter()
+        .filter(|event| event.task == id && event.kind == PollStart)
+        .collect();
+    assert!(polls.iter().all(|event| event.worker.is_some()));
+    assert!(
+        polls
+            .iter()
+            .all(|event| event.name.as_deref() == Some("lifecycle"))
+    );
+    // 名前はイベントごとに写さず、タスクの持つものを共有する
+    let names: Vec<_> = events
+        .iter()
+        .filter(|event| event.task == id)
+        .map(|event| event.name.clone().unwrap())
+        .collect();
+    assert!(names.windows(2).all(|w| Arc::ptr_eq(&w[0], &w[1])));
+    // spawnはWorkerの外から呼んだ
+    assert_eq!(events[0].worker, None);
+    assert!(events.windows(2).all(|w| w[0].at <= w[1].at));
+}
+
+#[test]
+fn shows_deadline_order() {
+    let recorder = Recorder::new();
+    let engine = Engine::builder()
+        .with_worker_threads(1)
+        .with_scheduler(DeadLineScheduler::new)
+        .with_recorder(recorder.clone())
+        .build()
+        .unwrap();
+
+    // 唯一のWorkerを塞いでいる間に登録し、キューの中で並べ替えさせる
+    let (started_tx, started_rx) = mpsc::channel();
+    let (release_tx, release_rx) = mpsc::channel::<()>();
+    let gate = engine.spawn(async move {
+        started_tx.send(()).unwrap();
+        release_rx.recv().unwrap();
+    });
+    started_rx.recv().unwrap();
+    let handles: Vec<_> = [30, 10, 20]
+        .into_iter()
+        .map(|deadline| engine.task_builder().deadline(deadline).spawn(async {}))
+        .collect();
+    release_tx.send(()).unwrap();
+    block_on(gate).unwrap();
+    for handle in handles {
+        block_on(handle).unwrap();
+    }
+    engine.shutdown_now();
+
+    let order: Vec<_> = recorder
+        .events()
+        .into_iter()
+        .filter(|event| event.kind == EventKind::PollStart)
+        .filter_map(|event| event.deadline)
+        .collect();
+    assert_eq!(order, vec![10, 20, 30]);
+}
+
+#[test]
+fn writes_chrome_trace() {
+    let recorder = Recorder::new();
+    let engine = Engine::builder()
+        .with_worker_threads(1)
+        .with_recorder(recorder.clone())
+        .build()
+        .unwrap();
+    block_on(engine.task_builder().name("say \"hi\"").spawn(async {})).unwrap();
+    engine.shutdown_now();
+
+    let mut out = Vec::new();
+    recorder.write_chrome_trace(&mut out).unwrap();
+    let json = String::from_utf8(out).unwrap();
+    assert!(json.starts_with("{\"traceEvents\":["));
+    assert!(json.trim_end().ends_with("]}"));
+    assert!(json.contains(r#""ph":"M","pid":1,"tid":1,"args":{"name":"worker 0"}"#));
+    assert!(json.contains(r#""name":"external""#));
+    assert_eq!(json.matches(r#""ph":"B""#).count(), 1);
+    assert_eq!(json.matches(r#""ph":"E""#).count(), 1);
+    assert!(json.contains(r#""name":"say \"hi\"""#));
+    assert!(json.contains(r#""name":"spawn","cat":"spawn","ph":"i","s":"t""#));
+}
+
+#[test]
+fn quote_escapes_control_characters() {
+    assert_eq!(quote("a\"b\\c\n\u{1}"), r#""a\"b\\c\n\u0001""#);
+}
+
+#[test]
+fn clear_discards_events() {
+    let recorder = Recorder::new();
+    let engine = Engine::builder()
+        .with_worker_threads(1)
+        .with_recorder(recorder.clone())
+        .build()
+        .unwrap();
+    block_on(engine.spawn(async {})).unwrap();
+    engine.shutdown_now();
+    assert!(!recorder.events().is_empty());
+    recorder.clear();
+    assert!(recorder.events().is_empty());
+}
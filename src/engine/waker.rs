@@ -1,46 +1,77 @@
-use std::sync::{Arc, Mutex};
-use std::task::Wake;
-
-use crate::engine::schedule::Scheduler;
-use crate::engine::task::{self, SharedTask};
-
-pub struct Waker<T>
-where
-    T: Scheduler,
-{
-    scheduler: Arc<Mutex<T>>,
-    task: SharedTask,
-}
-
-impl<T> Waker<T>
-where
-    T: Scheduler,
-{
-    pub fn new(schedule: Arc<Mutex<T>>, task: SharedTask) -> Self {
-        Self {
-            scheduler: schedule,
-            task,
-        }
+use std::marker::PhantomData;
+use std::mem::ManuallyDrop;
+use std::ops::Deref;
+use std::task::{RawWaker, RawWakerVTable, Waker};
+
+use crate::engine::task::SharedTask;
+
+// タスクのヘッダをそのままWakerとして使う。dataはヘッダへのポインタで、参照カウントもヘッダが持つ
+// Futureの型によらず同じvtableなので、同じタスクのWakerはwill_wakeで等しくなる
+static VTABLE: RawWakerVTable = RawWakerVTable::new(clone_waker, wake, wake_by_ref, drop_waker);
+
+// 参照カウントを1つ持つWaker
+pub fn waker(task: SharedTask) -> Waker {
+    let raw = RawWaker::new(SharedTask::into_raw(task), &VTABLE);
+    unsafe { Waker::from_raw(raw) }
+}
+
+// タスクを借りている間だけ使えるWaker。pollのたびに参照カウントを触らない
+pub(crate) struct WakerRef<'a> {
+    waker: ManuallyDrop<Waker>,
+    _task: PhantomData<&'a SharedTask>,
+}
+
+pub(crate) fn waker_ref(task: &SharedTask) -> WakerRef<'_> {
+    let raw = RawWaker::new(SharedTask::as_ptr(task).cast(), &VTABLE);
+    WakerRef {
+        waker: ManuallyDrop::new(unsafe { Waker::from_raw(raw) }),
+        _task: PhantomData,
+    }
+}
+
+impl Deref for WakerRef<'_> {
+    type Target = Waker;
+
+    fn deref(&self) -> &Waker {
+        &self.waker
     }
 }
 
-impl<T> Wake for Waker<T>
-where
-    T: Scheduler,
-{
-    fn wake(self: Arc<Self>) {
-        eprintln!("called waker");
-        // PENDING状態のタスクのみ再スケジュール
-        // poll()が終了してPENDINGになった後に、外部イベントからwakeが呼ばれる想定
-        if self.task.get_state() == task::PENDING {
-            self.scheduler
-                .lock()
-                .unwrap()
-                .schedule(Arc::clone(&self.task));
-            eprintln!("task reshceduled!!");
-        }
+fn wake_task(task: &SharedTask) {
+    // キューに入っているか完了したタスクは何もしない
+    // poll中ならNOTIFIEDだけ立て、pollの後で再スケジュールされる
+    if let Some(dispatcher) = task.dispatcher() {
+        dispatcher.wake(task);
     }
 }
 
-#[cfg(test)]
+// 参照カウントを持たずにタスクを借りる
+unsafe fn borrow(ptr: *const ()) -> ManuallyDrop<SharedTask> {
+    ManuallyDrop::new(unsafe { SharedTask::from_raw(ptr) })
+}
+
+unsafe fn clone_waker(ptr: *const ()) -> RawWaker {
+    let task = unsafe { borrow(ptr) };
+    RawWaker::new(SharedTask::into_raw((*task).clone()), &VTABLE)
+}
+
+unsafe fn wake(ptr: *const ()) {
+    let task = unsafe { SharedTask::from_raw(ptr) };
+    wake_task(&task);
+}
+
+// キューへ入れるときだけ参照カウントを増やす
+unsafe fn wake_by_ref(ptr: *const ()) {
+    let task = unsafe { borrow(ptr) };
+    wake_task(&task);
+}
+
+unsafe fn drop_waker(ptr: *const ()) {
+    drop(unsafe { SharedTask::from_raw(ptr) });
+}
+
+#[cfg(all(test, not(loom)))]
 mod test;
+
+#[cfg(all(test, loom))]
+mod model;
@@ -0,0 +1,101 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use loom::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use loom::thread;
+
+use crate::engine::{
+    dispatch::Dispatcher,
+    schedule::fifo::Fifo,
+    task::{Attributes, SharedTask, Task},
+    waker::{waker, waker_ref},
+};
+use crate::time::driver::Driver;
+
+// フラグが立つまでPendingを返す
+struct Flag {
+    ready: Arc<AtomicBool>,
+    polls: Arc<AtomicUsize>,
+}
+
+impl Future for Flag {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<()> {
+        self.polls.fetch_add(1, Ordering::SeqCst);
+        if self.ready.load(Ordering::SeqCst) {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+fn scheduled(ready: &Arc<AtomicBool>, polls: &Arc<AtomicUsize>) -> (Arc<Dispatcher>, SharedTask) {
+    let dispatcher = Arc::new(Dispatcher::new(
+        Box::new(Fifo::new()),
+        Driver::global().clone(),
+    ));
+    let task = Task::from_future(
+        Flag {
+            ready: ready.clone(),
+            polls: polls.clone(),
+        },
+        Attributes::default(),
+    );
+    dispatcher.schedule(task.clone());
+    (dispatcher, task)
+}
+
+// Workerと同じようにキューが空になるまで取り出してpollする
+fn run_until_idle(dispatcher: &Dispatcher) {
+    while let Some(task) = dispatcher.next_task() {
+        let waker = waker_ref(&task);
+        let _ = task.poll(&mut Context::from_waker(&waker));
+    }
+}
+
+#[test]
+fn wake_from_other_thread_is_not_lost() {
+    loom::model(|| {
+        let ready = Arc::new(AtomicBool::new(false));
+        let polls = Arc::new(AtomicUsize::new(0));
+        let (dispatcher, task) = scheduled(&ready, &polls);
+
+        let waker = waker(task.clone());
+        let notifier = {
+            let ready = ready.clone();
+            thread::spawn(move || {
+                ready.store(true, Ordering::SeqCst);
+                waker.wake();
+            })
+        };
+        run_until_idle(&dispatcher);
+        notifier.join().unwrap();
+        run_until_idle(&dispatcher);
+
+        // pollの最中にwakeされても、pollの後でキューへ戻されて完了する
+        assert!(task.is_finished());
+        assert!(polls.load(Ordering::SeqCst) <= 2);
+    });
+}
+
+#[test]
+fn wake_after_complete_is_not_enqueued() {
+    loom::model(|| {
+        let ready = Arc::new(AtomicBool::new(true));
+        let polls = Arc::new(AtomicUsize::new(0));
+        let (dispatcher, task) = scheduled(&ready, &polls);
+
+        let waker = waker(task.clone());
+        let notifier = thread::spawn(move || waker.wake());
+        run_until_idle(&dispatcher);
+        notifier.join().unwrap();
+
+        assert!(task.is_finished());
+        assert!(dispatcher.next_task().is_none());
+        assert_eq!(polls.load(Ordering::SeqCst), 1);
+    });
+}
@@ -1,14 +1,20 @@
 use std::{
     future::Future,
-    sync::{Arc, Mutex},
-    task::{Poll, Wake},
+    future::poll_fn,
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    task::{Context, Poll},
 };
 
 use crate::engine::{
+    dispatch::Dispatcher,
     schedule::Scheduler,
-    task::{self, SharedTask, Task},
-    waker::Waker,
+    task::{Attributes, SharedTask, Task},
+    waker::{waker, waker_ref},
 };
+use crate::time::driver::Driver;
 
 struct DummyFuture {}
 
@@ -22,75 +28,163 @@ impl Future for DummyFuture {
     }
 }
 
+#[derive(Default)]
 struct DummyScheduler {
-    scheduled_count: usize,
+    pushed: Arc<AtomicUsize>,
+    woken: Arc<AtomicUsize>,
     tasks: Vec<SharedTask>,
-    worker_receiver: std::sync::mpsc::Receiver<crate::engine::worker::WorkerInfo>,
-    pending_workers: std::collections::VecDeque<crate::engine::worker::WorkerInfo>,
-}
-impl DummyScheduler {
-    fn new() -> Self {
-        let (_, worker_receiver) = std::sync::mpsc::channel();
-        Self {
-            scheduled_count: 0,
-            tasks: Vec::new(),
-            worker_receiver,
-            pending_workers: std::collections::VecDeque::new(),
-        }
-    }
 }
 
 impl Scheduler for DummyScheduler {
-    fn register(&mut self, task: SharedTask) {
-        self.scheduled_count += 1;
+    fn push(&mut self, task: SharedTask) {
+        self.pushed.fetch_add(1, Ordering::SeqCst);
         self.tasks.push(task);
     }
 
-    fn take(&mut self) -> Option<SharedTask> {
+    fn pop(&mut self) -> Option<SharedTask> {
         self.tasks.pop()
     }
 
-    fn get_pending_workers(
-        &mut self,
-    ) -> &mut std::collections::VecDeque<crate::engine::worker::WorkerInfo> {
-        &mut self.pending_workers
+    fn len(&self) -> usize {
+        self.tasks.len()
+    }
+
+    fn on_task_woken(&mut self, _task: &SharedTask) {
+        self.woken.fetch_add(1, Ordering::SeqCst);
     }
+}
+
+struct Counts {
+    pushed: Arc<AtomicUsize>,
+    woken: Arc<AtomicUsize>,
+}
 
-    fn get_worker_receiver(
-        &mut self,
-    ) -> &mut std::sync::mpsc::Receiver<crate::engine::worker::WorkerInfo> {
-        &mut self.worker_receiver
+impl Counts {
+    fn get(&self) -> (usize, usize) {
+        (
+            self.pushed.load(Ordering::SeqCst),
+            self.woken.load(Ordering::SeqCst),
+        )
     }
 }
 
-fn test_waker_schedule_count(task_state: u8, expected_count: usize) {
-    let scheduler = Arc::new(Mutex::new(DummyScheduler::new()));
+fn dispatcher() -> (Arc<Dispatcher>, Counts) {
+    let scheduler = DummyScheduler::default();
+    let counts = Counts {
+        pushed: scheduler.pushed.clone(),
+        woken: scheduler.woken.clone(),
+    };
+    (
+        Arc::new(Dispatcher::new(
+            Box::new(scheduler),
+            Driver::global().clone(),
+        )),
+        counts,
+    )
+}
+
+fn bound_task(dispatcher: &Arc<Dispatcher>) -> SharedTask {
     let (sender, _) = crate::utils::channel::channel();
     let task = Task::new(DummyFuture {}, sender, None);
-    task.set_state(task_state);
-    let waker = Arc::new(Waker::new(scheduler.clone(), task));
+    task.bind(dispatcher);
+    task
+}
 
-    waker.wake();
+#[test]
+fn schedule_idle() {
+    let (dispatcher, counts) = dispatcher();
+    let task = bound_task(&dispatcher);
 
-    assert_eq!(scheduler.lock().unwrap().scheduled_count, expected_count);
+    waker(task.clone()).wake();
+
+    // 起こされたタスクはinjectorを経由して、取り出すときにスケジューラへ入る
+    assert!(dispatcher.next_task().is_some());
+    assert_eq!(counts.get(), (1, 1));
 }
 
 #[test]
-fn not_schedule_already_scheduled() {
-    test_waker_schedule_count(task::SCHEDULED, 0);
+fn not_schedule_already_notified() {
+    let (dispatcher, counts) = dispatcher();
+    let task = bound_task(&dispatcher);
+    assert!(task.notify());
+
+    waker(task).wake();
+
+    assert!(dispatcher.next_task().is_none());
+    assert_eq!(counts.get(), (0, 0));
 }
 
 #[test]
-fn not_schedule_running() {
-    test_waker_schedule_count(task::RUNNING, 0);
+fn not_schedule_completed() {
+    let (dispatcher, counts) = dispatcher();
+    let task = bound_task(&dispatcher);
+    assert!(task.notify());
+    let waker = waker(task.clone());
+    assert!(task.poll(&mut Context::from_waker(&waker)).is_ready());
+
+    waker.wake();
+
+    assert!(dispatcher.next_task().is_none());
+    assert_eq!(counts.get(), (0, 0));
 }
 
 #[test]
-fn schedule_pending() {
-    test_waker_schedule_count(task::PENDING, 1);
+fn wake_during_poll_reschedules_after_poll() {
+    let (dispatcher, counts) = dispatcher();
+    let polled = Arc::new(AtomicUsize::new(0));
+    let cloned_polled = polled.clone();
+    let task = Task::from_future(
+        poll_fn(move |cx| {
+            // 1回目のpollの最中に自分を起こす
+            if cloned_polled.fetch_add(1, Ordering::SeqCst) == 0 {
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            } else {
+                Poll::Ready(())
+            }
+        }),
+        Attributes::default(),
+    );
+    dispatcher.schedule(task.clone());
+    let task = dispatcher.next_task().unwrap();
+
+    let waker = waker_ref(&task);
+    assert!(task.poll(&mut Context::from_waker(&waker)).is_pending());
+
+    // pollの後でキューへ戻っている
+    let task = dispatcher.next_task().expect("task should be rescheduled");
+    assert!(task.state().is_notified());
+    assert!(task.poll(&mut Context::from_waker(&waker)).is_ready());
+    assert_eq!(polled.load(Ordering::SeqCst), 2);
+    assert_eq!(counts.get(), (2, 1));
 }
 
 #[test]
-fn schedule_completed() {
-    test_waker_schedule_count(task::COMPLETED, 0);
+fn clones_of_same_task_will_wake() {
+    let (sender, _) = crate::utils::channel::channel();
+    let task = Task::new(DummyFuture {}, sender, None);
+    let (sender, _) = crate::utils::channel::channel();
+    let other = Task::new(DummyFuture {}, sender, None);
+
+    let waker = waker_ref(&task);
+    assert!(waker.will_wake(&waker.clone()));
+    assert!(waker.will_wake(&crate::engine::waker::waker(task.clone())));
+    assert!(!waker.will_wake(&waker_ref(&other)));
+}
+
+#[test]
+fn waker_holds_task_reference() {
+    let (sender, _) = crate::utils::channel::channel();
+    let task = Task::new(DummyFuture {}, sender, None);
+
+    // 借りたWakerは参照カウントを増やさない
+    let borrowed = waker_ref(&task);
+    assert_eq!(SharedTask::ref_count(&task), 1);
+
+    let cloned = borrowed.clone();
+    assert_eq!(SharedTask::ref_count(&task), 2);
+    cloned.wake_by_ref();
+    assert_eq!(SharedTask::ref_count(&task), 2);
+    cloned.wake();
+    assert_eq!(SharedTask::ref_count(&task), 1);
 }
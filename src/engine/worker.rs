@@ -1,72 +1,230 @@
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::mpsc::{Receiver, Sender, channel};
-use std::sync::{Arc, Mutex};
-use std::task::{self, Context};
+use std::cell::Cell;
+use std::fmt;
+use std::hint;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::task::Context;
 use std::thread;
+use std::time::{Duration, Instant};
 
-use crate::engine::schedule::Scheduler;
+use crate::engine::coop;
+use crate::engine::dispatch::SharedDispatcher;
 use crate::engine::task::SharedTask;
+use crate::engine::trace::EventKind;
 use crate::engine::waker;
 
+// parkする前にタスクを探して回る回数。後半はスレッドを譲りながら回る
+const SPIN_LIMIT: usize = 64;
+
+thread_local! {
+    // このスレッドで動いているWorker
+    static CURRENT: Cell<Option<WorkerId>> = const { Cell::new(None) };
+}
+
+// Workerスレッドの外ではNone
+pub(crate) fn current_id() -> Option<WorkerId> {
+    CURRENT.with(Cell::get)
+}
+
+// Engineの中でWorkerを区別する番号。Workerを増減させても使い回さない
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct WorkerId(usize);
+
+impl WorkerId {
+    pub(crate) fn new(id: usize) -> Self {
+        WorkerId(id)
+    }
+
+    pub(crate) fn index(self) -> usize {
+        self.0
+    }
+}
+
+impl fmt::Display for WorkerId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkerMetrics {
+    pub id: WorkerId,
+    // pollした回数とその累計時間
+    pub polls: u64,
+    pub busy_time: Duration,
+    // タスクがなくて眠った回数
+    pub parks: u64,
+    // 退役済みのWorker
+    pub retired: bool,
+}
+
+// Workerごとの統計。Workerが退役した後もEngineが持ち続ける
+pub(crate) struct WorkerStats {
+    id: WorkerId,
+    created: Instant,
+    polls: AtomicU64,
+    busy: AtomicU64,
+    parks: AtomicU64,
+    // 眠り始めた時刻（createdからのナノ秒 + 1）。0ならタスクを実行している
+    idle_since: AtomicU64,
+    retire: AtomicBool,
+    retired: AtomicBool,
+}
+
+impl WorkerStats {
+    pub(crate) fn new(id: WorkerId) -> Self {
+        Self {
+            id,
+            created: Instant::now(),
+            polls: AtomicU64::new(0),
+            busy: AtomicU64::new(0),
+            parks: AtomicU64::new(0),
+            idle_since: AtomicU64::new(0),
+            retire: AtomicBool::new(false),
+            retired: AtomicBool::new(false),
+        }
+    }
+
+    pub(crate) fn id(&self) -> WorkerId {
+        self.id
+    }
+
+    // 次にタスクを探す前に止まるよう頼む
+    pub(crate) fn request_retire(&self) {
+        self.retire.store(true, Ordering::Release);
+    }
+
+    pub(crate) fn mark_retired(&self) {
+        self.retired.store(true, Ordering::Release);
+    }
+
+    // 眠り続けている時間。タスクを実行中ならNone
+    pub(crate) fn idle_for(&self) -> Option<Duration> {
+        match self.idle_since.load(Ordering::Acquire) {
+            0 => None,
+            since => Some(
+                self.created
+                    .elapsed()
+                    .saturating_sub(Duration::from_nanos(since - 1)),
+            ),
+        }
+    }
+
+    pub(crate) fn metrics(&self) -> WorkerMetrics {
+        WorkerMetrics {
+            id: self.id,
+            polls: self.polls.load(Ordering::Relaxed),
+            busy_time: Duration::from_nanos(self.busy.load(Ordering::Relaxed)),
+            parks: self.parks.load(Ordering::Relaxed),
+            retired: self.retired.load(Ordering::Acquire),
+        }
+    }
+
+    fn mark_idle(&self) {
+        self.parks.fetch_add(1, Ordering::Relaxed);
+        let since = self.created.elapsed().as_nanos() as u64 + 1;
+        let _ = self
+            .idle_since
+            .compare_exchange(0, since, Ordering::AcqRel, Ordering::Acquire);
+    }
+
+    fn record_poll(&self, elapsed: Duration) {
+        self.idle_since.store(0, Ordering::Release);
+        self.polls.fetch_add(1, Ordering::Relaxed);
+        self.busy
+            .fetch_add(elapsed.as_nanos() as u64, Ordering::Relaxed);
+    }
+}
+
 pub struct Worker {
-    worker_sender: Sender<WorkerInfo>,
-    t_sender: Sender<SharedTask>,
-    t_receiver: Receiver<SharedTask>,
-    scheduler: Arc<Mutex<Box<dyn Scheduler + Send>>>,
+    dispatcher: SharedDispatcher,
     shutdown: Arc<AtomicBool>,
+    stats: Arc<WorkerStats>,
 }
 
 impl Worker {
-    pub fn new(
-        worker_sender: Sender<WorkerInfo>,
-        scheduler: Arc<Mutex<Box<dyn Scheduler + Send>>>,
+    pub(crate) fn new(
+        dispatcher: SharedDispatcher,
         shutdown: Arc<AtomicBool>,
+        stats: Arc<WorkerStats>,
     ) -> Self {
-        let (t_sender, t_receiver) = channel();
+        dispatcher.add_worker();
         Self {
-            worker_sender,
-            t_receiver,
-            t_sender,
-            scheduler,
+            dispatcher,
             shutdown,
+            stats,
         }
     }
 
+    pub fn id(&self) -> WorkerId {
+        self.stats.id
+    }
+
     pub fn execute(&self) {
-        loop {
-            if self.shutdown.load(Ordering::Acquire) {
-                break;
+        CURRENT.with(|current| current.set(Some(self.id())));
+        while !self.should_stop() {
+            match self.search() {
+                Some(task) => self.run(task),
+                // 探しても見つからなければ、schedule()で起こされるまで眠る
+                None => {
+                    self.stats.mark_idle();
+                    self.dispatcher.park();
+                }
             }
+        }
+        CURRENT.with(|current| current.set(None));
+    }
 
-            let _ = self.worker_sender.send(WorkerInfo {
-                t: thread::current(),
-                sender: self.t_sender.clone(),
-            });
-
-            self.scheduler.lock().unwrap().notify();
-
-            thread::park();
+    // Engineの停止か、このWorkerの退役
+    fn should_stop(&self) -> bool {
+        self.shutdown.load(Ordering::Acquire) || self.stats.retire.load(Ordering::Acquire)
+    }
 
-            if self.shutdown.load(Ordering::Acquire) {
-                break;
+    // タスクがある間はparkせずに取り出し続ける
+    fn search(&self) -> Option<SharedTask> {
+        if let Some(task) = self.dispatcher.next_task() {
+            return Some(task);
+        }
+        for i in 0..SPIN_LIMIT {
+            if self.should_stop() {
+                return None;
             }
-
-            if let Ok(task) = self.t_receiver.recv() {
-                let waker = waker::Waker::new(self.scheduler.clone(), Arc::clone(&task));
-                let waker = task::Waker::from(Arc::new(waker));
-                let mut context = Context::from_waker(&waker);
-                let _ = task.poll(&mut context);
-                // Poll::Pendingが返された場合、Wakerが呼ばれるまで待つ
-                // （Wakerが呼ばれると自動的に再スケジュールされる）
+            if self.dispatcher.has_injected()
+                && let Some(task) = self.dispatcher.next_task()
+            {
+                return Some(task);
+            }
+            if i < SPIN_LIMIT / 2 {
+                hint::spin_loop();
+            } else {
+                thread::yield_now();
             }
         }
+        None
+    }
+
+    fn run(&self, task: SharedTask) {
+        self.dispatcher.record_latency(task.queue_latency());
+        let waker = waker::waker_ref(&task);
+        let mut context = Context::from_waker(&waker);
+        self.dispatcher.record(EventKind::PollStart, &task);
+        let started = Instant::now();
+        let _ = coop::budget(|| task.poll(&mut context));
+        let elapsed = started.elapsed();
+        self.dispatcher.record(EventKind::PollEnd, &task);
+        task.record_poll(elapsed);
+        self.stats.record_poll(elapsed);
+        self.dispatcher.on_poll_complete(&task, elapsed);
+        // Poll::Pendingが返された場合、Wakerが呼ばれるまで待つ
+        // （Wakerが呼ばれると自動的に再スケジュールされる）
     }
 }
 
-pub struct WorkerInfo {
-    pub t: thread::Thread,
-    pub sender: Sender<SharedTask>,
+impl Drop for Worker {
+    fn drop(&mut self) {
+        self.dispatcher.remove_worker();
+    }
 }
 
-#[cfg(test)]
+#[cfg(all(test, not(loom)))]
 mod test;
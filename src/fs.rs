@@ -0,0 +1,413 @@
+use std::collections::VecDeque;
+use std::ffi::OsString;
+use std::fs as std_fs;
+use std::future::{Future, poll_fn};
+use std::io::{self, Read as _, Seek as _, SeekFrom, Write as _};
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use crate::engine::blocking::{Blocking, spawn_blocking};
+use crate::io::{AsyncRead, AsyncWrite};
+use crate::utils::stream::Stream;
+
+// Fileが一度にブロッキングスレッドへ渡す量
+// 小さな書き込みはここまで溜めてからまとめて書き、読み込みは先読みしておく
+const BUF_SIZE: usize = 64 * 1024;
+// read_dirが一度に読むエントリの数
+const DIR_BATCH: usize = 32;
+
+// ファイル操作はブロッキングスレッドで実行して、Workerを止めない
+async fn asyncify<T, F>(f: F) -> io::Result<T>
+where
+    F: FnOnce() -> io::Result<T> + Send + 'static,
+    T: Send + 'static,
+{
+    spawn_blocking(f).await
+}
+
+pub async fn read(path: impl AsRef<Path>) -> io::Result<Vec<u8>> {
+    let path = path.as_ref().to_owned();
+    asyncify(move || std_fs::read(path)).await
+}
+
+pub async fn read_to_string(path: impl AsRef<Path>) -> io::Result<String> {
+    let path = path.as_ref().to_owned();
+    asyncify(move || std_fs::read_to_string(path)).await
+}
+
+// ファイルを作り直してcontentsを書く
+pub async fn write(path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    let path = path.as_ref().to_owned();
+    let contents = contents.as_ref().to_vec();
+    asyncify(move || std_fs::write(path, contents)).await
+}
+
+pub async fn metadata(path: impl AsRef<Path>) -> io::Result<std_fs::Metadata> {
+    let path = path.as_ref().to_owned();
+    asyncify(move || std_fs::metadata(path)).await
+}
+
+pub async fn create_dir_all(path: impl AsRef<Path>) -> io::Result<()> {
+    let path = path.as_ref().to_owned();
+    asyncify(move || std_fs::create_dir_all(path)).await
+}
+
+pub async fn rename(from: impl AsRef<Path>, to: impl AsRef<Path>) -> io::Result<()> {
+    let from = from.as_ref().to_owned();
+    let to = to.as_ref().to_owned();
+    asyncify(move || std_fs::rename(from, to)).await
+}
+
+pub async fn remove_file(path: impl AsRef<Path>) -> io::Result<()> {
+    let path = path.as_ref().to_owned();
+    asyncify(move || std_fs::remove_file(path)).await
+}
+
+pub async fn read_dir(path: impl AsRef<Path>) -> io::Result<ReadDir> {
+    let path = path.as_ref().to_owned();
+    let inner = asyncify(move || std_fs::read_dir(path)).await?;
+    Ok(ReadDir {
+        entries: VecDeque::new(),
+        state: DirState::Idle(Some(inner)),
+    })
+}
+
+// ディレクトリのエントリを返すStream
+// 1回のスレッド移動でDIR_BATCH個ずつまとめて読む
+pub struct ReadDir {
+    entries: VecDeque<io::Result<DirEntry>>,
+    state: DirState,
+}
+
+type DirBatch = (VecDeque<io::Result<DirEntry>>, Option<std_fs::ReadDir>);
+
+enum DirState {
+    // Noneなら読み終わった
+    Idle(Option<std_fs::ReadDir>),
+    Busy(Blocking<DirBatch>),
+}
+
+impl ReadDir {
+    pub async fn next_entry(&mut self) -> io::Result<Option<DirEntry>> {
+        poll_fn(|cx| Pin::new(&mut *self).poll_next(cx))
+            .await
+            .transpose()
+    }
+}
+
+impl Stream for ReadDir {
+    type Item = io::Result<DirEntry>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            if let Some(entry) = this.entries.pop_front() {
+                return Poll::Ready(Some(entry));
+            }
+            match &mut this.state {
+                DirState::Idle(None) => return Poll::Ready(None),
+                DirState::Idle(inner) => {
+                    let mut inner = inner.take().unwrap();
+                    this.state = DirState::Busy(spawn_blocking(move || {
+                        let mut batch = VecDeque::with_capacity(DIR_BATCH);
+                        for _ in 0..DIR_BATCH {
+                            match inner.next() {
+                                Some(entry) => batch.push_back(entry.map(DirEntry::new)),
+                                None => return (batch, None),
+                            }
+                        }
+                        (batch, Some(inner))
+                    }));
+                }
+                DirState::Busy(blocking) => match Pin::new(blocking).poll(cx) {
+                    Poll::Ready((batch, inner)) => {
+                        this.entries = batch;
+                        this.state = DirState::Idle(inner);
+                    }
+                    Poll::Pending => return Poll::Pending,
+                },
+            }
+        }
+    }
+}
+
+pub struct DirEntry(Arc<std_fs::DirEntry>);
+
+impl DirEntry {
+    fn new(entry: std_fs::DirEntry) -> Self {
+        DirEntry(Arc::new(entry))
+    }
+
+    pub fn path(&self) -> PathBuf {
+        self.0.path()
+    }
+
+    pub fn file_name(&self) -> OsString {
+        self.0.file_name()
+    }
+
+    pub async fn metadata(&self) -> io::Result<std_fs::Metadata> {
+        let entry = self.0.clone();
+        asyncify(move || entry.metadata()).await
+    }
+
+    pub async fn file_type(&self) -> io::Result<std_fs::FileType> {
+        let entry = self.0.clone();
+        asyncify(move || entry.file_type()).await
+    }
+}
+
+// 読み書きをブロッキングスレッドで行うファイル
+// 書き込みはBUF_SIZEまで溜めてから書くので、閉じる前にflushして結果を確かめる
+// flushせずに破棄した場合も、溜まっていた分はバックグラウンドで書かれる
+pub struct File {
+    std: Arc<std_fs::File>,
+    state: State,
+}
+
+enum State {
+    Idle(Buffer),
+    Busy(Blocking<(Op, Vec<u8>)>),
+}
+
+enum Buffer {
+    Empty,
+    // 先読みしたデータ。OSのファイル位置はdataの終わりにある
+    Read { data: Vec<u8>, pos: usize },
+    // まだ書いていないデータ
+    Write(Vec<u8>),
+}
+
+enum Op {
+    Read(io::Result<usize>),
+    Write(io::Result<()>),
+    Seek(io::Result<u64>),
+}
+
+impl File {
+    pub async fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref().to_owned();
+        let std = asyncify(move || std_fs::File::open(path)).await?;
+        Ok(Self::from_std(std))
+    }
+
+    // なければ作り、あれば空にする
+    pub async fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref().to_owned();
+        let std = asyncify(move || std_fs::File::create(path)).await?;
+        Ok(Self::from_std(std))
+    }
+
+    pub fn from_std(std: std_fs::File) -> Self {
+        Self {
+            std: Arc::new(std),
+            state: State::Idle(Buffer::Empty),
+        }
+    }
+
+    // 位置を変える。溜まっている書き込みは先に書き、先読みした分は捨てる
+    pub async fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        poll_fn(|cx| self.poll_write_buffered(cx)).await?;
+        // OSの位置は先読みした分だけ進んでいるので、読んでいない分を戻す
+        let pos = match (&self.state, pos) {
+            (State::Idle(Buffer::Read { data, pos: read }), SeekFrom::Current(offset)) => {
+                SeekFrom::Current(offset - (data.len() - read) as i64)
+            }
+            (_, pos) => pos,
+        };
+        self.start_seek(pos);
+        loop {
+            match poll_fn(|cx| self.poll_busy(cx)).await {
+                Op::Seek(res) => return res,
+                Op::Write(Err(e)) => return Err(e),
+                _ => {}
+            }
+        }
+    }
+
+    pub async fn metadata(&self) -> io::Result<std_fs::Metadata> {
+        let std = self.std.clone();
+        asyncify(move || std.metadata()).await
+    }
+
+    // 溜まっている書き込みを書いてから、ディスクへの書き込みを待つ
+    pub async fn sync_all(&mut self) -> io::Result<()> {
+        poll_fn(|cx| self.poll_write_buffered(cx)).await?;
+        let std = self.std.clone();
+        asyncify(move || std.sync_all()).await
+    }
+
+    fn start_read(&mut self, len: usize) {
+        let std = self.std.clone();
+        self.state = State::Busy(spawn_blocking(move || {
+            let mut data = vec![0; len];
+            let res = (&*std).read(&mut data);
+            data.truncate(*res.as_ref().unwrap_or(&0));
+            (Op::Read(res), data)
+        }));
+    }
+
+    fn start_write(&mut self, mut data: Vec<u8>) {
+        let std = self.std.clone();
+        self.state = State::Busy(spawn_blocking(move || {
+            let res = (&*std).write_all(&data);
+            data.clear();
+            (Op::Write(res), data)
+        }));
+    }
+
+    fn start_seek(&mut self, pos: SeekFrom) {
+        let std = self.std.clone();
+        self.state = State::Busy(spawn_blocking(move || {
+            (Op::Seek((&*std).seek(pos)), Vec::new())
+        }));
+    }
+
+    // 実行中の操作が終わるのを待ち、結果に合わせてバッファを戻す
+    fn poll_busy(&mut self, cx: &mut Context<'_>) -> Poll<Op> {
+        let State::Busy(blocking) = &mut self.state else {
+            unreachable!("poll_busy called while idle");
+        };
+        let (op, data) = match Pin::new(blocking).poll(cx) {
+            Poll::Ready(done) => done,
+            Poll::Pending => return Poll::Pending,
+        };
+        let buffer = match &op {
+            Op::Read(Ok(n)) if *n > 0 => Buffer::Read { data, pos: 0 },
+            _ => Buffer::Empty,
+        };
+        self.state = State::Idle(buffer);
+        Poll::Ready(op)
+    }
+
+    // 溜まっている書き込みを全て書き、実行中の操作も終わらせる
+    fn poll_write_buffered(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        loop {
+            match &mut self.state {
+                State::Idle(Buffer::Write(data)) if !data.is_empty() => {
+                    let data = mem::take(data);
+                    self.start_write(data);
+                }
+                State::Idle(_) => return Poll::Ready(Ok(())),
+                State::Busy(_) => match self.poll_busy(cx) {
+                    Poll::Ready(Op::Write(Err(e)) | Op::Seek(Err(e))) => {
+                        return Poll::Ready(Err(e));
+                    }
+                    Poll::Ready(_) => {}
+                    Poll::Pending => return Poll::Pending,
+                },
+            }
+        }
+    }
+}
+
+impl AsyncRead for File {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        if buf.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+        loop {
+            match &mut this.state {
+                // 先読みした分があれば、スレッドを移らずに返す
+                State::Idle(Buffer::Read { data, pos }) if *pos < data.len() => {
+                    let n = buf.len().min(data.len() - *pos);
+                    buf[..n].copy_from_slice(&data[*pos..*pos + n]);
+                    *pos += n;
+                    return Poll::Ready(Ok(n));
+                }
+                State::Idle(Buffer::Write(data)) if !data.is_empty() => {
+                    let data = mem::take(data);
+                    this.start_write(data);
+                }
+                State::Idle(_) => this.start_read(buf.len().max(BUF_SIZE)),
+                State::Busy(_) => match this.poll_busy(cx) {
+                    Poll::Ready(Op::Read(Ok(0))) => return Poll::Ready(Ok(0)),
+                    Poll::Ready(Op::Read(Err(e)) | Op::Write(Err(e)) | Op::Seek(Err(e))) => {
+                        return Poll::Ready(Err(e));
+                    }
+                    Poll::Ready(_) => {}
+                    Poll::Pending => return Poll::Pending,
+                },
+            }
+        }
+    }
+}
+
+impl AsyncWrite for File {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        if buf.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+        loop {
+            match &mut this.state {
+                // 先読みした分だけOSの位置が先にあるので、戻してから書く
+                State::Idle(Buffer::Read { data, pos }) => {
+                    let unread = data.len() - *pos;
+                    if unread == 0 {
+                        this.state = State::Idle(Buffer::Empty);
+                    } else {
+                        this.start_seek(SeekFrom::Current(-(unread as i64)));
+                    }
+                }
+                // BUF_SIZEまではスレッドを移らずに溜める
+                State::Idle(Buffer::Write(data)) if data.len() < BUF_SIZE => {
+                    let n = buf.len().min(BUF_SIZE - data.len());
+                    data.extend_from_slice(&buf[..n]);
+                    return Poll::Ready(Ok(n));
+                }
+                State::Idle(Buffer::Write(data)) => {
+                    let data = mem::take(data);
+                    this.start_write(data);
+                }
+                State::Idle(Buffer::Empty) => {
+                    this.state = State::Idle(Buffer::Write(Vec::with_capacity(BUF_SIZE)));
+                }
+                State::Busy(_) => match this.poll_busy(cx) {
+                    Poll::Ready(Op::Read(Err(e)) | Op::Write(Err(e)) | Op::Seek(Err(e))) => {
+                        return Poll::Ready(Err(e));
+                    }
+                    Poll::Ready(_) => {}
+                    Poll::Pending => return Poll::Pending,
+                },
+            }
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.get_mut().poll_write_buffered(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.get_mut().poll_write_buffered(cx)
+    }
+}
+
+impl Drop for File {
+    fn drop(&mut self) {
+        if let State::Idle(Buffer::Write(data)) = &mut self.state
+            && !data.is_empty()
+        {
+            let data = mem::take(data);
+            let std = self.std.clone();
+            drop(spawn_blocking(move || {
+                let _ = (&*std).write_all(&data);
+            }));
+        }
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod test;
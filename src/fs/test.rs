@@ -0,0 +1,177 @@
+use std::collections::BTreeSet;
+use std::io::SeekFrom;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use super::{
+    File, create_dir_all, metadata, read, read_dir, read_to_string, remove_file, rename, write,
+};
+use crate::engine::schedule::fifo::Fifo;
+use crate::engine::{Engine, block_on};
+use crate::io::{AsyncReadExt, AsyncWriteExt};
+use crate::utils::stream::StreamExt;
+
+// テストごとに別のディレクトリを使い、終わったら消す
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new() -> Self {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let path = std::env::temp_dir().join(format!(
+            "async_runtime_fs_{}_{}",
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        ));
+        std::fs::create_dir_all(&path).unwrap();
+        TempDir(path)
+    }
+
+    fn join(&self, name: &str) -> PathBuf {
+        self.0.join(name)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+#[test]
+fn write_then_read() {
+    let dir = TempDir::new();
+    let path = dir.join("config.toml");
+    block_on(async {
+        write(&path, "key = 1\n").await.unwrap();
+        assert_eq!(read(&path).await.unwrap(), b"key = 1\n");
+        assert_eq!(read_to_string(&path).await.unwrap(), "key = 1\n");
+        assert_eq!(metadata(&path).await.unwrap().len(), 8);
+    });
+}
+
+#[test]
+fn rename_and_remove() {
+    let dir = TempDir::new();
+    let from = dir.join("a");
+    let to = dir.join("nested/deeper");
+    block_on(async {
+        write(&from, "x").await.unwrap();
+        create_dir_all(&to).await.unwrap();
+        let moved = to.join("b");
+        rename(&from, &moved).await.unwrap();
+        assert!(metadata(&from).await.is_err());
+        remove_file(&moved).await.unwrap();
+        let err = read(&moved).await.unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+    });
+}
+
+#[test]
+fn file_small_writes_are_batched() {
+    let dir = TempDir::new();
+    let path = dir.join("log");
+    block_on(async {
+        let mut file = File::create(&path).await.unwrap();
+        for i in 0..1000 {
+            file.write_all(format!("line {i}\n").as_bytes())
+                .await
+                .unwrap();
+        }
+        // flushするまでは溜まっている
+        assert!(std::fs::metadata(&path).unwrap().len() < 1000 * 6);
+        file.flush().await.unwrap();
+        let expected: String = (0..1000).map(|i| format!("line {i}\n")).collect();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), expected);
+    });
+}
+
+#[test]
+fn file_read_seek_and_overwrite() {
+    let dir = TempDir::new();
+    let path = dir.join("data");
+    std::fs::write(&path, b"0123456789").unwrap();
+    block_on(async {
+        let mut file = File::from_std(
+            std::fs::OpenOptions::new()
+                .read(true)
+                .write(true)
+                .open(&path)
+                .unwrap(),
+        );
+        let mut buf = [0u8; 3];
+        file.read(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"012");
+
+        // 先読みしていても、論理的な位置から相対的に動く
+        assert_eq!(file.seek(SeekFrom::Current(2)).await.unwrap(), 5);
+        file.read(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"567");
+
+        // 読んだ直後の位置に書く
+        file.write_all(b"ab").await.unwrap();
+        file.flush().await.unwrap();
+        assert_eq!(file.seek(SeekFrom::Start(0)).await.unwrap(), 0);
+        let mut all = Vec::new();
+        file.read_to_end(&mut all).await.unwrap();
+        assert_eq!(all, b"01234567ab");
+        assert_eq!(file.metadata().await.unwrap().len(), 10);
+    });
+}
+
+#[test]
+fn large_file_round_trip() {
+    let dir = TempDir::new();
+    let path = dir.join("large");
+    let data: Vec<u8> = (0..300_000).map(|i| (i % 251) as u8).collect();
+    block_on(async {
+        let mut file = File::create(&path).await.unwrap();
+        file.write_all(&data).await.unwrap();
+        file.sync_all().await.unwrap();
+
+        let mut file = File::open(&path).await.unwrap();
+        let mut read_back = Vec::new();
+        file.read_to_end(&mut read_back).await.unwrap();
+        assert_eq!(read_back, data);
+    });
+}
+
+#[test]
+fn dropping_file_writes_buffered_data() {
+    let dir = TempDir::new();
+    let path = dir.join("dropped");
+    block_on(async {
+        let mut file = File::create(&path).await.unwrap();
+        file.write_all(b"kept").await.unwrap();
+    });
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+    while std::fs::read(&path).unwrap() != b"kept" {
+        assert!(std::time::Instant::now() < deadline);
+        std::thread::sleep(std::time::Duration::from_millis(5));
+    }
+}
+
+#[test]
+fn read_dir_lists_entries_in_batches() {
+    let dir = TempDir::new();
+    for i in 0..100 {
+        std::fs::write(dir.join(&format!("f{i}")), "").unwrap();
+    }
+    let engine = Engine::new(1, Fifo::new());
+    let root = dir.0.clone();
+    let names = block_on(engine.spawn(async move {
+        let mut entries = read_dir(&root).await.unwrap();
+        let mut names = BTreeSet::new();
+        while let Some(entry) = entries.next().await {
+            let entry = entry.unwrap();
+            assert!(entry.file_type().await.unwrap().is_file());
+            names.insert(entry.file_name().into_string().unwrap());
+        }
+        // 読み終わったら何度呼んでもNone
+        assert!(entries.next_entry().await.unwrap().is_none());
+        names
+    }))
+    .unwrap();
+    assert_eq!(names.len(), 100);
+    assert!(names.contains("f42"));
+    engine.shutdown_now();
+}
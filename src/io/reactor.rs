@@ -0,0 +1,268 @@
+use std::collections::HashMap;
+use std::io;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+
+use crate::engine::coop;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Interest {
+    Readable,
+    Writable,
+}
+
+impl Interest {
+    fn mask(self) -> u64 {
+        match self {
+            Interest::Readable => READABLE,
+            Interest::Writable => WRITABLE,
+        }
+    }
+}
+
+const READABLE: u64 = 0b01;
+const WRITABLE: u64 = 0b10;
+const READINESS_MASK: u64 = 0b11;
+// 下位ビットがreadiness、上位ビットがイベントを受け取るたびに増えるtick
+const TICK_SHIFT: u32 = 8;
+
+// epollのイベントを専用スレッドで待ち、登録されたWakerを起こす
+pub(crate) struct Reactor {
+    epoll: OwnedFd,
+    resources: Mutex<HashMap<u64, Arc<ScheduledIo>>>,
+    next_token: AtomicU64,
+}
+
+static REACTOR: OnceLock<&'static Reactor> = OnceLock::new();
+
+impl Reactor {
+    pub(crate) fn global() -> &'static Reactor {
+        REACTOR.get_or_init(|| {
+            let fd = unsafe { libc::epoll_create1(libc::EPOLL_CLOEXEC) };
+            if fd < 0 {
+                panic!("epoll_create1 failed: {}", io::Error::last_os_error());
+            }
+            let reactor: &'static Reactor = Box::leak(Box::new(Reactor {
+                epoll: unsafe { OwnedFd::from_raw_fd(fd) },
+                resources: Mutex::new(HashMap::new()),
+                next_token: AtomicU64::new(0),
+            }));
+            thread::Builder::new()
+                .name("async-runtime-reactor".to_string())
+                .spawn(move || reactor.run())
+                .expect("failed to spawn reactor thread");
+            reactor
+        })
+    }
+
+    fn run(&self) {
+        let mut events: Vec<libc::epoll_event> = vec![libc::epoll_event { events: 0, u64: 0 }; 256];
+        loop {
+            let n = unsafe {
+                libc::epoll_wait(
+                    self.epoll.as_raw_fd(),
+                    events.as_mut_ptr(),
+                    events.len() as i32,
+                    -1,
+                )
+            };
+            if n < 0 {
+                let err = io::Error::last_os_error();
+                if err.kind() == io::ErrorKind::Interrupted {
+                    continue;
+                }
+                panic!("epoll_wait failed: {}", err);
+            }
+
+            for event in &events[..n as usize] {
+                let token = event.u64;
+                let flags = event.events as i32;
+                let Some(io) = self.resources.lock().unwrap().get(&token).cloned() else {
+                    continue;
+                };
+
+                let mut ready = 0;
+                if flags & (libc::EPOLLIN | libc::EPOLLRDHUP | libc::EPOLLHUP | libc::EPOLLERR) != 0
+                {
+                    ready |= READABLE;
+                }
+                if flags & (libc::EPOLLOUT | libc::EPOLLHUP | libc::EPOLLERR) != 0 {
+                    ready |= WRITABLE;
+                }
+                io.set_readiness(ready);
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+struct Wakers {
+    reader: Option<Waker>,
+    writer: Option<Waker>,
+}
+
+struct ScheduledIo {
+    readiness: AtomicU64,
+    wakers: Mutex<Wakers>,
+}
+
+impl ScheduledIo {
+    fn set_readiness(&self, ready: u64) {
+        let _ = self
+            .readiness
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |current| {
+                let tick = (current >> TICK_SHIFT).wrapping_add(1);
+                Some((tick << TICK_SHIFT) | (current & READINESS_MASK) | ready)
+            });
+
+        let (reader, writer) = {
+            let mut wakers = self.wakers.lock().unwrap();
+            let reader = if ready & READABLE != 0 {
+                wakers.reader.take()
+            } else {
+                None
+            };
+            let writer = if ready & WRITABLE != 0 {
+                wakers.writer.take()
+            } else {
+                None
+            };
+            (reader, writer)
+        };
+        reader.into_iter().chain(writer).for_each(Waker::wake);
+    }
+}
+
+// poll_readyが返した時点のtick。clear_readinessでこれより新しいイベントを消さないために使う
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ReadyEvent {
+    tick: u64,
+    interest: Interest,
+}
+
+// fdをReactorに登録し、読み書きの準備ができるまで待てるようにする
+pub(crate) struct Registration {
+    token: u64,
+    fd: RawFd,
+    io: Arc<ScheduledIo>,
+}
+
+impl Registration {
+    pub(crate) fn new(fd: RawFd) -> io::Result<Self> {
+        let reactor = Reactor::global();
+        let token = reactor.next_token.fetch_add(1, Ordering::Relaxed);
+        let io = Arc::new(ScheduledIo {
+            readiness: AtomicU64::new(0),
+            wakers: Mutex::new(Wakers::default()),
+        });
+        reactor.resources.lock().unwrap().insert(token, io.clone());
+
+        let mut event = libc::epoll_event {
+            events: (libc::EPOLLIN | libc::EPOLLOUT | libc::EPOLLRDHUP | libc::EPOLLET) as u32,
+            u64: token,
+        };
+        let res = unsafe {
+            libc::epoll_ctl(
+                reactor.epoll.as_raw_fd(),
+                libc::EPOLL_CTL_ADD,
+                fd,
+                &mut event,
+            )
+        };
+        if res < 0 {
+            reactor.resources.lock().unwrap().remove(&token);
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Self { token, fd, io })
+    }
+
+    pub(crate) fn poll_ready(&self, cx: &mut Context<'_>, interest: Interest) -> Poll<ReadyEvent> {
+        let current = self.io.readiness.load(Ordering::Acquire);
+        if current & interest.mask() != 0 {
+            return Poll::Ready(ReadyEvent {
+                tick: current >> TICK_SHIFT,
+                interest,
+            });
+        }
+
+        let mut wakers = self.io.wakers.lock().unwrap();
+        let slot = match interest {
+            Interest::Readable => &mut wakers.reader,
+            Interest::Writable => &mut wakers.writer,
+        };
+        match slot {
+            Some(waker) => waker.clone_from(cx.waker()),
+            None => *slot = Some(cx.waker().clone()),
+        }
+
+        // Wakerを登録している間にイベントが来ていないか確認する
+        let current = self.io.readiness.load(Ordering::Acquire);
+        if current & interest.mask() != 0 {
+            return Poll::Ready(ReadyEvent {
+                tick: current >> TICK_SHIFT,
+                interest,
+            });
+        }
+        Poll::Pending
+    }
+
+    // WouldBlockになった時だけ呼ぶ。その後に届いたイベントは消さない
+    pub(crate) fn clear_readiness(&self, event: ReadyEvent) {
+        let mut current = self.io.readiness.load(Ordering::Acquire);
+        loop {
+            if current >> TICK_SHIFT != event.tick {
+                return;
+            }
+            let new = current & !event.interest.mask();
+            match self.io.readiness.compare_exchange(
+                current,
+                new,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => return,
+                Err(actual) => current = actual,
+            }
+        }
+    }
+
+    // 準備ができるまで待ってからopを試し、WouldBlockなら再び待つ
+    pub(crate) fn poll_io<R>(
+        &self,
+        cx: &mut Context<'_>,
+        interest: Interest,
+        mut op: impl FnMut() -> io::Result<R>,
+    ) -> Poll<io::Result<R>> {
+        if coop::poll_proceed(cx).is_pending() {
+            return Poll::Pending;
+        }
+        loop {
+            let event = match self.poll_ready(cx, interest) {
+                Poll::Ready(event) => event,
+                Poll::Pending => return Poll::Pending,
+            };
+            match op() {
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => self.clear_readiness(event),
+                res => return Poll::Ready(res),
+            }
+        }
+    }
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        let reactor = Reactor::global();
+        unsafe {
+            libc::epoll_ctl(
+                reactor.epoll.as_raw_fd(),
+                libc::EPOLL_CTL_DEL,
+                self.fd,
+                std::ptr::null_mut(),
+            );
+        }
+        reactor.resources.lock().unwrap().remove(&self.token);
+    }
+}
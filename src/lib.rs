@@ -1,4 +1,15 @@
 pub mod engine;
+pub mod fs;
+pub mod io;
+mod loom;
+#[cfg(target_os = "linux")]
+pub mod net;
+#[cfg(target_os = "linux")]
+pub mod process;
+#[cfg(target_os = "linux")]
+pub mod signal;
+pub mod time;
 pub mod utils;
 
+pub use async_runtime_macros::{main, test};
 pub use engine::Engine;
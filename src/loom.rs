@@ -0,0 +1,52 @@
+// 同期プリミティブの差し替え口
+// 通常はstdをそのまま使い、cfg(loom)ではloomのモデルに置き換えて全てのスレッドの実行順を検査する
+// タスクの参照カウントもここを通すので、参照の増減とタスクの解放の順番も検査される
+
+#[cfg(not(loom))]
+pub(crate) mod sync {
+    pub(crate) use std::sync::{Condvar, Mutex, mpsc};
+
+    pub(crate) mod atomic {
+        pub(crate) use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering, fence};
+    }
+}
+
+#[cfg(loom)]
+pub(crate) mod sync {
+    pub(crate) use ::loom::sync::{Condvar, Mutex, mpsc};
+
+    pub(crate) mod atomic {
+        pub(crate) use ::loom::sync::atomic::{AtomicBool, AtomicUsize, Ordering, fence};
+    }
+}
+
+#[cfg(not(loom))]
+pub(crate) mod thread {
+    pub(crate) use std::thread::{Thread, current, park};
+}
+
+#[cfg(loom)]
+pub(crate) mod thread {
+    pub(crate) use ::loom::thread::{Thread, current, park};
+}
+
+#[cfg(not(loom))]
+pub(crate) mod cell {
+    // loomのUnsafeCellと同じく、クロージャの中でだけポインタに触れる
+    pub(crate) struct UnsafeCell<T>(std::cell::UnsafeCell<T>);
+
+    impl<T> UnsafeCell<T> {
+        pub(crate) const fn new(data: T) -> Self {
+            Self(std::cell::UnsafeCell::new(data))
+        }
+
+        pub(crate) fn with_mut<R>(&self, f: impl FnOnce(*mut T) -> R) -> R {
+            f(self.0.get())
+        }
+    }
+}
+
+#[cfg(loom)]
+pub(crate) mod cell {
+    pub(crate) use ::loom::cell::UnsafeCell;
+}
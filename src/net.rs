@@ -0,0 +1,250 @@
+use std::future::{Future, poll_fn};
+use std::io::{self, Read as _, Write as _};
+use std::mem;
+use std::net::{self, Shutdown, SocketAddr, ToSocketAddrs};
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use crate::io::reactor::{Interest, Registration};
+use crate::io::{AsyncRead, AsyncWrite};
+use crate::utils::stream::Stream;
+
+pub struct TcpListener {
+    // fdを閉じる前にReactorから外すため、registrationを先に置く
+    registration: Registration,
+    inner: net::TcpListener,
+}
+
+impl TcpListener {
+    // bind自体はブロックしないので、stdのものをそのまま使う
+    pub fn bind<A: ToSocketAddrs>(addr: A) -> io::Result<Self> {
+        Self::from_std(net::TcpListener::bind(addr)?)
+    }
+
+    pub fn from_std(listener: net::TcpListener) -> io::Result<Self> {
+        listener.set_nonblocking(true)?;
+        Ok(Self {
+            registration: Registration::new(listener.as_raw_fd())?,
+            inner: listener,
+        })
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.inner.local_addr()
+    }
+
+    pub fn poll_accept(&self, cx: &mut Context<'_>) -> Poll<io::Result<(TcpStream, SocketAddr)>> {
+        let (stream, addr) = match self
+            .registration
+            .poll_io(cx, Interest::Readable, || self.inner.accept())
+        {
+            Poll::Ready(Ok(accepted)) => accepted,
+            Poll::Ready(Err(e)) => return Poll::Ready(Err(e)),
+            Poll::Pending => return Poll::Pending,
+        };
+        Poll::Ready(TcpStream::from_std(stream).map(|stream| (stream, addr)))
+    }
+
+    pub fn accept(&self) -> Accept<'_> {
+        Accept { listener: self }
+    }
+
+    // 接続を受け付け続けるStream。終端はない
+    pub fn incoming(&self) -> Incoming<'_> {
+        Incoming { listener: self }
+    }
+}
+
+impl AsRawFd for TcpListener {
+    fn as_raw_fd(&self) -> RawFd {
+        self.inner.as_raw_fd()
+    }
+}
+
+pub struct Accept<'a> {
+    listener: &'a TcpListener,
+}
+
+impl Future for Accept<'_> {
+    type Output = io::Result<(TcpStream, SocketAddr)>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.listener.poll_accept(cx)
+    }
+}
+
+pub struct Incoming<'a> {
+    listener: &'a TcpListener,
+}
+
+impl Stream for Incoming<'_> {
+    type Item = io::Result<TcpStream>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.listener
+            .poll_accept(cx)
+            .map(|res| Some(res.map(|(stream, _)| stream)))
+    }
+}
+
+pub struct TcpStream {
+    registration: Registration,
+    inner: net::TcpStream,
+}
+
+impl TcpStream {
+    pub async fn connect(addr: SocketAddr) -> io::Result<Self> {
+        let stream = Self::from_std(start_connect(&addr)?)?;
+
+        // ノンブロッキングのconnectは、書き込み可能になった時点で結果が確定する
+        poll_fn(|cx| {
+            loop {
+                let event = match stream.registration.poll_ready(cx, Interest::Writable) {
+                    Poll::Ready(event) => event,
+                    Poll::Pending => return Poll::Pending,
+                };
+                if let Some(e) = stream.inner.take_error()? {
+                    return Poll::Ready(Err(e));
+                }
+                match stream.inner.peer_addr() {
+                    Ok(_) => return Poll::Ready(Ok(())),
+                    Err(e) if e.kind() == io::ErrorKind::NotConnected => {
+                        stream.registration.clear_readiness(event)
+                    }
+                    Err(e) => return Poll::Ready(Err(e)),
+                }
+            }
+        })
+        .await?;
+
+        Ok(stream)
+    }
+
+    pub fn from_std(stream: net::TcpStream) -> io::Result<Self> {
+        stream.set_nonblocking(true)?;
+        Ok(Self {
+            registration: Registration::new(stream.as_raw_fd())?,
+            inner: stream,
+        })
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.inner.local_addr()
+    }
+
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        self.inner.peer_addr()
+    }
+
+    pub fn set_nodelay(&self, nodelay: bool) -> io::Result<()> {
+        self.inner.set_nodelay(nodelay)
+    }
+}
+
+impl AsRawFd for TcpStream {
+    fn as_raw_fd(&self) -> RawFd {
+        self.inner.as_raw_fd()
+    }
+}
+
+impl AsyncRead for TcpStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        this.registration
+            .poll_io(cx, Interest::Readable, || (&this.inner).read(buf))
+    }
+}
+
+impl AsyncWrite for TcpStream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        this.registration
+            .poll_io(cx, Interest::Writable, || (&this.inner).write(buf))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        // ソケットはバッファを持たないので何もしない
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(self.inner.shutdown(Shutdown::Write))
+    }
+}
+
+fn start_connect(addr: &SocketAddr) -> io::Result<net::TcpStream> {
+    let family = match addr {
+        SocketAddr::V4(_) => libc::AF_INET,
+        SocketAddr::V6(_) => libc::AF_INET6,
+    };
+    let fd = unsafe {
+        libc::socket(
+            family,
+            libc::SOCK_STREAM | libc::SOCK_NONBLOCK | libc::SOCK_CLOEXEC,
+            0,
+        )
+    };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+
+    let (storage, len) = sockaddr(addr);
+    let res = unsafe {
+        libc::connect(
+            fd.as_raw_fd(),
+            &storage as *const libc::sockaddr_storage as *const libc::sockaddr,
+            len,
+        )
+    };
+    if res < 0 {
+        let err = io::Error::last_os_error();
+        if err.raw_os_error() != Some(libc::EINPROGRESS) {
+            return Err(err);
+        }
+    }
+    Ok(net::TcpStream::from(fd))
+}
+
+fn sockaddr(addr: &SocketAddr) -> (libc::sockaddr_storage, libc::socklen_t) {
+    let mut storage: libc::sockaddr_storage = unsafe { mem::zeroed() };
+    let len = match addr {
+        SocketAddr::V4(addr) => {
+            let sin = &mut storage as *mut libc::sockaddr_storage as *mut libc::sockaddr_in;
+            unsafe {
+                (*sin).sin_family = libc::AF_INET as libc::sa_family_t;
+                (*sin).sin_port = addr.port().to_be();
+                (*sin).sin_addr = libc::in_addr {
+                    s_addr: u32::from_ne_bytes(addr.ip().octets()),
+                };
+            }
+            mem::size_of::<libc::sockaddr_in>()
+        }
+        SocketAddr::V6(addr) => {
+            let sin6 = &mut storage as *mut libc::sockaddr_storage as *mut libc::sockaddr_in6;
+            unsafe {
+                (*sin6).sin6_family = libc::AF_INET6 as libc::sa_family_t;
+                (*sin6).sin6_port = addr.port().to_be();
+                (*sin6).sin6_flowinfo = addr.flowinfo();
+                (*sin6).sin6_addr = libc::in6_addr {
+                    s6_addr: addr.ip().octets(),
+                };
+                (*sin6).sin6_scope_id = addr.scope_id();
+            }
+            mem::size_of::<libc::sockaddr_in6>()
+        }
+    };
+    (storage, len as libc::socklen_t)
+}
+
+#[cfg(all(test, not(loom)))]
+mod test;
@@ -0,0 +1,53 @@
+use super::{TcpListener, TcpStream};
+use crate::engine::block_on;
+use crate::io::{AsyncReadExt, AsyncWriteExt};
+use crate::utils::stream::StreamExt;
+
+use std::io::{Read, Write};
+use std::thread;
+
+#[test]
+fn accept_connection_from_std_client() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let client = thread::spawn(move || {
+        let mut stream = std::net::TcpStream::connect(addr).unwrap();
+        stream.write_all(b"ping").unwrap();
+        stream.shutdown(std::net::Shutdown::Write).unwrap();
+        let mut buf = String::new();
+        stream.read_to_string(&mut buf).unwrap();
+        buf
+    });
+
+    block_on(async {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut buf = Vec::new();
+        stream.read_to_end(&mut buf).await.unwrap();
+        assert_eq!(buf, b"ping");
+        stream.write_all(b"pong").await.unwrap();
+    });
+
+    assert_eq!(client.join().unwrap(), "pong");
+}
+
+#[test]
+fn incoming_yields_each_connection() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    block_on(async {
+        for i in 0..3u8 {
+            let mut stream = TcpStream::connect(addr).await.unwrap();
+            stream.write_all(&[i]).await.unwrap();
+        }
+
+        let mut incoming = listener.incoming();
+        for i in 0..3u8 {
+            let mut stream = incoming.next().await.unwrap().unwrap();
+            let mut buf = [0u8; 1];
+            assert_eq!(stream.read(&mut buf).await.unwrap(), 1);
+            assert_eq!(buf[0], i);
+        }
+    });
+}
@@ -0,0 +1,495 @@
+use std::ffi::OsStr;
+use std::fs::File;
+use std::future::poll_fn;
+use std::io::{self, Read as _, Write as _};
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+use std::path::Path;
+use std::pin::{Pin, pin};
+use std::process::{self, ExitStatus, Output, Stdio};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Wake, Waker};
+
+use crate::io::reactor::{Interest, Registration};
+use crate::io::{AsyncRead, AsyncReadExt, AsyncWrite};
+use crate::signal::{self, Signal, SignalKind};
+
+// 子プロセスを起動する。設定はstd::process::Commandと同じように&mut selfで積み上げる
+// 標準入出力をpipedにすると、ノンブロッキングのパイプとしてChildから取り出せる
+pub struct Command {
+    inner: process::Command,
+    kill_on_drop: bool,
+    // output()で、明示的に設定されていない標準入出力だけを置き換えるために覚えておく
+    stdin_set: bool,
+    stdout_set: bool,
+    stderr_set: bool,
+}
+
+impl Command {
+    pub fn new<S: AsRef<OsStr>>(program: S) -> Self {
+        Self {
+            inner: process::Command::new(program),
+            kill_on_drop: false,
+            stdin_set: false,
+            stdout_set: false,
+            stderr_set: false,
+        }
+    }
+
+    pub fn arg<S: AsRef<OsStr>>(&mut self, arg: S) -> &mut Self {
+        self.inner.arg(arg);
+        self
+    }
+
+    pub fn args<I, S>(&mut self, args: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        self.inner.args(args);
+        self
+    }
+
+    pub fn env<K: AsRef<OsStr>, V: AsRef<OsStr>>(&mut self, key: K, val: V) -> &mut Self {
+        self.inner.env(key, val);
+        self
+    }
+
+    pub fn env_remove<K: AsRef<OsStr>>(&mut self, key: K) -> &mut Self {
+        self.inner.env_remove(key);
+        self
+    }
+
+    pub fn env_clear(&mut self) -> &mut Self {
+        self.inner.env_clear();
+        self
+    }
+
+    pub fn current_dir<P: AsRef<Path>>(&mut self, dir: P) -> &mut Self {
+        self.inner.current_dir(dir);
+        self
+    }
+
+    pub fn stdin<T: Into<Stdio>>(&mut self, cfg: T) -> &mut Self {
+        self.inner.stdin(cfg);
+        self.stdin_set = true;
+        self
+    }
+
+    pub fn stdout<T: Into<Stdio>>(&mut self, cfg: T) -> &mut Self {
+        self.inner.stdout(cfg);
+        self.stdout_set = true;
+        self
+    }
+
+    pub fn stderr<T: Into<Stdio>>(&mut self, cfg: T) -> &mut Self {
+        self.inner.stderr(cfg);
+        self.stderr_set = true;
+        self
+    }
+
+    // Childを終了を待たずに破棄したら、子プロセスをSIGKILLで止める
+    // どちらの場合も、終了した子プロセスは裏で回収される
+    pub fn kill_on_drop(&mut self, kill_on_drop: bool) -> &mut Self {
+        self.kill_on_drop = kill_on_drop;
+        self
+    }
+
+    pub fn spawn(&mut self) -> io::Result<Child> {
+        let mut child = self.inner.spawn()?;
+        let stdin = child.stdin.take().map(ChildStdin::new).transpose();
+        let stdout = child.stdout.take().map(ChildStdout::new).transpose();
+        let stderr = child.stderr.take().map(ChildStderr::new).transpose();
+        let exit = Exit::new(child.id());
+        let (stdin, stdout, stderr, exit) = match (stdin, stdout, stderr, exit) {
+            (Ok(stdin), Ok(stdout), Ok(stderr), Ok(exit)) => (stdin, stdout, stderr, exit),
+            (Err(e), ..) | (_, Err(e), ..) | (_, _, Err(e), _) | (.., Err(e)) => {
+                // 待てない子プロセスを残さない
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(e);
+            }
+        };
+        Ok(Child {
+            child,
+            exit,
+            status: None,
+            kill_on_drop: self.kill_on_drop,
+            stdin,
+            stdout,
+            stderr,
+        })
+    }
+
+    // 子プロセスを起動して終了を待つ
+    pub async fn status(&mut self) -> io::Result<ExitStatus> {
+        self.spawn()?.wait().await
+    }
+
+    // 設定していなければ、標準入力は空にし、標準出力とエラー出力は読み取って返す
+    pub async fn output(&mut self) -> io::Result<Output> {
+        if !self.stdin_set {
+            self.inner.stdin(Stdio::null());
+        }
+        if !self.stdout_set {
+            self.inner.stdout(Stdio::piped());
+        }
+        if !self.stderr_set {
+            self.inner.stderr(Stdio::piped());
+        }
+        let child = self.spawn();
+        // 置き換えはこのspawnだけに使い、後のspawnでは設定していないものを元の継承に戻す
+        if !self.stdin_set {
+            self.inner.stdin(Stdio::inherit());
+        }
+        if !self.stdout_set {
+            self.inner.stdout(Stdio::inherit());
+        }
+        if !self.stderr_set {
+            self.inner.stderr(Stdio::inherit());
+        }
+        child?.wait_with_output().await
+    }
+}
+
+impl From<process::Command> for Command {
+    fn from(inner: process::Command) -> Self {
+        // stdのCommandに設定済みの標準入出力はそのまま使う
+        Self {
+            inner,
+            kill_on_drop: false,
+            stdin_set: true,
+            stdout_set: true,
+            stderr_set: true,
+        }
+    }
+}
+
+pub struct Child {
+    child: process::Child,
+    exit: Exit,
+    // 一度回収した終了状態。waitを何度呼んでも同じものを返す
+    status: Option<ExitStatus>,
+    kill_on_drop: bool,
+    pub stdin: Option<ChildStdin>,
+    pub stdout: Option<ChildStdout>,
+    pub stderr: Option<ChildStderr>,
+}
+
+impl Child {
+    pub fn id(&self) -> u32 {
+        self.child.id()
+    }
+
+    // 終了を待つ。子プロセスが入力を待ち続けないように、先にstdinを閉じる
+    pub async fn wait(&mut self) -> io::Result<ExitStatus> {
+        drop(self.stdin.take());
+        poll_fn(|cx| self.poll_wait(cx)).await
+    }
+
+    // 終了していれば終了状態を返し、まだ動いていればNone
+    pub fn try_wait(&mut self) -> io::Result<Option<ExitStatus>> {
+        if self.status.is_none() {
+            self.status = self.child.try_wait()?;
+        }
+        Ok(self.status)
+    }
+
+    // SIGKILLを送るだけで、終了は待たない
+    pub fn start_kill(&mut self) -> io::Result<()> {
+        if self.status.is_some() {
+            return Ok(());
+        }
+        self.child.kill()
+    }
+
+    pub async fn kill(&mut self) -> io::Result<()> {
+        self.start_kill()?;
+        self.wait().await.map(drop)
+    }
+
+    // 標準出力とエラー出力を同時に読みながら終了を待つ
+    // 片方だけを読んでいると、もう片方のパイプが詰まって子プロセスが止まる
+    pub async fn wait_with_output(mut self) -> io::Result<Output> {
+        drop(self.stdin.take());
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+        {
+            let mut stdout_pipe = self.stdout.take();
+            let mut stderr_pipe = self.stderr.take();
+            let mut read_stdout = pin!(async {
+                match &mut stdout_pipe {
+                    Some(pipe) => pipe.read_to_end(&mut stdout).await.map(drop),
+                    None => Ok(()),
+                }
+            });
+            let mut read_stderr = pin!(async {
+                match &mut stderr_pipe {
+                    Some(pipe) => pipe.read_to_end(&mut stderr).await.map(drop),
+                    None => Ok(()),
+                }
+            });
+            let (mut stdout_done, mut stderr_done) = (false, false);
+            poll_fn(|cx| {
+                if !stdout_done && let Poll::Ready(res) = read_stdout.as_mut().poll(cx) {
+                    res?;
+                    stdout_done = true;
+                }
+                if !stderr_done && let Poll::Ready(res) = read_stderr.as_mut().poll(cx) {
+                    res?;
+                    stderr_done = true;
+                }
+                if stdout_done && stderr_done {
+                    Poll::Ready(Ok::<_, io::Error>(()))
+                } else {
+                    Poll::Pending
+                }
+            })
+            .await?;
+        }
+        let status = self.wait().await?;
+        Ok(Output {
+            status,
+            stdout,
+            stderr,
+        })
+    }
+
+    fn poll_wait(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<ExitStatus>> {
+        loop {
+            if let Some(status) = self.try_wait()? {
+                return Poll::Ready(Ok(status));
+            }
+            // 終了を知らせるイベントが来たら回収し直す
+            match self.exit.poll_exited(cx) {
+                Poll::Ready(Ok(())) => continue,
+                Poll::Ready(Err(e)) => return Poll::Ready(Err(e)),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+impl Drop for Child {
+    fn drop(&mut self) {
+        // 回収済みか、もう待てない子プロセスなら何もしない
+        if !matches!(self.try_wait(), Ok(None)) {
+            return;
+        }
+        if self.kill_on_drop {
+            let _ = self.child.kill();
+        }
+        // ここで待つとDropが止まるので、終了を待たずにOrphansへ渡す
+        orphan(self.child.id() as libc::pid_t);
+    }
+}
+
+// 終了を待たずに破棄された子プロセス。SIGCHLDが届くたびにまとめて回収し、ゾンビを残さない
+static ORPHANS: Mutex<Orphans> = Mutex::new(Orphans {
+    pids: Vec::new(),
+    signal: None,
+});
+
+struct Orphans {
+    pids: Vec<libc::pid_t>,
+    // 作れなかったときは、次に破棄されたときの回収に任せる
+    signal: Option<Signal>,
+}
+
+fn orphan(pid: libc::pid_t) {
+    let mut orphans = ORPHANS.lock().unwrap();
+    if orphans.signal.is_none() {
+        orphans.signal = signal::unix(SignalKind::child()).ok();
+    }
+    orphans.pids.push(pid);
+    orphans.reap();
+}
+
+impl Orphans {
+    fn reap(&mut self) {
+        if self.pids.is_empty() {
+            return;
+        }
+        if let Some(signal) = &mut self.signal {
+            // 先に次のSIGCHLDで起こされるようにしてから回収し、その間に終了したものを取りこぼさない
+            let waker = Waker::from(Arc::new(Reaper));
+            let mut cx = Context::from_waker(&waker);
+            while signal.poll_recv(&mut cx).is_ready() {}
+        }
+        self.pids.retain(|&pid| {
+            match unsafe { libc::waitpid(pid, std::ptr::null_mut(), libc::WNOHANG) } {
+                0 => true,
+                -1 => io::Error::last_os_error().kind() == io::ErrorKind::Interrupted,
+                _ => false,
+            }
+        });
+    }
+}
+
+// SIGCHLDが届いたら、Reactorのスレッドから呼ばれる。waitpidはWNOHANGなので止まらない
+struct Reaper;
+
+impl Wake for Reaper {
+    fn wake(self: Arc<Self>) {
+        ORPHANS.lock().unwrap().reap();
+    }
+}
+
+// 子プロセスの終了を待つ方法
+// pidfdが使えればReactorで待ち、古いカーネルではSIGCHLDを待つ
+// SIGCHLDはどの子プロセスのものか分からないので、届くたびにtry_waitで確かめ直す
+enum Exit {
+    Pidfd {
+        // fdを閉じる前にReactorから外すため、registrationを先に置く
+        registration: Registration,
+        _fd: OwnedFd,
+    },
+    Signal(Signal),
+}
+
+impl Exit {
+    fn new(pid: u32) -> io::Result<Self> {
+        let fd = unsafe { libc::syscall(libc::SYS_pidfd_open, pid as libc::pid_t, 0) };
+        if fd >= 0 {
+            let fd = unsafe { OwnedFd::from_raw_fd(fd as RawFd) };
+            return Ok(Exit::Pidfd {
+                registration: Registration::new(fd.as_raw_fd())?,
+                _fd: fd,
+            });
+        }
+        let err = io::Error::last_os_error();
+        if err.raw_os_error() != Some(libc::ENOSYS) {
+            return Err(err);
+        }
+        // spawnの後に作るが、その前に終了していてもpoll_waitが先にtry_waitで拾う
+        Ok(Exit::Signal(signal::unix(SignalKind::child())?))
+    }
+
+    // 子プロセスが終了していればReady。その後はtry_waitで回収できる
+    fn poll_exited(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self {
+            Exit::Pidfd { registration, .. } => {
+                // pidfdは終了すると読み込み可能になり、その後はずっとそのまま
+                registration
+                    .poll_ready(cx, Interest::Readable)
+                    .map(|_| Ok(()))
+            }
+            // 別の子プロセスのSIGCHLDでもReadyになる。poll_waitがtry_waitで確かめる
+            Exit::Signal(signal) => signal.poll_recv(cx).map(|_| Ok(())),
+        }
+    }
+}
+
+// Reactorに登録したノンブロッキングのパイプ
+struct Pipe {
+    registration: Registration,
+    file: File,
+}
+
+impl Pipe {
+    fn new(fd: OwnedFd) -> io::Result<Self> {
+        let raw = fd.as_raw_fd();
+        let flags = unsafe { libc::fcntl(raw, libc::F_GETFL) };
+        if flags < 0 || unsafe { libc::fcntl(raw, libc::F_SETFL, flags | libc::O_NONBLOCK) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Self {
+            registration: Registration::new(raw)?,
+            file: File::from(fd),
+        })
+    }
+
+    fn poll_read(&mut self, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+        let file = &self.file;
+        self.registration
+            .poll_io(cx, Interest::Readable, || (&*file).read(buf))
+    }
+
+    fn poll_write(&mut self, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        let file = &self.file;
+        self.registration
+            .poll_io(cx, Interest::Writable, || (&*file).write(buf))
+    }
+}
+
+// 子プロセスの標準入力。shutdownすると閉じて、子プロセスにEOFを伝える
+pub struct ChildStdin {
+    pipe: Option<Pipe>,
+}
+
+impl ChildStdin {
+    fn new(stdin: process::ChildStdin) -> io::Result<Self> {
+        Ok(Self {
+            pipe: Some(Pipe::new(stdin.into())?),
+        })
+    }
+}
+
+impl AsyncWrite for ChildStdin {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        match &mut self.get_mut().pipe {
+            Some(pipe) => pipe.poll_write(cx, buf),
+            None => Poll::Ready(Err(io::ErrorKind::BrokenPipe.into())),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        // パイプはバッファを持たないので何もしない
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        drop(self.get_mut().pipe.take());
+        Poll::Ready(Ok(()))
+    }
+}
+
+pub struct ChildStdout {
+    pipe: Pipe,
+}
+
+impl ChildStdout {
+    fn new(stdout: process::ChildStdout) -> io::Result<Self> {
+        Ok(Self {
+            pipe: Pipe::new(stdout.into())?,
+        })
+    }
+}
+
+impl AsyncRead for ChildStdout {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        self.get_mut().pipe.poll_read(cx, buf)
+    }
+}
+
+pub struct ChildStderr {
+    pipe: Pipe,
+}
+
+impl ChildStderr {
+    fn new(stderr: process::ChildStderr) -> io::Result<Self> {
+        Ok(Self {
+            pipe: Pipe::new(stderr.into())?,
+        })
+    }
+}
+
+impl AsyncRead for ChildStderr {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        self.get_mut().pipe.poll_read(cx, buf)
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod test;
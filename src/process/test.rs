@@ -0,0 +1,151 @@
+use std::os::unix::process::ExitStatusExt;
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+
+use super::Command;
+use crate::engine::schedule::fifo::Fifo;
+use crate::engine::{Engine, block_on};
+use crate::io::{AsyncReadExt, AsyncWriteExt};
+
+fn sh(script: &str) -> Command {
+    let mut command = Command::new("sh");
+    command.arg("-c").arg(script);
+    command
+}
+
+#[test]
+fn output_captures_stdout_and_stderr() {
+    let output = block_on(sh("echo out; echo err >&2; exit 3").output()).unwrap();
+    assert_eq!(output.status.code(), Some(3));
+    assert_eq!(output.stdout, b"out\n");
+    assert_eq!(output.stderr, b"err\n");
+}
+
+#[test]
+fn output_reads_both_pipes_concurrently() {
+    // パイプのバッファより大きい出力を両方に書く
+    let output =
+        block_on(sh("head -c 200000 /dev/zero >&2; head -c 200000 /dev/zero").output()).unwrap();
+    assert!(output.status.success());
+    assert_eq!(output.stdout.len(), 200000);
+    assert_eq!(output.stderr.len(), 200000);
+}
+
+#[test]
+fn output_defaults_do_not_leak_into_spawn() {
+    let mut command = sh("exit 0");
+    block_on(command.output()).unwrap();
+    // output()が使った既定値は残らず、設定していない標準入出力は継承される
+    let mut child = command.spawn().unwrap();
+    assert!(child.stdin.is_none());
+    assert!(child.stdout.is_none());
+    assert!(child.stderr.is_none());
+    block_on(child.wait()).unwrap();
+}
+
+#[test]
+fn piped_stdin_and_stdout() {
+    block_on(async {
+        let mut child = Command::new("cat")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut stdin = child.stdin.take().unwrap();
+        stdin.write_all(b"hello").await.unwrap();
+        stdin.shutdown().await.unwrap();
+
+        let mut out = Vec::new();
+        child
+            .stdout
+            .as_mut()
+            .unwrap()
+            .read_to_end(&mut out)
+            .await
+            .unwrap();
+        assert_eq!(out, b"hello");
+        assert!(child.wait().await.unwrap().success());
+    });
+}
+
+#[test]
+fn wait_on_engine_worker() {
+    let engine = Engine::new(1, Fifo::new());
+    let status = block_on(engine.spawn(async { sh("sleep 0.1; exit 7").status().await }));
+    assert_eq!(status.unwrap().unwrap().code(), Some(7));
+    engine.shutdown_now();
+}
+
+#[test]
+fn wait_returns_the_same_status_twice() {
+    block_on(async {
+        let mut child = sh("exit 4").spawn().unwrap();
+        assert_eq!(child.wait().await.unwrap().code(), Some(4));
+        assert_eq!(child.wait().await.unwrap().code(), Some(4));
+        assert_eq!(child.try_wait().unwrap().unwrap().code(), Some(4));
+    });
+}
+
+#[test]
+fn kill_stops_child() {
+    block_on(async {
+        let mut child = Command::new("sleep").arg("10").spawn().unwrap();
+        assert!(child.try_wait().unwrap().is_none());
+        child.kill().await.unwrap();
+        let status = child.wait().await.unwrap();
+        assert_eq!(status.signal(), Some(libc::SIGKILL));
+    });
+}
+
+// ゾンビもkill(pid, 0)には成功するので、ESRCHになれば回収済み
+fn wait_reaped(pid: libc::pid_t) {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while unsafe { libc::kill(pid, 0) } == 0 {
+        assert!(Instant::now() < deadline, "child {pid} was not reaped");
+        std::thread::sleep(Duration::from_millis(5));
+    }
+    assert_eq!(
+        std::io::Error::last_os_error().raw_os_error(),
+        Some(libc::ESRCH)
+    );
+}
+
+#[test]
+fn kill_on_drop_reaps_child() {
+    let child = Command::new("sleep")
+        .arg("10")
+        .kill_on_drop(true)
+        .spawn()
+        .unwrap();
+    let pid = child.id() as libc::pid_t;
+    drop(child);
+    wait_reaped(pid);
+}
+
+#[test]
+fn dropped_child_is_reaped_after_it_exits() {
+    let child = sh("sleep 0.1").spawn().unwrap();
+    let pid = child.id() as libc::pid_t;
+    drop(child);
+    wait_reaped(pid);
+}
+
+#[test]
+fn child_keeps_running_without_kill_on_drop() {
+    let child = Command::new("sleep").arg("10").spawn().unwrap();
+    let pid = child.id() as libc::pid_t;
+    drop(child);
+    std::thread::sleep(Duration::from_millis(20));
+    assert_eq!(unsafe { libc::kill(pid, 0) }, 0);
+    // 後片付け
+    unsafe {
+        libc::kill(pid, libc::SIGKILL);
+        libc::waitpid(pid, std::ptr::null_mut(), 0);
+    }
+}
+
+#[test]
+fn spawn_error_is_reported() {
+    let err = Command::new("/nonexistent/program").spawn().err().unwrap();
+    assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+}
@@ -0,0 +1,293 @@
+use std::collections::HashMap;
+use std::future::{Future, poll_fn};
+use std::io;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicI32, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::task::{Context, Poll, Wake, Waker};
+
+use crate::io::reactor::{Interest, Registration};
+use crate::utils::stream::Stream;
+
+// 受け取れるシグナル番号の上限（リアルタイムシグナルを含む）
+const MAX_SIGNAL: usize = 65;
+
+// シグナルハンドラから触るものは、ロックを使わずに済むstaticに置く
+static COUNTS: [AtomicU64; MAX_SIGNAL] = [const { AtomicU64::new(0) }; MAX_SIGNAL];
+static WRITE_FD: AtomicI32 = AtomicI32::new(-1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SignalKind(libc::c_int);
+
+impl SignalKind {
+    pub const fn from_raw(signum: libc::c_int) -> Self {
+        SignalKind(signum)
+    }
+
+    pub const fn as_raw_value(self) -> libc::c_int {
+        self.0
+    }
+
+    pub const fn alarm() -> Self {
+        SignalKind(libc::SIGALRM)
+    }
+
+    pub const fn child() -> Self {
+        SignalKind(libc::SIGCHLD)
+    }
+
+    // 設定の再読み込みによく使われる
+    pub const fn hangup() -> Self {
+        SignalKind(libc::SIGHUP)
+    }
+
+    // Ctrl-C
+    pub const fn interrupt() -> Self {
+        SignalKind(libc::SIGINT)
+    }
+
+    pub const fn pipe() -> Self {
+        SignalKind(libc::SIGPIPE)
+    }
+
+    pub const fn quit() -> Self {
+        SignalKind(libc::SIGQUIT)
+    }
+
+    // 停止の要求
+    pub const fn terminate() -> Self {
+        SignalKind(libc::SIGTERM)
+    }
+
+    pub const fn user_defined1() -> Self {
+        SignalKind(libc::SIGUSR1)
+    }
+
+    pub const fn user_defined2() -> Self {
+        SignalKind(libc::SIGUSR2)
+    }
+
+    pub const fn window_change() -> Self {
+        SignalKind(libc::SIGWINCH)
+    }
+}
+
+// シグナルが届くたびに値を返すStream。終端はない
+// 前回受け取ってから何度届いていても、まとめて1回になる
+// 最初に作ったときにハンドラを差し替え、それ以降はデフォルトの動作（終了など）をしなくなる
+pub struct Signal {
+    signum: usize,
+    // 最後に受け取ったときの届いた回数
+    seen: u64,
+    id: u64,
+}
+
+pub fn unix(kind: SignalKind) -> io::Result<Signal> {
+    let signum = kind.as_raw_value();
+    if signum <= 0 || signum as usize >= MAX_SIGNAL || FORBIDDEN.contains(&signum) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("signal {signum} cannot be handled"),
+        ));
+    }
+    let globals = Globals::get()?;
+    globals.install(signum)?;
+    let signum = signum as usize;
+    Ok(Signal {
+        signum,
+        seen: COUNTS[signum].load(Ordering::Acquire),
+        id: globals.next_id.fetch_add(1, Ordering::Relaxed),
+    })
+}
+
+// SIGINTが届くまで待つ
+pub async fn ctrl_c() -> io::Result<()> {
+    let mut signal = unix(SignalKind::interrupt())?;
+    signal.recv().await;
+    Ok(())
+}
+
+// 捕まえられないか、捕まえると処理を続けられないシグナル
+const FORBIDDEN: [libc::c_int; 6] = [
+    libc::SIGKILL,
+    libc::SIGSTOP,
+    libc::SIGSEGV,
+    libc::SIGBUS,
+    libc::SIGILL,
+    libc::SIGFPE,
+];
+
+impl Signal {
+    // 次に届くまで待つ。いつもSomeを返す
+    pub fn recv(&mut self) -> impl Future<Output = Option<()>> + '_ {
+        poll_fn(|cx| self.poll_recv(cx))
+    }
+
+    pub fn poll_recv(&mut self, cx: &mut Context<'_>) -> Poll<Option<()>> {
+        // unix()が成功していれば初期化済み
+        let globals = GLOBALS.get().expect("signal globals must be initialized");
+        if self.take() {
+            return Poll::Ready(Some(()));
+        }
+        globals
+            .listeners
+            .lock()
+            .unwrap()
+            .insert(self.id, cx.waker().clone());
+        globals.drain();
+        // Wakerを登録している間に届いたものを取りこぼさない
+        if self.take() {
+            return Poll::Ready(Some(()));
+        }
+        Poll::Pending
+    }
+
+    fn take(&mut self) -> bool {
+        let count = COUNTS[self.signum].load(Ordering::Acquire);
+        if count == self.seen {
+            return false;
+        }
+        self.seen = count;
+        true
+    }
+}
+
+impl Stream for Signal {
+    type Item = ();
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<()>> {
+        self.get_mut().poll_recv(cx)
+    }
+}
+
+impl Drop for Signal {
+    fn drop(&mut self) {
+        if let Some(globals) = GLOBALS.get() {
+            globals.listeners.lock().unwrap().remove(&self.id);
+        }
+    }
+}
+
+// ハンドラが書き込むパイプと、それを待っているSignal
+struct Globals {
+    // fdを閉じる前にReactorから外すため、registrationを先に置く
+    registration: Registration,
+    read: OwnedFd,
+    write: OwnedFd,
+    installed: Mutex<[bool; MAX_SIGNAL]>,
+    // Signalごとの起こし先。パイプに書き込まれたら全て起こす
+    listeners: Mutex<HashMap<u64, Waker>>,
+    next_id: AtomicU64,
+    broadcast: Waker,
+}
+
+static GLOBALS: OnceLock<Globals> = OnceLock::new();
+
+impl Globals {
+    fn get() -> io::Result<&'static Globals> {
+        if let Some(globals) = GLOBALS.get() {
+            return Ok(globals);
+        }
+        let mut fds = [0; 2];
+        if unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_NONBLOCK | libc::O_CLOEXEC) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let read = unsafe { OwnedFd::from_raw_fd(fds[0]) };
+        let write = unsafe { OwnedFd::from_raw_fd(fds[1]) };
+        let globals = Globals {
+            registration: Registration::new(read.as_raw_fd())?,
+            read,
+            write,
+            installed: Mutex::new([false; MAX_SIGNAL]),
+            listeners: Mutex::new(HashMap::new()),
+            next_id: AtomicU64::new(0),
+            broadcast: Waker::from(Arc::new(Broadcast)),
+        };
+        // 同時に初期化された場合は、先に入った方を使う
+        let _ = GLOBALS.set(globals);
+        let globals = GLOBALS.get().unwrap();
+        let _ = WRITE_FD.compare_exchange(
+            -1,
+            globals.write.as_raw_fd(),
+            Ordering::AcqRel,
+            Ordering::Acquire,
+        );
+        Ok(globals)
+    }
+
+    fn install(&self, signum: libc::c_int) -> io::Result<()> {
+        let mut installed = self.installed.lock().unwrap();
+        if installed[signum as usize] {
+            return Ok(());
+        }
+        let mut action: libc::sigaction = unsafe { std::mem::zeroed() };
+        action.sa_sigaction = handler as extern "C" fn(libc::c_int) as libc::sighandler_t;
+        action.sa_flags = libc::SA_RESTART;
+        unsafe { libc::sigemptyset(&mut action.sa_mask) };
+        if unsafe { libc::sigaction(signum, &action, std::ptr::null_mut()) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        installed[signum as usize] = true;
+        Ok(())
+    }
+
+    // パイプを空にする。読み込み可能になったらbroadcastが全てのSignalを起こす
+    fn drain(&self) {
+        let mut cx = Context::from_waker(&self.broadcast);
+        let mut buf = [0u8; 64];
+        while let Poll::Ready(event) = self.registration.poll_ready(&mut cx, Interest::Readable) {
+            let n =
+                unsafe { libc::read(self.read.as_raw_fd(), buf.as_mut_ptr().cast(), buf.len()) };
+            if n < 0 {
+                let err = io::Error::last_os_error();
+                match err.kind() {
+                    io::ErrorKind::WouldBlock => self.registration.clear_readiness(event),
+                    io::ErrorKind::Interrupted => {}
+                    _ => panic!("failed to read signal pipe: {err}"),
+                }
+            }
+        }
+    }
+}
+
+struct Broadcast;
+
+impl Wake for Broadcast {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        let Some(globals) = GLOBALS.get() else {
+            return;
+        };
+        let wakers: Vec<Waker> = globals
+            .listeners
+            .lock()
+            .unwrap()
+            .drain()
+            .map(|(_, waker)| waker)
+            .collect();
+        wakers.into_iter().for_each(Waker::wake);
+    }
+}
+
+// シグナルハンドラの中ではasync-signal-safeな操作しかできない
+// 回数を数えてパイプに1バイト書くだけにして、残りはSignalのpollに任せる
+extern "C" fn handler(signum: libc::c_int) {
+    let errno = io::Error::last_os_error().raw_os_error().unwrap_or(0);
+    if let Some(count) = COUNTS.get(signum as usize) {
+        count.fetch_add(1, Ordering::AcqRel);
+    }
+    let fd = WRITE_FD.load(Ordering::Acquire);
+    if fd >= 0 {
+        // パイプが一杯なら、読まれていないバイトが残っているので書かなくてよい
+        let byte = signum as u8;
+        unsafe { libc::write(fd, (&byte as *const u8).cast(), 1) };
+    }
+    unsafe { *libc::__errno_location() = errno };
+}
+
+#[cfg(all(test, not(loom)))]
+mod test;
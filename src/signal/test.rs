@@ -0,0 +1,80 @@
+use std::future::Future;
+use std::pin::pin;
+use std::sync::mpsc;
+use std::task::{Context, Poll, Waker};
+
+use super::{SignalKind, ctrl_c, unix};
+use crate::engine::schedule::fifo::Fifo;
+use crate::engine::{Engine, block_on};
+use crate::utils::stream::StreamExt;
+
+// テストは同じプロセスで並行に動くので、テストごとに別のシグナルを使う
+fn send(kind: SignalKind) {
+    assert_eq!(
+        unsafe { libc::kill(libc::getpid(), kind.as_raw_value()) },
+        0
+    );
+}
+
+#[test]
+fn receives_signal() {
+    let mut signal = unix(SignalKind::user_defined1()).unwrap();
+    send(SignalKind::user_defined1());
+    assert_eq!(block_on(signal.recv()), Some(()));
+}
+
+#[test]
+fn every_listener_is_woken() {
+    let mut a = unix(SignalKind::user_defined2()).unwrap();
+    let mut b = unix(SignalKind::user_defined2()).unwrap();
+    send(SignalKind::user_defined2());
+    assert_eq!(block_on(a.next()), Some(()));
+    assert_eq!(block_on(b.next()), Some(()));
+}
+
+#[test]
+fn earlier_signals_are_not_observed() {
+    let kind = SignalKind::window_change();
+    let mut early = unix(kind).unwrap();
+    send(kind);
+    block_on(early.recv());
+
+    let mut late = unix(kind).unwrap();
+    let mut cx = Context::from_waker(Waker::noop());
+    assert_eq!(late.poll_recv(&mut cx), Poll::Pending);
+    send(kind);
+    assert_eq!(block_on(late.recv()), Some(()));
+}
+
+#[test]
+fn wakes_task_on_engine() {
+    let engine = Engine::new(1, Fifo::new());
+    let (ready_tx, ready_rx) = mpsc::channel();
+    let handle = engine.spawn(async move {
+        let mut hangup = unix(SignalKind::hangup()).unwrap();
+        ready_tx.send(()).unwrap();
+        hangup.recv().await
+    });
+    ready_rx.recv().unwrap();
+    send(SignalKind::hangup());
+    assert_eq!(block_on(handle), Ok(Some(())));
+    engine.shutdown_now();
+}
+
+#[test]
+fn ctrl_c_completes_on_sigint() {
+    let mut future = pin!(ctrl_c());
+    // 最初のpollでハンドラを設定してから送る
+    let mut cx = Context::from_waker(Waker::noop());
+    assert!(future.as_mut().poll(&mut cx).is_pending());
+    send(SignalKind::interrupt());
+    block_on(future).unwrap();
+}
+
+#[test]
+fn forbidden_signals_are_rejected() {
+    for signum in [libc::SIGKILL, libc::SIGSTOP, libc::SIGSEGV, 0, 100] {
+        let err = unix(SignalKind::from_raw(signum)).err().unwrap();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    }
+}
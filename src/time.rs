@@ -0,0 +1,211 @@
+pub(crate) mod clock;
+pub(crate) mod driver;
+
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use crate::engine::coop;
+use crate::utils::stream::Stream;
+use driver::{Driver, TimerKey};
+
+// 現在時刻。シミュレーション中は仮想時計の時刻を返す
+pub fn now() -> Instant {
+    Driver::with_current(|driver| driver.now())
+}
+
+// 時計を止める。以降はadvanceか自動で進めたときだけ時刻が進む
+// 止めている間に全てのWorkerが眠ると、次のタイマーの期限まで自動で進む
+// 他のEngineに影響しないよう、Engine（Handle::enter()の範囲内）かSimulationの中で呼ぶ
+pub fn pause() {
+    Driver::with_current(|driver| {
+        assert!(
+            !driver.is_global(),
+            "time::pause must be called from within an Engine or Simulation"
+        );
+        driver.clock().pause();
+    })
+}
+
+// 止めた時計をdurationだけ進め、期限が来たタイマーを起こす
+pub fn advance(duration: Duration) {
+    Driver::with_current(|driver| {
+        assert!(
+            driver.clock().is_paused(),
+            "time must be paused before advancing"
+        );
+        driver.advance(duration);
+    })
+}
+
+pub fn sleep(duration: Duration) -> Sleep {
+    sleep_until(now() + duration)
+}
+
+pub fn sleep_until(deadline: Instant) -> Sleep {
+    Sleep {
+        deadline,
+        key: None,
+        driver: Driver::current(),
+    }
+}
+
+pub struct Sleep {
+    deadline: Instant,
+    key: Option<TimerKey>,
+    // 作られたときの時計で期限を判定する
+    driver: Arc<Driver>,
+}
+
+impl Sleep {
+    pub fn deadline(&self) -> Instant {
+        self.deadline
+    }
+
+    pub fn is_elapsed(&self) -> bool {
+        self.driver.now() >= self.deadline
+    }
+
+    pub fn reset(&mut self, deadline: Instant) {
+        self.deadline = deadline;
+    }
+}
+
+impl Future for Sleep {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if coop::poll_proceed(cx).is_pending() {
+            return Poll::Pending;
+        }
+        if self.is_elapsed() {
+            if let Some(key) = self.key.take() {
+                self.driver.cancel(key);
+            }
+            return Poll::Ready(());
+        }
+
+        let key = self.driver.register(self.key, self.deadline, cx.waker());
+        self.key = Some(key);
+        Poll::Pending
+    }
+}
+
+impl Drop for Sleep {
+    fn drop(&mut self) {
+        if let Some(key) = self.key.take() {
+            self.driver.cancel(key);
+        }
+    }
+}
+
+// 最初のtickは即座に完了し、以降periodごとに完了する
+pub fn interval(period: Duration) -> Interval {
+    interval_at(now(), period)
+}
+
+pub fn interval_at(start: Instant, period: Duration) -> Interval {
+    assert!(!period.is_zero(), "interval period must be non-zero");
+    Interval {
+        period,
+        sleep: sleep_until(start),
+    }
+}
+
+pub struct Interval {
+    period: Duration,
+    sleep: Sleep,
+}
+
+impl Interval {
+    pub fn period(&self) -> Duration {
+        self.period
+    }
+
+    pub fn tick(&mut self) -> Tick<'_> {
+        Tick { interval: self }
+    }
+
+    pub fn poll_tick(&mut self, cx: &mut Context<'_>) -> Poll<Instant> {
+        if Pin::new(&mut self.sleep).poll(cx).is_pending() {
+            return Poll::Pending;
+        }
+
+        let tick = self.sleep.deadline();
+        let now = self.sleep.driver.now();
+        // 処理が遅れて複数回分の期限を過ぎた場合は、まとめて追いつかずにスキップする
+        let mut next = tick + self.period;
+        if next <= now {
+            next = now + self.period;
+        }
+        self.sleep.reset(next);
+        Poll::Ready(tick)
+    }
+}
+
+impl Stream for Interval {
+    type Item = Instant;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Instant>> {
+        self.get_mut().poll_tick(cx).map(Some)
+    }
+}
+
+pub struct Tick<'a> {
+    interval: &'a mut Interval,
+}
+
+impl Future for Tick<'_> {
+    type Output = Instant;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Instant> {
+        self.interval.poll_tick(cx)
+    }
+}
+
+pub fn timeout<F: Future>(duration: Duration, future: F) -> Timeout<F> {
+    Timeout {
+        future,
+        sleep: sleep(duration),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Elapsed;
+
+impl fmt::Display for Elapsed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "deadline has elapsed")
+    }
+}
+
+impl std::error::Error for Elapsed {}
+
+pub struct Timeout<F> {
+    future: F,
+    sleep: Sleep,
+}
+
+impl<F: Future> Future for Timeout<F> {
+    type Output = Result<F::Output, Elapsed>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // SAFETY: futureはピン留めされたまま、ここ以外で動かさない
+        let this = unsafe { self.get_unchecked_mut() };
+        let future = unsafe { Pin::new_unchecked(&mut this.future) };
+
+        if let Poll::Ready(out) = future.poll(cx) {
+            return Poll::Ready(Ok(out));
+        }
+        match Pin::new(&mut this.sleep).poll(cx) {
+            Poll::Ready(()) => Poll::Ready(Err(Elapsed)),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod test;
@@ -0,0 +1,49 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+// タイマーが参照する時刻
+// 止めている間は実時間と切り離され、advance_toでのみ進む
+pub(crate) struct Clock {
+    frozen: Mutex<Option<Instant>>,
+}
+
+impl Clock {
+    pub(crate) fn real() -> Self {
+        Self {
+            frozen: Mutex::new(None),
+        }
+    }
+
+    // 作った時点の時刻で止まった時計
+    pub(crate) fn paused() -> Self {
+        Self {
+            frozen: Mutex::new(Some(Instant::now())),
+        }
+    }
+
+    pub(crate) fn now(&self) -> Instant {
+        self.frozen.lock().unwrap().unwrap_or_else(Instant::now)
+    }
+
+    // 今の時刻で止める。止まっていれば何もしない
+    pub(crate) fn pause(&self) {
+        self.frozen.lock().unwrap().get_or_insert_with(Instant::now);
+    }
+
+    pub(crate) fn is_paused(&self) -> bool {
+        self.frozen.lock().unwrap().is_some()
+    }
+
+    // 止めている時計をdeadlineまで進める。過去へは戻さない
+    pub(crate) fn advance_to(&self, deadline: Instant) {
+        let mut frozen = self.frozen.lock().unwrap();
+        let now = frozen.as_mut().expect("clock must be paused to advance");
+        *now = (*now).max(deadline);
+    }
+
+    pub(crate) fn advance(&self, duration: Duration) {
+        let mut frozen = self.frozen.lock().unwrap();
+        let now = frozen.as_mut().expect("clock must be paused to advance");
+        *now += duration;
+    }
+}
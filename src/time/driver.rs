@@ -0,0 +1,261 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex, OnceLock};
+use std::task::Waker;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::time::clock::Clock;
+
+// タイマーの登録キー：期限が同じでも区別できるようにidを付ける
+pub(crate) type TimerKey = (Instant, u64);
+
+// 期限順にWakerを保持し、期限が来たものを起こす
+// Engineごとのものとグローバルなものは専用スレッドが起こし、シミュレーション用のものは呼び出し側が進める
+pub(crate) struct Driver {
+    state: Mutex<State>,
+    cond: Condvar,
+    clock: Clock,
+    shutdown: AtomicBool,
+    // 時計を止めている間、全てのタスクが待機中かどうかを答える。trueなら次の期限まで時計を進める
+    idle_check: OnceLock<Box<dyn Fn() -> bool + Send + Sync>>,
+    // spawn_blockingで実行中の処理の数。Workerが眠っていても、終わるまでは時計を進めない
+    blocking: AtomicUsize,
+}
+
+struct State {
+    timers: BTreeMap<TimerKey, Waker>,
+    next_id: u64,
+}
+
+static DRIVER: OnceLock<Arc<Driver>> = OnceLock::new();
+
+thread_local! {
+    // Noneの場合はグローバルなDriverを使う
+    static CURRENT: RefCell<Option<Arc<Driver>>> = const { RefCell::new(None) };
+}
+
+impl Driver {
+    // スレッドを持たないDriver。期限の到来はfire_expiredで処理する
+    pub(crate) fn new(clock: Clock) -> Self {
+        Self {
+            state: Mutex::new(State {
+                timers: BTreeMap::new(),
+                next_id: 0,
+            }),
+            cond: Condvar::new(),
+            clock,
+            shutdown: AtomicBool::new(false),
+            idle_check: OnceLock::new(),
+            blocking: AtomicUsize::new(0),
+        }
+    }
+
+    // 専用スレッドで期限を待つDriver
+    pub(crate) fn spawn(clock: Clock) -> Arc<Driver> {
+        let driver = Arc::new(Driver::new(clock));
+        let cloned = driver.clone();
+        thread::Builder::new()
+            .name("async-runtime-timer".to_string())
+            .spawn(move || cloned.run())
+            .expect("failed to spawn timer thread");
+        driver
+    }
+
+    // Engineの外で作られたタイマーが使う
+    pub(crate) fn global() -> &'static Arc<Driver> {
+        DRIVER.get_or_init(|| Driver::spawn(Clock::real()))
+    }
+
+    pub(crate) fn is_global(self: &Arc<Self>) -> bool {
+        DRIVER.get().is_some_and(|global| Arc::ptr_eq(global, self))
+    }
+
+    pub(crate) fn set_idle_check(&self, idle_check: impl Fn() -> bool + Send + Sync + 'static) {
+        let _ = self.idle_check.set(Box::new(idle_check));
+    }
+
+    // enter()の範囲内ならそのDriver、それ以外はグローバルなDriver
+    pub(crate) fn current() -> Arc<Driver> {
+        Self::with_current(Arc::clone)
+    }
+
+    pub(crate) fn with_current<R>(f: impl FnOnce(&Arc<Driver>) -> R) -> R {
+        CURRENT.with(|current| match &*current.borrow() {
+            Some(driver) => f(driver),
+            None => f(Self::global()),
+        })
+    }
+
+    pub(crate) fn enter(self: &Arc<Self>) -> EnterGuard {
+        let prev = CURRENT.with(|current| current.borrow_mut().replace(self.clone()));
+        EnterGuard { prev }
+    }
+
+    pub(crate) fn now(&self) -> Instant {
+        self.clock.now()
+    }
+
+    pub(crate) fn clock(&self) -> &Clock {
+        &self.clock
+    }
+
+    // keyがあれば登録済みのタイマーを置き換える
+    pub(crate) fn register(
+        &self,
+        key: Option<TimerKey>,
+        deadline: Instant,
+        waker: &Waker,
+    ) -> TimerKey {
+        let mut state = self.state.lock().unwrap();
+        if let Some(key) = key {
+            if key.0 == deadline {
+                if let Some(registered) = state.timers.get_mut(&key) {
+                    registered.clone_from(waker);
+                    return key;
+                }
+            } else {
+                state.timers.remove(&key);
+            }
+        }
+
+        let key = (deadline, state.next_id);
+        state.next_id += 1;
+        let is_earliest = state.timers.first_key_value().is_none_or(|(k, _)| key < *k);
+        state.timers.insert(key, waker.clone());
+        if is_earliest || self.clock.is_paused() {
+            // 待機時間が短くなるか、時計を進められるかもしれないのでタイマースレッドを起こす
+            self.cond.notify_one();
+        }
+        key
+    }
+
+    pub(crate) fn cancel(&self, key: TimerKey) {
+        self.state.lock().unwrap().timers.remove(&key);
+    }
+
+    // 登録済みのタイマーを全て捨てる。Wakerの破棄でタスクが解放されるので、ロックの外で破棄する
+    pub(crate) fn clear(&self) {
+        let timers = std::mem::take(&mut self.state.lock().unwrap().timers);
+        drop(timers);
+    }
+
+    // 一番早い期限
+    pub(crate) fn next_deadline(&self) -> Option<Instant> {
+        let state = self.state.lock().unwrap();
+        state
+            .timers
+            .first_key_value()
+            .map(|((deadline, _), _)| *deadline)
+    }
+
+    // 現在時刻までに期限が来たタイマーを期限順に起こし、その数を返す
+    pub(crate) fn fire_expired(&self) -> usize {
+        let expired = {
+            let mut state = self.state.lock().unwrap();
+            take_expired(&mut state, self.clock.now())
+        };
+        let fired = expired.len();
+        expired.into_iter().for_each(Waker::wake);
+        fired
+    }
+
+    // 止めた時計を進め、期限が来たタイマーをその場で起こす
+    pub(crate) fn advance(&self, duration: Duration) {
+        self.clock.advance(duration);
+        self.fire_expired();
+        self.notify();
+    }
+
+    // 全てのWorkerが眠った。時計を止めていれば、進められるか確かめさせる
+    pub(crate) fn notify_idle(&self) {
+        if self.clock.is_paused() {
+            self.notify();
+        }
+    }
+
+    pub(crate) fn blocking_started(&self) {
+        self.blocking.fetch_add(1, Ordering::SeqCst);
+    }
+
+    // 最後の1つが終わったら、進められるか確かめさせる
+    pub(crate) fn blocking_finished(&self) {
+        if self.blocking.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.notify_idle();
+        }
+    }
+
+    pub(crate) fn shutdown(&self) {
+        self.shutdown.store(true, Ordering::Release);
+        self.notify();
+    }
+
+    // ロックを取ってから起こし、タイマースレッドが判定してから待つまでの間に取りこぼさない
+    fn notify(&self) {
+        let _state = self.state.lock().unwrap();
+        self.cond.notify_one();
+    }
+
+    fn is_idle(&self) -> bool {
+        self.blocking.load(Ordering::SeqCst) == 0
+            && self.idle_check.get().is_some_and(|idle_check| idle_check())
+    }
+
+    fn run(&self) {
+        let mut state = self.state.lock().unwrap();
+        loop {
+            if self.shutdown.load(Ordering::Acquire) {
+                return;
+            }
+            let now = self.clock.now();
+            let expired = take_expired(&mut state, now);
+
+            if !expired.is_empty() {
+                // ロックを解放してから起こす
+                drop(state);
+                expired.into_iter().for_each(Waker::wake);
+                state = self.state.lock().unwrap();
+                continue;
+            }
+
+            state = match state.timers.first_key_value() {
+                Some(((deadline, _), _)) if self.clock.is_paused() => {
+                    // 止めた時計は、誰も動いていないときだけ次の期限まで進める
+                    if self.is_idle() {
+                        self.clock.advance_to(*deadline);
+                        continue;
+                    }
+                    self.cond.wait(state).unwrap()
+                }
+                Some(((deadline, _), _)) => {
+                    let timeout = deadline.saturating_duration_since(now);
+                    self.cond.wait_timeout(state, timeout).unwrap().0
+                }
+                None => self.cond.wait(state).unwrap(),
+            };
+        }
+    }
+}
+
+fn take_expired(state: &mut State, now: Instant) -> Vec<Waker> {
+    let mut expired = Vec::new();
+    while let Some(entry) = state.timers.first_entry() {
+        if entry.key().0 > now {
+            break;
+        }
+        expired.push(entry.remove());
+    }
+    expired
+}
+
+pub(crate) struct EnterGuard {
+    prev: Option<Arc<Driver>>,
+}
+
+impl Drop for EnterGuard {
+    fn drop(&mut self) {
+        let prev = self.prev.take();
+        CURRENT.with(|current| *current.borrow_mut() = prev);
+    }
+}
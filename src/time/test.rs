@@ -0,0 +1,148 @@
+use super::{advance, interval, now, pause, sleep, timeout};
+use crate::engine::schedule::fifo::Fifo;
+use crate::engine::{Engine, Simulation, block_on};
+use crate::utils::stream::StreamExt;
+
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+#[test]
+fn sleep_waits_for_duration() {
+    let start = Instant::now();
+    block_on(sleep(Duration::from_millis(20)));
+    assert!(start.elapsed() >= Duration::from_millis(20));
+}
+
+#[test]
+fn earlier_sleep_completes_first() {
+    let start = Instant::now();
+    block_on(async {
+        let long = sleep(Duration::from_millis(100));
+        sleep(Duration::from_millis(10)).await;
+        drop(long);
+    });
+    assert!(start.elapsed() < Duration::from_millis(100));
+}
+
+#[test]
+fn interval_ticks_periodically() {
+    let start = Instant::now();
+    let ticks = block_on(async {
+        let mut ticks = Vec::new();
+        let mut interval = interval(Duration::from_millis(10));
+        while ticks.len() < 3 {
+            ticks.push(interval.next().await.unwrap());
+        }
+        ticks
+    });
+
+    // 最初のtickは即座に完了する
+    assert!(ticks[0] - start < Duration::from_millis(10));
+    assert!(ticks[1] - ticks[0] >= Duration::from_millis(10));
+    assert!(ticks[2] - ticks[1] >= Duration::from_millis(10));
+}
+
+#[test]
+fn timeout_returns_output_before_deadline() {
+    let res = block_on(timeout(Duration::from_millis(50), async { 42 }));
+    assert_eq!(res, Ok(42));
+}
+
+#[test]
+fn timeout_elapses() {
+    let res = block_on(timeout(
+        Duration::from_millis(10),
+        sleep(Duration::from_secs(10)),
+    ));
+    assert!(res.is_err());
+}
+
+// 時計を止めたEngineの上でfutureを実行し、結果と実時間の経過を返す
+fn run_paused<F, T>(future: F) -> (T, Duration)
+where
+    F: Future<Output = T> + Send + 'static,
+    T: Send + 'static,
+{
+    let engine = Engine::new(2, Fifo::new());
+    let started = Instant::now();
+    let res = {
+        let _guard = engine.handle().enter();
+        pause();
+        block_on(engine.spawn(future)).unwrap()
+    };
+    engine.graceful_shutdown();
+    (res, started.elapsed())
+}
+
+#[test]
+fn paused_sleep_auto_advances() {
+    let (elapsed, real) = run_paused(async {
+        let start = now();
+        sleep(Duration::from_secs(3600)).await;
+        now() - start
+    });
+    assert_eq!(elapsed, Duration::from_secs(3600));
+    assert!(real < Duration::from_secs(1));
+}
+
+#[test]
+fn paused_timeout_elapses_exactly() {
+    let ((res, elapsed), _) = run_paused(async {
+        let start = now();
+        let res = timeout(Duration::from_millis(250), std::future::pending::<()>()).await;
+        (res, now() - start)
+    });
+    assert!(res.is_err());
+    assert_eq!(elapsed, Duration::from_millis(250));
+}
+
+#[test]
+fn paused_interval_ticks_by_period() {
+    let (ticks, _) = run_paused(async {
+        let mut interval = interval(Duration::from_secs(60));
+        let mut ticks = Vec::new();
+        while ticks.len() < 3 {
+            ticks.push(interval.next().await.unwrap());
+        }
+        ticks
+    });
+    assert_eq!(ticks[1] - ticks[0], Duration::from_secs(60));
+    assert_eq!(ticks[2] - ticks[1], Duration::from_secs(60));
+}
+
+#[test]
+fn advance_moves_paused_clock() {
+    let engine = Engine::new(1, Fifo::new());
+    {
+        let _guard = engine.handle().enter();
+        pause();
+        let start = now();
+        advance(Duration::from_secs(5));
+        assert_eq!(now() - start, Duration::from_secs(5));
+    }
+    engine.graceful_shutdown();
+}
+
+#[test]
+fn advance_in_simulation() {
+    let elapsed = Simulation::new(0).block_on(async {
+        let start = now();
+        advance(Duration::from_secs(5));
+        now() - start
+    });
+    assert_eq!(elapsed, Duration::from_secs(5));
+}
+
+#[test]
+#[should_panic(expected = "within an Engine or Simulation")]
+fn pause_outside_engine_panics() {
+    pause();
+}
+
+#[test]
+#[should_panic(expected = "must be paused")]
+fn advance_without_pause_panics() {
+    let engine = Engine::new(1, Fifo::new());
+    let _guard = engine.handle().enter();
+    advance(Duration::from_secs(1));
+}
@@ -1 +1,3 @@
 pub mod channel;
+pub mod mpsc;
+pub mod stream;
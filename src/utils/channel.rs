@@ -1,11 +1,13 @@
 pub use std::future::Future;
 pub use std::pin::Pin;
 use std::sync::Arc;
-use std::sync::Mutex;
 use std::task::Waker;
 pub use std::task::{Context, Poll};
 
-pub use std::sync::mpsc;
+use crate::engine::coop;
+use crate::engine::join;
+use crate::engine::task::JoinError;
+use crate::loom::sync::{Mutex, mpsc};
 
 pub type Channel<T> = (Sender<T>, Receiver<T>);
 pub fn channel<T>() -> Channel<T>
@@ -33,16 +35,17 @@ where
     T: Clone,
 {
     fn new(sender: mpsc::Sender<T>, context: SharedInnerContext) -> Self {
-        Self {
-            sender: sender,
-            context: context,
-        }
+        Self { sender, context }
     }
 
     pub fn send(self, val: T) {
         let _ = self.sender.send(val.clone());
+        self.finish(InnerState::Ready);
+    }
+
+    fn finish(&self, state: InnerState) {
         let mut context = self.context.lock().unwrap();
-        context.set_state(InnerState::Ready);
+        context.set_state(state);
         if let Some(waker) = context.waker.take() {
             eprintln!("[Sender] Calling waker to wake up receiver!");
             waker.wake();
@@ -50,6 +53,25 @@ where
     }
 }
 
+impl<T> Drop for Sender<T>
+where
+    T: Clone,
+{
+    // 送らずに破棄された（タスクが破棄された）ら、Receiverを待たせたままにしない
+    // Engineの停止で破棄されたならErr(Shutdown)、abortなどならErr(Cancelled)を受け取らせる
+    fn drop(&mut self) {
+        let pending = matches!(self.context.lock().unwrap().state, InnerState::Pending);
+        if pending {
+            let error = if join::is_shutting_down() {
+                JoinError::Shutdown
+            } else {
+                JoinError::Cancelled
+            };
+            self.finish(InnerState::Closed(error));
+        }
+    }
+}
+
 pub struct Receiver<T>
 where
     T: Clone,
@@ -64,12 +86,12 @@ where
 {
     fn new(receiver: mpsc::Receiver<T>, shared_context: SharedInnerContext) -> Self {
         Self {
-            receiver: receiver,
+            receiver,
             context: shared_context,
         }
     }
 
-    pub fn set_state(&mut self, state: InnerState) -> () {
+    pub fn set_state(&mut self, state: InnerState) {
         self.context.lock().unwrap().set_state(state)
     }
 }
@@ -78,9 +100,12 @@ impl<T> Future for Receiver<T>
 where
     T: Clone,
 {
-    type Output = T;
+    type Output = Result<T, JoinError>;
 
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if coop::poll_proceed(cx).is_pending() {
+            return Poll::Pending;
+        }
         let mut context = self.context.lock().unwrap();
         match context.state {
             InnerState::Pending => {
@@ -90,7 +115,8 @@ where
                 }
                 Poll::Pending
             }
-            InnerState::Ready => Poll::Ready(self.receiver.recv().unwrap()),
+            InnerState::Ready => Poll::Ready(Ok(self.receiver.recv().unwrap())),
+            InnerState::Closed(error) => Poll::Ready(Err(error)),
         }
     }
 }
@@ -120,7 +146,12 @@ impl InnerContext {
 pub enum InnerState {
     Pending,
     Ready,
+    // 値を送らずにSenderが破棄された
+    Closed(JoinError),
 }
 
-#[cfg(test)]
+#[cfg(all(test, not(loom)))]
 mod test;
+
+#[cfg(all(test, loom))]
+mod model;
@@ -0,0 +1,40 @@
+use std::pin::pin;
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake, Waker};
+
+use loom::sync::atomic::{AtomicBool, Ordering};
+use loom::thread;
+
+use super::channel;
+
+struct FlagWaker(AtomicBool);
+
+impl Wake for FlagWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn send_racing_poll_wakes_receiver() {
+    loom::model(|| {
+        let (sender, receiver) = channel::<i64>();
+        let sender = thread::spawn(move || sender.send(42));
+
+        let flag = Arc::new(FlagWaker(AtomicBool::new(false)));
+        let waker = Waker::from(flag.clone());
+        let mut context = Context::from_waker(&waker);
+        let mut receiver = pin!(receiver);
+
+        let res = receiver.as_mut().poll(&mut context);
+        sender.join().unwrap();
+
+        // Pendingを返したなら、送信側が必ずWakerを呼んでいる
+        if res.is_pending() {
+            assert!(flag.0.load(Ordering::SeqCst));
+            assert_eq!(receiver.as_mut().poll(&mut context), Poll::Ready(Ok(42)));
+        } else {
+            assert_eq!(res, Poll::Ready(Ok(42)));
+        }
+    });
+}
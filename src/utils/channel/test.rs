@@ -1,3 +1,5 @@
+use crate::engine::join;
+use crate::engine::task::JoinError;
 use crate::utils::channel::{InnerContext, Sender};
 
 use super::channel;
@@ -32,7 +34,7 @@ fn sender_call_waker() {
 
 #[test]
 fn receiver_return_pending() {
-    let (_, receiver) = channel::<()>();
+    let (_sender, receiver) = channel::<()>();
 
     let mut receiver = pin!(receiver);
     let mut context = Context::from_waker(Waker::noop());
@@ -53,5 +55,34 @@ fn receiver_return_ready() {
 
     let receiver = receiver.as_mut();
     let res = receiver.poll(&mut context);
-    assert_eq!(res, Poll::Ready(42));
+    assert_eq!(res, Poll::Ready(Ok(42)));
+}
+
+#[test]
+fn receiver_errors_when_sender_dropped() {
+    let (sender, receiver) = channel::<i64>();
+
+    let mut receiver = pin!(receiver);
+    let mut context = Context::from_waker(Waker::noop());
+    assert_eq!(receiver.as_mut().poll(&mut context), Poll::Pending);
+
+    drop(sender);
+    assert_eq!(
+        receiver.as_mut().poll(&mut context),
+        Poll::Ready(Err(JoinError::Cancelled))
+    );
+}
+
+#[test]
+fn receiver_reports_shutdown_when_dropped_by_engine() {
+    let (sender, receiver) = channel::<i64>();
+
+    let mut receiver = pin!(receiver);
+    let mut context = Context::from_waker(Waker::noop());
+
+    join::shutting_down(|| drop(sender));
+    assert_eq!(
+        receiver.as_mut().poll(&mut context),
+        Poll::Ready(Err(JoinError::Shutdown))
+    );
 }
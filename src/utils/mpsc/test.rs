@@ -0,0 +1,46 @@
+use super::{SendError, TryRecvError, channel};
+use crate::engine::block_on;
+use crate::utils::stream::StreamExt;
+
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn receive_values_in_send_order() {
+    let (sender, mut receiver) = channel();
+
+    sender.send(1).unwrap();
+    sender.send(2).unwrap();
+    sender.send(3).unwrap();
+
+    assert_eq!(receiver.try_recv(), Ok(1));
+    assert_eq!(block_on(receiver.recv()), Some(2));
+    assert_eq!(block_on(receiver.next()), Some(3));
+    assert_eq!(receiver.try_recv(), Err(TryRecvError::Empty));
+}
+
+#[test]
+fn receiver_returns_none_after_all_senders_dropped() {
+    let (sender, mut receiver) = channel();
+    let cloned = sender.clone();
+
+    thread::spawn(move || {
+        thread::sleep(Duration::from_millis(10));
+        cloned.send(42).unwrap();
+        drop(cloned);
+        drop(sender);
+    });
+
+    assert_eq!(block_on(receiver.recv()), Some(42));
+    assert_eq!(block_on(receiver.recv()), None);
+    assert_eq!(receiver.try_recv(), Err(TryRecvError::Disconnected));
+}
+
+#[test]
+fn send_fails_after_receiver_dropped() {
+    let (sender, receiver) = channel();
+    drop(receiver);
+
+    assert!(sender.is_closed());
+    assert_eq!(sender.send(1), Err(SendError(1)));
+}
@@ -0,0 +1,167 @@
+mod adapter;
+mod buffer;
+mod timed;
+
+pub use adapter::{Filter, Map, Merge, TakeUntil, Then};
+pub use buffer::BufferUnordered;
+pub use timed::{ChunksTimeout, Throttle};
+
+use std::future::Future;
+use std::ops::DerefMut;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+// Futureの複数値版：Noneを返したら終端
+pub trait Stream {
+    type Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>>;
+}
+
+impl<S> Stream for &mut S
+where
+    S: Stream + Unpin + ?Sized,
+{
+    type Item = S::Item;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut **self).poll_next(cx)
+    }
+}
+
+impl<S> Stream for Box<S>
+where
+    S: Stream + Unpin + ?Sized,
+{
+    type Item = S::Item;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut **self).poll_next(cx)
+    }
+}
+
+impl<P> Stream for Pin<P>
+where
+    P: DerefMut<Target: Stream>,
+{
+    type Item = <P::Target as Stream>::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.as_deref_mut().poll_next(cx)
+    }
+}
+
+// コンビネータは内部のStreamをPin::newで扱うため、Unpinを要求する
+// !UnpinなStreamはBox::pinしてから使う
+pub trait StreamExt: Stream {
+    fn next(&mut self) -> Next<'_, Self>
+    where
+        Self: Unpin,
+    {
+        Next { stream: self }
+    }
+
+    fn map<T, F>(self, f: F) -> Map<Self, F>
+    where
+        Self: Sized + Unpin,
+        F: FnMut(Self::Item) -> T,
+    {
+        Map::new(self, f)
+    }
+
+    fn filter<F>(self, f: F) -> Filter<Self, F>
+    where
+        Self: Sized + Unpin,
+        F: FnMut(&Self::Item) -> bool,
+    {
+        Filter::new(self, f)
+    }
+
+    fn then<Fut, F>(self, f: F) -> Then<Self, Fut, F>
+    where
+        Self: Sized + Unpin,
+        F: FnMut(Self::Item) -> Fut,
+        Fut: Future,
+    {
+        Then::new(self, f)
+    }
+
+    fn buffer_unordered(self, limit: usize) -> BufferUnordered<Self>
+    where
+        Self: Sized + Unpin,
+        Self::Item: Future,
+    {
+        BufferUnordered::new(self, limit)
+    }
+
+    fn chunks_timeout(self, capacity: usize, duration: Duration) -> ChunksTimeout<Self>
+    where
+        Self: Sized + Unpin,
+    {
+        ChunksTimeout::new(self, capacity, duration)
+    }
+
+    fn throttle(self, duration: Duration) -> Throttle<Self>
+    where
+        Self: Sized + Unpin,
+    {
+        Throttle::new(self, duration)
+    }
+
+    fn merge<U>(self, other: U) -> Merge<Self, U>
+    where
+        Self: Sized + Unpin,
+        U: Stream<Item = Self::Item> + Unpin,
+    {
+        Merge::new(self, other)
+    }
+
+    fn take_until<Fut>(self, fut: Fut) -> TakeUntil<Self, Fut>
+    where
+        Self: Sized + Unpin,
+        Fut: Future,
+    {
+        TakeUntil::new(self, fut)
+    }
+}
+
+impl<S: Stream + ?Sized> StreamExt for S {}
+
+pub struct Next<'a, S: ?Sized> {
+    stream: &'a mut S,
+}
+
+impl<S> Future for Next<'_, S>
+where
+    S: Stream + Unpin + ?Sized,
+{
+    type Output = Option<S::Item>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut *self.stream).poll_next(cx)
+    }
+}
+
+pub fn iter<I: IntoIterator>(iter: I) -> Iter<I::IntoIter> {
+    Iter {
+        iter: iter.into_iter(),
+    }
+}
+
+pub struct Iter<I> {
+    iter: I,
+}
+
+impl<I> Unpin for Iter<I> {}
+
+impl<I: Iterator> Stream for Iter<I> {
+    type Item = I::Item;
+
+    fn poll_next(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<I::Item>> {
+        Poll::Ready(self.iter.next())
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod test;
@@ -0,0 +1,242 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use super::Stream;
+
+pub struct Map<S, F> {
+    stream: S,
+    f: F,
+}
+
+impl<S, F> Map<S, F> {
+    pub(super) fn new(stream: S, f: F) -> Self {
+        Self { stream, f }
+    }
+}
+
+// Fはピン留めして使わないので、Sに関係なくUnpinにできる
+impl<S: Unpin, F> Unpin for Map<S, F> {}
+
+impl<S, F, T> Stream for Map<S, F>
+where
+    S: Stream + Unpin,
+    F: FnMut(S::Item) -> T,
+{
+    type Item = T;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        let this = self.get_mut();
+        match Pin::new(&mut this.stream).poll_next(cx) {
+            Poll::Ready(Some(item)) => Poll::Ready(Some((this.f)(item))),
+            Poll::Ready(None) => Poll::Ready(None),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+pub struct Filter<S, F> {
+    stream: S,
+    f: F,
+}
+
+impl<S, F> Filter<S, F> {
+    pub(super) fn new(stream: S, f: F) -> Self {
+        Self { stream, f }
+    }
+}
+
+impl<S: Unpin, F> Unpin for Filter<S, F> {}
+
+impl<S, F> Stream for Filter<S, F>
+where
+    S: Stream + Unpin,
+    F: FnMut(&S::Item) -> bool,
+{
+    type Item = S::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<S::Item>> {
+        let this = self.get_mut();
+        loop {
+            match Pin::new(&mut this.stream).poll_next(cx) {
+                Poll::Ready(Some(item)) if (this.f)(&item) => return Poll::Ready(Some(item)),
+                Poll::Ready(Some(_)) => continue,
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+pub struct Then<S, Fut, F> {
+    stream: S,
+    f: F,
+    pending: Option<Pin<Box<Fut>>>,
+}
+
+impl<S, Fut, F> Then<S, Fut, F> {
+    pub(super) fn new(stream: S, f: F) -> Self {
+        Self {
+            stream,
+            f,
+            pending: None,
+        }
+    }
+}
+
+impl<S: Unpin, Fut, F> Unpin for Then<S, Fut, F> {}
+
+impl<S, Fut, F> Stream for Then<S, Fut, F>
+where
+    S: Stream + Unpin,
+    F: FnMut(S::Item) -> Fut,
+    Fut: Future,
+{
+    type Item = Fut::Output;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Fut::Output>> {
+        let this = self.get_mut();
+        loop {
+            if let Some(fut) = &mut this.pending {
+                let out = match fut.as_mut().poll(cx) {
+                    Poll::Ready(out) => out,
+                    Poll::Pending => return Poll::Pending,
+                };
+                this.pending = None;
+                return Poll::Ready(Some(out));
+            }
+
+            match Pin::new(&mut this.stream).poll_next(cx) {
+                Poll::Ready(Some(item)) => this.pending = Some(Box::pin((this.f)(item))),
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+pub struct Merge<S, U> {
+    first: Option<S>,
+    second: Option<U>,
+    // 片方に偏らないよう、最初にpollする側を毎回入れ替える
+    poll_second_first: bool,
+}
+
+impl<S, U> Merge<S, U> {
+    pub(super) fn new(first: S, second: U) -> Self {
+        Self {
+            first: Some(first),
+            second: Some(second),
+            poll_second_first: false,
+        }
+    }
+}
+
+impl<S: Unpin, U: Unpin> Unpin for Merge<S, U> {}
+
+impl<S, U> Merge<S, U>
+where
+    S: Stream + Unpin,
+    U: Stream<Item = S::Item> + Unpin,
+{
+    fn poll_first(&mut self, cx: &mut Context<'_>) -> Poll<Option<S::Item>> {
+        let Some(stream) = &mut self.first else {
+            return Poll::Ready(None);
+        };
+        let res = Pin::new(stream).poll_next(cx);
+        if let Poll::Ready(None) = res {
+            self.first = None;
+        }
+        res
+    }
+
+    fn poll_second(&mut self, cx: &mut Context<'_>) -> Poll<Option<S::Item>> {
+        let Some(stream) = &mut self.second else {
+            return Poll::Ready(None);
+        };
+        let res = Pin::new(stream).poll_next(cx);
+        if let Poll::Ready(None) = res {
+            self.second = None;
+        }
+        res
+    }
+}
+
+impl<S, U> Stream for Merge<S, U>
+where
+    S: Stream + Unpin,
+    U: Stream<Item = S::Item> + Unpin,
+{
+    type Item = S::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<S::Item>> {
+        let this = self.get_mut();
+        this.poll_second_first = !this.poll_second_first;
+
+        // 先にpollした側が値を返したら、もう片方はpollしない（値を取りこぼすため）
+        let res = if this.poll_second_first {
+            match this.poll_second(cx) {
+                Poll::Ready(Some(item)) => return Poll::Ready(Some(item)),
+                _ => this.poll_first(cx),
+            }
+        } else {
+            match this.poll_first(cx) {
+                Poll::Ready(Some(item)) => return Poll::Ready(Some(item)),
+                _ => this.poll_second(cx),
+            }
+        };
+
+        match res {
+            Poll::Ready(Some(item)) => Poll::Ready(Some(item)),
+            _ if this.first.is_none() && this.second.is_none() => Poll::Ready(None),
+            _ => Poll::Pending,
+        }
+    }
+}
+
+pub struct TakeUntil<S, Fut> {
+    stream: S,
+    until: Option<Pin<Box<Fut>>>,
+    done: bool,
+}
+
+impl<S, Fut> TakeUntil<S, Fut> {
+    pub(super) fn new(stream: S, until: Fut) -> Self {
+        Self {
+            stream,
+            until: Some(Box::pin(until)),
+            done: false,
+        }
+    }
+}
+
+impl<S: Unpin, Fut> Unpin for TakeUntil<S, Fut> {}
+
+impl<S, Fut> Stream for TakeUntil<S, Fut>
+where
+    S: Stream + Unpin,
+    Fut: Future,
+{
+    type Item = S::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<S::Item>> {
+        let this = self.get_mut();
+        if this.done {
+            return Poll::Ready(None);
+        }
+
+        if let Some(until) = &mut this.until
+            && until.as_mut().poll(cx).is_ready()
+        {
+            this.until = None;
+            this.done = true;
+            return Poll::Ready(None);
+        }
+
+        let res = Pin::new(&mut this.stream).poll_next(cx);
+        if let Poll::Ready(None) = res {
+            this.done = true;
+        }
+        res
+    }
+}
@@ -0,0 +1,74 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use super::Stream;
+
+// 最大limit個のFutureを同時に進め、完了した順に結果を返す
+pub struct BufferUnordered<S>
+where
+    S: Stream,
+    S::Item: Future,
+{
+    stream: Option<S>,
+    in_flight: Vec<Pin<Box<S::Item>>>,
+    limit: usize,
+}
+
+impl<S> BufferUnordered<S>
+where
+    S: Stream,
+    S::Item: Future,
+{
+    pub(super) fn new(stream: S, limit: usize) -> Self {
+        Self {
+            stream: Some(stream),
+            in_flight: Vec::new(),
+            limit: limit.max(1),
+        }
+    }
+}
+
+impl<S> Unpin for BufferUnordered<S>
+where
+    S: Stream + Unpin,
+    S::Item: Future,
+{
+}
+
+impl<S> Stream for BufferUnordered<S>
+where
+    S: Stream + Unpin,
+    S::Item: Future,
+{
+    type Item = <S::Item as Future>::Output;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        // 枠が空いている分だけ上流から取り出す
+        while this.in_flight.len() < this.limit {
+            let Some(stream) = &mut this.stream else {
+                break;
+            };
+            match Pin::new(stream).poll_next(cx) {
+                Poll::Ready(Some(fut)) => this.in_flight.push(Box::pin(fut)),
+                Poll::Ready(None) => this.stream = None,
+                Poll::Pending => break,
+            }
+        }
+
+        for idx in 0..this.in_flight.len() {
+            if let Poll::Ready(out) = this.in_flight[idx].as_mut().poll(cx) {
+                drop(this.in_flight.swap_remove(idx));
+                return Poll::Ready(Some(out));
+            }
+        }
+
+        if this.stream.is_none() && this.in_flight.is_empty() {
+            Poll::Ready(None)
+        } else {
+            Poll::Pending
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use super::{StreamExt, iter};
+use crate::engine::block_on;
+use crate::time::sleep;
+use crate::utils::mpsc;
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+fn collect<S: StreamExt + Unpin>(mut stream: S) -> Vec<S::Item> {
+    block_on(async move {
+        let mut items = Vec::new();
+        while let Some(item) = stream.next().await {
+            items.push(item);
+        }
+        items
+    })
+}
+
+#[test]
+fn map_and_filter() {
+    let stream = iter(1..=6).filter(|v| v % 2 == 0).map(|v| v * 10);
+    assert_eq!(collect(stream), vec![20, 40, 60]);
+}
+
+#[test]
+fn then_awaits_each_future_in_order() {
+    let stream = iter(1..=3).then(|v| async move {
+        sleep(Duration::from_millis(5 * (4 - v))).await;
+        v
+    });
+    assert_eq!(collect(stream), vec![1, 2, 3]);
+}
+
+#[test]
+fn buffer_unordered_yields_in_completion_order() {
+    let stream = iter([30, 10, 20])
+        .map(|ms| async move {
+            sleep(Duration::from_millis(ms)).await;
+            ms
+        })
+        .buffer_unordered(3);
+    assert_eq!(collect(stream), vec![10, 20, 30]);
+}
+
+#[test]
+fn buffer_unordered_respects_limit() {
+    let start = Instant::now();
+    let stream = iter(0..4)
+        .map(|v| async move {
+            sleep(Duration::from_millis(20)).await;
+            v
+        })
+        .buffer_unordered(2);
+    let mut items = collect(stream);
+    items.sort();
+
+    assert_eq!(items, vec![0, 1, 2, 3]);
+    // 2つずつしか走らないので、2回分は待つことになる
+    assert!(start.elapsed() >= Duration::from_millis(40));
+}
+
+#[test]
+fn chunks_timeout_flushes_on_capacity_and_timeout() {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        for v in 0..3 {
+            sender.send(v).unwrap();
+        }
+        thread::sleep(Duration::from_millis(50));
+        sender.send(3).unwrap();
+    });
+
+    let chunks = collect(receiver.chunks_timeout(2, Duration::from_millis(20)));
+    assert_eq!(chunks, vec![vec![0, 1], vec![2], vec![3]]);
+}
+
+#[test]
+fn throttle_spaces_out_items() {
+    let start = Instant::now();
+    let items = collect(iter(0..3).throttle(Duration::from_millis(10)));
+
+    assert_eq!(items, vec![0, 1, 2]);
+    assert!(start.elapsed() >= Duration::from_millis(20));
+}
+
+#[test]
+fn merge_yields_items_from_both_streams() {
+    let mut items = collect(iter([1, 3, 5]).merge(iter([2, 4])));
+    items.sort();
+    assert_eq!(items, vec![1, 2, 3, 4, 5]);
+}
+
+#[test]
+fn take_until_stops_when_future_completes() {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        for v in 0.. {
+            if sender.send(v).is_err() {
+                break;
+            }
+            thread::sleep(Duration::from_millis(5));
+        }
+    });
+
+    let items = collect(receiver.take_until(sleep(Duration::from_millis(30))));
+    assert!(!items.is_empty());
+    assert!(items.windows(2).all(|w| w[0] + 1 == w[1]));
+}
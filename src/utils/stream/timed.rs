@@ -0,0 +1,115 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use super::Stream;
+use crate::time::{Sleep, sleep};
+
+// capacity個たまるか、最初の要素からdurationが経過したらまとめて返す
+pub struct ChunksTimeout<S: Stream> {
+    stream: Option<S>,
+    items: Vec<S::Item>,
+    capacity: usize,
+    duration: Duration,
+    sleep: Option<Sleep>,
+}
+
+impl<S: Stream> ChunksTimeout<S> {
+    pub(super) fn new(stream: S, capacity: usize, duration: Duration) -> Self {
+        assert!(capacity > 0, "chunk capacity must be non-zero");
+        Self {
+            stream: Some(stream),
+            items: Vec::with_capacity(capacity),
+            capacity,
+            duration,
+            sleep: None,
+        }
+    }
+
+    fn take_chunk(&mut self) -> Vec<S::Item> {
+        self.sleep = None;
+        std::mem::replace(&mut self.items, Vec::with_capacity(self.capacity))
+    }
+}
+
+impl<S: Stream + Unpin> Unpin for ChunksTimeout<S> {}
+
+impl<S: Stream + Unpin> Stream for ChunksTimeout<S> {
+    type Item = Vec<S::Item>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Vec<S::Item>>> {
+        let this = self.get_mut();
+
+        while let Some(stream) = &mut this.stream {
+            match Pin::new(stream).poll_next(cx) {
+                Poll::Ready(Some(item)) => {
+                    if this.items.is_empty() {
+                        this.sleep = Some(sleep(this.duration));
+                    }
+                    this.items.push(item);
+                    if this.items.len() >= this.capacity {
+                        return Poll::Ready(Some(this.take_chunk()));
+                    }
+                }
+                Poll::Ready(None) => this.stream = None,
+                Poll::Pending => break,
+            }
+        }
+
+        if this.stream.is_none() {
+            // 上流が終わったら残りを返してから終了する
+            if this.items.is_empty() {
+                return Poll::Ready(None);
+            }
+            return Poll::Ready(Some(this.take_chunk()));
+        }
+
+        if let Some(sleep) = &mut this.sleep
+            && Pin::new(sleep).poll(cx).is_ready()
+        {
+            return Poll::Ready(Some(this.take_chunk()));
+        }
+        Poll::Pending
+    }
+}
+
+// 要素を返した後、duration経過するまで次の要素を返さない
+pub struct Throttle<S> {
+    stream: S,
+    duration: Duration,
+    sleep: Option<Sleep>,
+}
+
+impl<S> Throttle<S> {
+    pub(super) fn new(stream: S, duration: Duration) -> Self {
+        Self {
+            stream,
+            duration,
+            sleep: None,
+        }
+    }
+}
+
+impl<S: Unpin> Unpin for Throttle<S> {}
+
+impl<S: Stream + Unpin> Stream for Throttle<S> {
+    type Item = S::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<S::Item>> {
+        let this = self.get_mut();
+
+        if let Some(sleep) = &mut this.sleep {
+            if Pin::new(sleep).poll(cx).is_pending() {
+                return Poll::Pending;
+            }
+            this.sleep = None;
+        }
+
+        let res = Pin::new(&mut this.stream).poll_next(cx);
+        if let Poll::Ready(Some(_)) = res {
+            this.sleep = Some(sleep(this.duration));
+        }
+        res
+    }
+}
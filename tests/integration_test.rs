@@ -1,3 +1,5 @@
+#![cfg(not(loom))]
+
 use std::sync::{Arc, Mutex};
 use std::task::{Poll, Waker};
 use std::thread;
@@ -9,11 +11,11 @@ use async_runtime::engine::schedule::fifo::Fifo;
 
 #[test]
 fn engine_executes_simple_task() {
-    let mut engine = Engine::new(2, |receiver| Box::new(Fifo::new(receiver)));
+    let mut engine = Engine::new(2, Fifo::new());
 
     let receiver = engine.reserve(async { 1 + 1 }, None);
 
-    let result = block_on(receiver);
+    let result = block_on(receiver).unwrap();
     assert_eq!(result, 2);
 
     engine.graceful_shutdown();
@@ -21,22 +23,22 @@ fn engine_executes_simple_task() {
 
 #[test]
 fn engine_executes_multiple_tasks() {
-    let mut engine = Engine::new(4, |receiver| Box::new(Fifo::new(receiver)));
+    let mut engine = Engine::new(4, Fifo::new());
 
     let r1 = engine.reserve(async { 10 }, None);
     let r2 = engine.reserve(async { 20 }, None);
     let r3 = engine.reserve(async { 30 }, None);
 
-    assert_eq!(block_on(r1), 10);
-    assert_eq!(block_on(r2), 20);
-    assert_eq!(block_on(r3), 30);
+    assert_eq!(block_on(r1), Ok(10));
+    assert_eq!(block_on(r2), Ok(20));
+    assert_eq!(block_on(r3), Ok(30));
 
     engine.graceful_shutdown();
 }
 
 #[test]
 fn engine_executes_async_computation() {
-    let mut engine = Engine::new(2, |receiver| Box::new(Fifo::new(receiver)));
+    let mut engine = Engine::new(2, Fifo::new());
 
     let receiver = engine.reserve(
         async {
@@ -47,7 +49,7 @@ fn engine_executes_async_computation() {
         None,
     );
 
-    let result = block_on(receiver);
+    let result = block_on(receiver).unwrap();
     assert_eq!(result, 35);
 
     engine.graceful_shutdown();
@@ -55,11 +57,11 @@ fn engine_executes_async_computation() {
 
 #[test]
 fn engine_handles_string_results() {
-    let mut engine = Engine::new(2, |receiver| Box::new(Fifo::new(receiver)));
+    let mut engine = Engine::new(2, Fifo::new());
 
     let receiver = engine.reserve(async { "Hello, async runtime!".to_string() }, None);
 
-    let result = block_on(receiver);
+    let result = block_on(receiver).unwrap();
     assert_eq!(result, "Hello, async runtime!");
 
     engine.graceful_shutdown();
@@ -67,20 +69,20 @@ fn engine_handles_string_results() {
 
 #[test]
 fn engine_with_single_worker() {
-    let mut engine = Engine::new(1, |receiver| Box::new(Fifo::new(receiver)));
+    let mut engine = Engine::new(1, Fifo::new());
 
     let r1 = engine.reserve(async { 100 }, None);
     let r2 = engine.reserve(async { 200 }, None);
 
-    assert_eq!(block_on(r1), 100);
-    assert_eq!(block_on(r2), 200);
+    assert_eq!(block_on(r1), Ok(100));
+    assert_eq!(block_on(r2), Ok(200));
 
     engine.graceful_shutdown();
 }
 
 #[test]
 fn engine_with_many_workers() {
-    let mut engine = Engine::new(8, |receiver| Box::new(Fifo::new(receiver)));
+    let mut engine = Engine::new(8, Fifo::new());
 
     let mut receivers = vec![];
     for i in 0..10 {
@@ -89,7 +91,7 @@ fn engine_with_many_workers() {
     }
 
     for (i, receiver) in receivers.into_iter().enumerate() {
-        assert_eq!(block_on(receiver), i * 2);
+        assert_eq!(block_on(receiver), Ok(i * 2));
     }
 
     engine.graceful_shutdown();
@@ -143,13 +145,13 @@ impl std::future::Future for DummyFuture {
 
 #[test]
 fn engine_handles_pending_future() {
-    let mut engine = Engine::new(2, |receiver| Box::new(Fifo::new(receiver)));
+    let mut engine = Engine::new(2, Fifo::new());
 
     // DummyFuture::new(3) は cnt > 3 になるまでPoll::Pendingを返す
     // poll: cnt=0->1 (Pending), cnt=1->2 (Pending), cnt=2->3 (Pending), cnt=3->4 (Ready(4))
     let receiver = engine.reserve(DummyFuture::new(3), None);
 
-    let result = block_on(receiver);
+    let result = block_on(receiver).unwrap();
     assert_eq!(result, 4);
 
     engine.graceful_shutdown();
@@ -157,16 +159,16 @@ fn engine_handles_pending_future() {
 
 #[test]
 fn engine_handles_multiple_pending_futures() {
-    let mut engine = Engine::new(4, |receiver| Box::new(Fifo::new(receiver)));
+    let mut engine = Engine::new(4, Fifo::new());
 
     // 異なる回数のPoll::Pendingを返すFutureを複数実行
     let r1 = engine.reserve(DummyFuture::new(2), None); // 2回Pending後、Ready(3)
     let r2 = engine.reserve(DummyFuture::new(4), None); // 4回Pending後、Ready(5)
     let r3 = engine.reserve(DummyFuture::new(1), None); // 1回Pending後、Ready(2)
 
-    assert_eq!(block_on(r1), 3);
-    assert_eq!(block_on(r2), 5);
-    assert_eq!(block_on(r3), 2);
+    assert_eq!(block_on(r1), Ok(3));
+    assert_eq!(block_on(r2), Ok(5));
+    assert_eq!(block_on(r3), Ok(2));
 
     engine.graceful_shutdown();
 }
@@ -204,7 +206,7 @@ fn deadline_scheduler_orders_tasks_by_deadline() {
         }
     }
 
-    let mut engine = Engine::new(1, |receiver| Box::new(DeadLineScheduler::new(receiver)));
+    let mut engine = Engine::new(1, DeadLineScheduler::new());
 
     // 実行順序を記録
     let execution_order = Arc::new(Mutex::new(Vec::new()));
@@ -268,9 +270,9 @@ fn deadline_scheduler_orders_tasks_by_deadline() {
     }
 
     // 結果を取得
-    assert_eq!(block_on(r3), "A");
-    assert_eq!(block_on(r2), "C");
-    assert_eq!(block_on(r1), "B");
+    assert_eq!(block_on(r3), Ok("A"));
+    assert_eq!(block_on(r2), Ok("C"));
+    assert_eq!(block_on(r1), Ok("B"));
 
     // 実行順序を確認: A -> C -> B (deadline順)
     let order = execution_order.lock().unwrap();
@@ -283,7 +285,7 @@ fn deadline_scheduler_orders_tasks_by_deadline() {
 fn deadline_scheduler_handles_same_deadline() {
     use async_runtime::engine::schedule::deadline::DeadLineScheduler;
 
-    let mut engine = Engine::new(1, |receiver| Box::new(DeadLineScheduler::new(receiver)));
+    let mut engine = Engine::new(1, DeadLineScheduler::new());
 
     // 同じdeadlineのタスク
     let r1 = engine.reserve(async { 1 }, Some(1000));
@@ -291,9 +293,9 @@ fn deadline_scheduler_handles_same_deadline() {
     let r3 = engine.reserve(async { 3 }, Some(1000));
 
     // すべて完了すること
-    assert_eq!(block_on(r1), 1);
-    assert_eq!(block_on(r2), 2);
-    assert_eq!(block_on(r3), 3);
+    assert_eq!(block_on(r1), Ok(1));
+    assert_eq!(block_on(r2), Ok(2));
+    assert_eq!(block_on(r3), Ok(3));
 
     engine.graceful_shutdown();
 }
@@ -302,7 +304,7 @@ fn deadline_scheduler_handles_same_deadline() {
 fn deadline_scheduler_with_none_deadline() {
     use async_runtime::engine::schedule::deadline::DeadLineScheduler;
 
-    let mut engine = Engine::new(1, |receiver| Box::new(DeadLineScheduler::new(receiver)));
+    let mut engine = Engine::new(1, DeadLineScheduler::new());
 
     // deadline=Noneのタスク
     let r1 = engine.reserve(async { 100 }, None);
@@ -310,9 +312,188 @@ fn deadline_scheduler_with_none_deadline() {
     let r3 = engine.reserve(async { 300 }, None);
 
     // すべて完了すること
-    assert_eq!(block_on(r1), 100);
-    assert_eq!(block_on(r2), 200);
-    assert_eq!(block_on(r3), 300);
+    assert_eq!(block_on(r1), Ok(100));
+    assert_eq!(block_on(r2), Ok(200));
+    assert_eq!(block_on(r3), Ok(300));
+
+    engine.graceful_shutdown();
+}
+
+#[test]
+fn join_set_spawned_from_inside_task() {
+    use async_runtime::engine::JoinSet;
+
+    let mut engine = Engine::new(4, Fifo::new());
+
+    // タスクの中ではHandle::current()経由でJoinSetに登録できる
+    let receiver = engine.reserve(
+        async {
+            let mut set = JoinSet::new();
+            for i in 1..=5 {
+                set.spawn(async move { i * 10 });
+            }
+            let mut sum = 0;
+            while let Some(res) = set.join_next().await {
+                sum += res.unwrap();
+            }
+            sum
+        },
+        None,
+    );
+
+    assert_eq!(block_on(receiver), Ok(150));
 
     engine.graceful_shutdown();
 }
+
+#[test]
+fn priority_scheduler_runs_higher_priority_first() {
+    use async_runtime::engine::schedule::priority::{Priority, PriorityScheduler};
+
+    let mut engine = Engine::new(1, PriorityScheduler::new().with_aging(None));
+
+    let order = Arc::new(Mutex::new(Vec::new()));
+
+    // 唯一のWorkerを塞いでいる間に、優先度の異なるタスクを登録する
+    let blocker = engine.reserve_with_priority(
+        async {
+            thread::sleep(Duration::from_millis(50));
+        },
+        Priority::Critical,
+    );
+    thread::sleep(Duration::from_millis(10));
+
+    let mut receivers = vec![];
+    for priority in [Priority::Background, Priority::Normal, Priority::Critical] {
+        let order = order.clone();
+        receivers.push(engine.reserve_with_priority(
+            async move {
+                order.lock().unwrap().push(priority);
+            },
+            priority,
+        ));
+    }
+
+    block_on(blocker).unwrap();
+    receivers
+        .into_iter()
+        .for_each(|receiver| block_on(receiver).unwrap());
+
+    assert_eq!(
+        *order.lock().unwrap(),
+        vec![Priority::Critical, Priority::Normal, Priority::Background]
+    );
+
+    engine.graceful_shutdown();
+}
+
+#[test]
+fn mlfq_scheduler_exposes_queue_levels() {
+    use async_runtime::engine::schedule::mlfq::Mlfq;
+
+    let mut engine = Engine::new(2, Mlfq::new().with_quantum(Duration::from_millis(1)));
+
+    // 自分で自分を起こし続ける計算の重いタスク
+    let heavy = engine.reserve(DummyFuture::new(5), None);
+    let light = engine.reserve(async { 1 }, None);
+
+    assert_eq!(block_on(light), Ok(1));
+    assert_eq!(block_on(heavy), Ok(6));
+    assert_eq!(engine.scheduler_metrics().queue_depths.len(), 3);
+
+    engine.graceful_shutdown();
+}
+
+#[async_runtime::test]
+async fn test_macro_runs_body_on_engine() {
+    let a = async_runtime::engine::spawn(async { 20 });
+    let b = async_runtime::engine::spawn(async { 22 });
+    assert_eq!(a.await.unwrap() + b.await.unwrap(), 42);
+}
+
+#[async_runtime::test(worker_threads = 4, scheduler = "priority")]
+async fn test_macro_accepts_worker_threads_and_scheduler() {
+    let handles: Vec<_> = (0..16)
+        .map(|i| async_runtime::engine::spawn(async move { i * 2 }))
+        .collect();
+    let mut sum = 0;
+    for handle in handles {
+        sum += handle.await.unwrap();
+    }
+    assert_eq!(sum, 240);
+}
+
+#[async_runtime::test(scheduler = async_runtime::engine::schedule::mlfq::Mlfq::new())]
+async fn test_macro_accepts_scheduler_expression() {
+    assert_eq!(async_runtime::engine::spawn(async { 1 }).await.unwrap(), 1);
+}
+
+#[async_runtime::test(start_paused = true)]
+async fn test_macro_start_paused_auto_advances() {
+    let start = async_runtime::time::now();
+    let started = std::time::Instant::now();
+    async_runtime::time::sleep(Duration::from_secs(3600)).await;
+
+    assert_eq!(
+        async_runtime::time::now() - start,
+        Duration::from_secs(3600)
+    );
+    assert!(started.elapsed() < Duration::from_secs(1));
+}
+
+#[async_runtime::test(start_paused = true)]
+async fn test_macro_start_paused_holds_clock_while_main_runs() {
+    let start = async_runtime::time::now();
+    let started = std::time::Instant::now();
+
+    // タイマーがあっても、メインのFutureが動いている間は時計を進めない
+    let res = async_runtime::time::timeout(Duration::from_secs(3600), async {
+        while started.elapsed() < Duration::from_millis(100) {
+            async_runtime::engine::yield_now().await;
+        }
+    })
+    .await;
+
+    assert!(res.is_ok());
+    assert_eq!(async_runtime::time::now(), start);
+}
+
+#[async_runtime::test(flavor = "simulation", seed = 7)]
+async fn test_macro_simulation_flavor() {
+    let start = async_runtime::time::now();
+    async_runtime::time::sleep(Duration::from_millis(250)).await;
+    assert_eq!(
+        async_runtime::time::now() - start,
+        Duration::from_millis(250)
+    );
+}
+
+#[async_runtime::test]
+async fn test_macro_supports_result_and_question_mark() -> Result<(), std::num::ParseIntError> {
+    let n: u32 = "42".parse()?;
+    assert_eq!(n, 42);
+    Ok(())
+}
+
+#[async_runtime::test]
+#[should_panic(expected = "boom")]
+async fn test_macro_propagates_panics() {
+    panic!("boom");
+}
+
+async_runtime::task_local! {
+    static TRACE_ID: u64;
+}
+
+#[async_runtime::test(worker_threads = 2)]
+async fn task_local_follows_spawned_task() {
+    let handle = async_runtime::engine::spawn(TRACE_ID.scope(42, async {
+        for _ in 0..10 {
+            async_runtime::engine::yield_now().await;
+            assert_eq!(TRACE_ID.get(), 42);
+        }
+        TRACE_ID.get()
+    }));
+    assert_eq!(handle.await, Ok(42));
+    assert!(TRACE_ID.try_with(|_| ()).is_err());
+}